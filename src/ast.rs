@@ -0,0 +1,508 @@
+//! Luau syntax trees.
+//!
+//! This module parses Luau source code using the bundled Luau parser and exposes the resulting
+//! syntax tree, together with the source comments. It is useful to write custom lint rules, to
+//! find `require` calls or to build other tooling for Luau scripts without running them.
+//!
+//! The tree is untyped: every [`Node`] has a [`NodeKind`], a [`Span`], optional text (such as a
+//! name or a constant value) and a list of children in source order.
+//!
+//! ```
+//! # use ulua::Result;
+//! # use ulua::ast::{Ast, NodeKind};
+//! # fn main() -> Result<()> {
+//! let ast = Ast::parse("local json = require('json')\nlocal utils = require('./utils')")?;
+//! let requires = (ast.nodes())
+//!     .filter(|node| node.kind() == NodeKind::ExprCall)
+//!     .filter(|call| call.children().next().and_then(|func| func.text()) == Some("require".into()))
+//!     .filter_map(|call| call.children().nth(1)?.text())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(requires, ["json", "./utils"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::Range;
+use std::os::raw::c_char;
+use std::{fmt, slice};
+
+use bstr::BStr;
+
+use crate::error::{Error, Result, SyntaxDiagnostic};
+
+macro_rules! node_kinds {
+    ($($(#[$meta:meta])* $name:ident = $val:expr;)*) => {
+        /// Kind of a syntax tree [`Node`].
+        ///
+        /// Kinds correspond to the node classes of the Luau parser.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum NodeKind {
+            $($(#[$meta])* $name = $val,)*
+        }
+
+        impl NodeKind {
+            fn from_raw(kind: i32) -> NodeKind {
+                match kind {
+                    $($val => NodeKind::$name,)*
+                    _ => NodeKind::Unknown,
+                }
+            }
+        }
+    };
+}
+
+// Must be kept in sync with `Kind` in `ulua-sys/src/luau/ast.cpp`
+node_kinds! {
+    /// A node not known to this version of the crate.
+    Unknown = 0;
+    /// A block of statements.
+    StatBlock = 1;
+    /// `if` statement.
+    StatIf = 2;
+    /// `while` loop.
+    StatWhile = 3;
+    /// `repeat ... until` loop.
+    StatRepeat = 4;
+    /// `break` statement.
+    StatBreak = 5;
+    /// `continue` statement.
+    StatContinue = 6;
+    /// `return` statement.
+    StatReturn = 7;
+    /// An expression (function call) used as a statement.
+    StatExpr = 8;
+    /// `local` statement. The declared variables are [`NodeKind::Local`] children.
+    StatLocal = 9;
+    /// Numeric `for` loop.
+    StatFor = 10;
+    /// Generic `for ... in` loop.
+    StatForIn = 11;
+    /// Assignment.
+    StatAssign = 12;
+    /// Compound assignment, such as `+=`. Text is the operator.
+    StatCompoundAssign = 13;
+    /// `function` statement.
+    StatFunction = 14;
+    /// `local function` statement.
+    StatLocalFunction = 15;
+    /// `type` alias. Text is the type name.
+    StatTypeAlias = 16;
+    /// `type function`. Text is the type function name.
+    StatTypeFunction = 17;
+    /// Global declaration (in definition files). Text is the global name.
+    StatDeclareGlobal = 18;
+    /// Function declaration (in definition files). Text is the function name.
+    StatDeclareFunction = 19;
+    /// Class declaration (in definition files). Text is the class name.
+    StatDeclareExternType = 20;
+    /// An invalid statement.
+    StatError = 21;
+    /// Parenthesized expression.
+    ExprGroup = 22;
+    /// `nil` constant.
+    ExprConstantNil = 23;
+    /// Boolean constant. Text is `true` or `false`.
+    ExprConstantBool = 24;
+    /// Number constant. Text is the number value.
+    ExprConstantNumber = 25;
+    /// String constant. Text is the string value.
+    ExprConstantString = 26;
+    /// Reference to a local variable. Text is the variable name.
+    ExprLocal = 27;
+    /// Reference to a global variable. Text is the variable name.
+    ExprGlobal = 28;
+    /// `...` expression.
+    ExprVarargs = 29;
+    /// Function call. The first child is the called function, the rest are arguments.
+    ExprCall = 30;
+    /// Field access, such as `a.b` or `a:b`. Text is the field name.
+    ExprIndexName = 31;
+    /// Index expression, such as `a[b]`.
+    ExprIndexExpr = 32;
+    /// Function expression. Text is the function name, if known.
+    ExprFunction = 33;
+    /// Table constructor.
+    ExprTable = 34;
+    /// Unary operation. Text is the operator.
+    ExprUnary = 35;
+    /// Binary operation. Text is the operator.
+    ExprBinary = 36;
+    /// Type assertion (`::`).
+    ExprTypeAssertion = 37;
+    /// `if ... then ... else` expression.
+    ExprIfElse = 38;
+    /// Interpolated string.
+    ExprInterpString = 39;
+    /// An invalid expression.
+    ExprError = 40;
+    /// Named type. Text is the type name, including the module prefix if present.
+    TypeReference = 41;
+    /// Table type.
+    TypeTable = 42;
+    /// Function type.
+    TypeFunction = 43;
+    /// `typeof` type.
+    TypeTypeof = 44;
+    /// `?` part of an optional type.
+    TypeOptional = 45;
+    /// Union type.
+    TypeUnion = 46;
+    /// Intersection type.
+    TypeIntersection = 47;
+    /// Boolean singleton type. Text is `true` or `false`.
+    TypeSingletonBool = 48;
+    /// String singleton type. Text is the string value.
+    TypeSingletonString = 49;
+    /// Parenthesized type.
+    TypeGroup = 50;
+    /// An invalid type.
+    TypeError = 51;
+    /// List of types.
+    TypePackExplicit = 52;
+    /// Variadic type pack, such as `...number`.
+    TypePackVariadic = 53;
+    /// Generic type pack, such as `T...`. Text is the generic name.
+    TypePackGeneric = 54;
+    /// Function attribute, such as `@native`. Text is the attribute name.
+    Attribute = 55;
+    /// Generic type parameter. Text is the parameter name.
+    GenericType = 56;
+    /// Generic type pack parameter. Text is the parameter name.
+    GenericTypePack = 57;
+    /// Declaration of a local variable or a function parameter. Text is the variable name.
+    ///
+    /// The type annotation, if any, is the only child.
+    Local = 58;
+}
+
+impl NodeKind {
+    /// Returns `true` if this is a statement.
+    pub fn is_statement(self) -> bool {
+        (NodeKind::StatBlock as i32..=NodeKind::StatError as i32).contains(&(self as i32))
+    }
+
+    /// Returns `true` if this is an expression.
+    pub fn is_expression(self) -> bool {
+        (NodeKind::ExprGroup as i32..=NodeKind::ExprError as i32).contains(&(self as i32))
+    }
+
+    /// Returns `true` if this is a type annotation (including type packs).
+    pub fn is_type(self) -> bool {
+        (NodeKind::TypeReference as i32..=NodeKind::TypePackGeneric as i32).contains(&(self as i32))
+    }
+}
+
+/// A position in the source code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Line number (1-based).
+    pub line: usize,
+    /// Column number (1-based, in bytes).
+    pub column: usize,
+}
+
+/// A range of the source code, with exclusive end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (start, end) = (self.start, self.end);
+        write!(f, "{}:{}-{}:{}", start.line, start.column, end.line, end.column)
+    }
+}
+
+/// Kind of a source [`Comment`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CommentKind {
+    /// `-- comment`
+    Line,
+    /// `--[[ comment ]]`
+    Block,
+    /// A block comment that is not terminated.
+    Unterminated,
+}
+
+/// A comment in the source code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub span: Span,
+}
+
+/// A parsed Luau syntax tree.
+///
+/// The tree owns a copy of the source code, which can be retrieved for any [`Span`] using
+/// [`Ast::source_text`].
+#[derive(Clone)]
+pub struct Ast {
+    source: Vec<u8>,
+    // Byte offset of each line start
+    lines: Vec<usize>,
+    // Nodes in pre-order, so every subtree is a contiguous range
+    nodes: Vec<NodeData>,
+    comments: Vec<Comment>,
+    text: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+struct NodeData {
+    kind: NodeKind,
+    span: Span,
+    parent: Option<usize>,
+    end: usize,
+    text: Option<Range<usize>>,
+}
+
+impl Ast {
+    /// Parses Luau source code into a syntax tree.
+    ///
+    /// Returns [`Error::SyntaxError`] with a diagnostic for each parse error if the source code is
+    /// not valid.
+    pub fn parse(source: impl AsRef<[u8]>) -> Result<Self> {
+        let source = source.as_ref();
+        let lines = (std::iter::once(0))
+            .chain(
+                source
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &b)| b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect::<Vec<_>>();
+
+        let result = unsafe { ffi::ulua_ast_parse(source.as_ptr() as *const c_char, source.len()) };
+        if result.is_null() {
+            return Err(Error::MemoryError("not enough memory".to_string()));
+        }
+        let result = ParseResult(result);
+        let result = unsafe { &*result.0 };
+
+        let mut ast = Ast {
+            source: source.to_vec(),
+            lines,
+            nodes: Vec::new(),
+            comments: Vec::new(),
+            text: unsafe { raw_slice(result.text as *const u8, result.text_len) }.to_vec(),
+        };
+
+        let errors = unsafe { raw_slice(result.errors, result.errors_len) };
+        if !errors.is_empty() {
+            return Err(ast.syntax_error(errors));
+        }
+
+        let nodes = unsafe { raw_slice(result.nodes, result.nodes_len) };
+        ast.nodes = (nodes.iter())
+            .map(|node| NodeData {
+                kind: NodeKind::from_raw(node.kind),
+                span: to_span(node.span),
+                parent: (node.parent != usize::MAX).then_some(node.parent),
+                end: node.end,
+                text: (node.has_text != 0).then(|| node.text_offset..node.text_offset + node.text_len),
+            })
+            .collect();
+        let comments = unsafe { raw_slice(result.comments, result.comments_len) };
+        ast.comments = (comments.iter())
+            .map(|comment| Comment {
+                kind: match comment.kind {
+                    0 => CommentKind::Line,
+                    1 => CommentKind::Block,
+                    _ => CommentKind::Unterminated,
+                },
+                span: to_span(comment.span),
+            })
+            .collect();
+        Ok(ast)
+    }
+
+    /// Returns the root node (a [`NodeKind::StatBlock`] with the top level statements).
+    pub fn root(&self) -> Node<'_> {
+        Node { ast: self, index: 0 }
+    }
+
+    /// Returns an iterator over all nodes of the tree in source order (pre-order).
+    pub fn nodes(&self) -> impl Iterator<Item = Node<'_>> + '_ {
+        (0..self.nodes.len()).map(move |index| Node { ast: self, index })
+    }
+
+    /// Returns the comments in the source code.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Returns the source code covered by the span.
+    pub fn source_text(&self, span: Span) -> &BStr {
+        let offset = |pos: Position| {
+            let line_start = self.lines.get(pos.line.wrapping_sub(1)).copied();
+            let offset = line_start.map_or(self.source.len(), |start| start + pos.column.saturating_sub(1));
+            offset.min(self.source.len())
+        };
+        let (start, end) = (offset(span.start), offset(span.end));
+        BStr::new(&self.source[start.min(end)..end])
+    }
+
+    fn syntax_error(&self, errors: &[ffi::ulua_AstError]) -> Error {
+        let diagnostics = (errors.iter())
+            .map(|error| {
+                let message = &self.text[error.message_offset..error.message_offset + error.message_len];
+                let span = to_span(error.span);
+                let start = self.char_column(span.start);
+                let end = match self.char_column(span.end) {
+                    end if span.end.line == span.start.line && end > start => end,
+                    _ => start + 1,
+                };
+                SyntaxDiagnostic {
+                    chunk_name: None,
+                    line: span.start.line,
+                    columns: Some(start..end),
+                    message: String::from_utf8_lossy(message).into_owned(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let first = &diagnostics[0];
+        Error::SyntaxError {
+            message: format!("{}: {}", first.line, first.message),
+            incomplete_input: first.message.ends_with("<eof>"),
+            diagnostics,
+        }
+    }
+
+    // Converts a byte column into a character column
+    fn char_column(&self, pos: Position) -> usize {
+        let line_start = Position {
+            line: pos.line,
+            column: 1,
+        };
+        let text = self.source_text(Span {
+            start: line_start,
+            end: pos,
+        });
+        text.iter().filter(|&&b| b & 0xc0 != 0x80).count() + 1
+    }
+}
+
+impl fmt::Debug for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Ast")
+            .field("root", &self.root())
+            .field("comments", &self.comments)
+            .finish()
+    }
+}
+
+/// A node of a syntax tree.
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    ast: &'a Ast,
+    index: usize,
+}
+
+impl<'a> Node<'a> {
+    /// Returns the node kind.
+    pub fn kind(&self) -> NodeKind {
+        self.data().kind
+    }
+
+    /// Returns the source span of the node.
+    pub fn span(&self) -> Span {
+        self.data().span
+    }
+
+    /// Returns the text associated with the node, such as a variable name or a constant value.
+    ///
+    /// See [`NodeKind`] for the text of each kind of node.
+    pub fn text(&self) -> Option<&'a BStr> {
+        let range = self.data().text.clone()?;
+        Some(BStr::new(&self.ast.text[range]))
+    }
+
+    /// Returns the source code of the node.
+    pub fn source_text(&self) -> &'a BStr {
+        self.ast.source_text(self.span())
+    }
+
+    /// Returns the parent node, or `None` for the root node.
+    pub fn parent(&self) -> Option<Node<'a>> {
+        let index = self.data().parent?;
+        Some(Node { ast: self.ast, index })
+    }
+
+    /// Returns an iterator over the direct children of the node, in source order.
+    pub fn children(&self) -> impl Iterator<Item = Node<'a>> + 'a {
+        let (ast, end) = (self.ast, self.data().end);
+        let mut next = self.index + 1;
+        std::iter::from_fn(move || {
+            if next >= end {
+                return None;
+            }
+            let node = Node { ast, index: next };
+            next = ast.nodes[next].end;
+            Some(node)
+        })
+    }
+
+    /// Returns an iterator over all descendants of the node in source order (pre-order).
+    pub fn descendants(&self) -> impl Iterator<Item = Node<'a>> + 'a {
+        let ast = self.ast;
+        (self.index + 1..self.data().end).map(move |index| Node { ast, index })
+    }
+
+    fn data(&self) -> &'a NodeData {
+        &self.ast.nodes[self.index]
+    }
+}
+
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("Node");
+        debug.field("kind", &self.kind()).field("span", &self.span());
+        if let Some(text) = self.text() {
+            debug.field("text", &text);
+        }
+        let children = self.children().collect::<Vec<_>>();
+        if !children.is_empty() {
+            debug.field("children", &children);
+        }
+        debug.finish()
+    }
+}
+
+impl PartialEq for Node<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.ast, other.ast) && self.index == other.index
+    }
+}
+
+impl Eq for Node<'_> {}
+
+fn to_span(span: ffi::ulua_AstSpan) -> Span {
+    // Luau positions are 0-based
+    let position = |line: u32, column: u32| Position {
+        line: line as usize + 1,
+        column: column as usize + 1,
+    };
+    Span {
+        start: position(span.begin_line, span.begin_column),
+        end: position(span.end_line, span.end_column),
+    }
+}
+
+unsafe fn raw_slice<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        return &[];
+    }
+    slice::from_raw_parts(data, len)
+}
+
+// Owns the parse result of the C++ shim
+struct ParseResult(*mut ffi::ulua_AstResult);
+
+impl Drop for ParseResult {
+    fn drop(&mut self) {
+        unsafe { ffi::ulua_ast_free(self.0) };
+    }
+}
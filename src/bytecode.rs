@@ -0,0 +1,960 @@
+//! Luau bytecode introspection.
+//!
+//! This module provides a structured, read-only view of the bytecode produced by
+//! [`Compiler::compile`], along with a textual disassembler. It is useful to verify what the
+//! compiler actually generated, for example that a library constant was folded or that a builtin
+//! call was turned into a fastcall.
+//!
+//! ```
+//! # use ulua::{Compiler, Result};
+//! # use ulua::bytecode::Bytecode;
+//! # fn main() -> Result<()> {
+//! let compiler = Compiler::new().set_optimization_level(2);
+//! let bytecode = Bytecode::parse(&compiler.compile("return math.floor(1.5)")?)?;
+//! println!("{bytecode}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Compiler::compile`]: crate::Compiler::compile
+
+use std::fmt;
+use std::string::String as StdString;
+
+use crate::error::{Error, Result};
+
+mod verify;
+
+/// Minimum bytecode version supported by the bundled Luau VM.
+pub const VERSION_MIN: u8 = 3;
+/// Maximum bytecode version supported by the bundled Luau VM.
+pub const VERSION_MAX: u8 = 6;
+/// Minimum type information version supported by the bundled Luau VM.
+pub const TYPES_VERSION_MIN: u8 = 1;
+/// Maximum type information version supported by the bundled Luau VM.
+pub const TYPES_VERSION_MAX: u8 = 3;
+
+/// Proto flag: the whole module was marked with `--!native`.
+pub const PROTO_NATIVE_MODULE: u8 = 1 << 0;
+/// Proto flag: the function is considered cold and should not be natively compiled.
+pub const PROTO_NATIVE_COLD: u8 = 1 << 1;
+/// Proto flag: the function was marked with `@native`.
+pub const PROTO_NATIVE_FUNCTION: u8 = 1 << 2;
+
+/// A parsed Luau bytecode blob.
+#[derive(Clone, Debug)]
+pub struct Bytecode {
+    /// Bytecode format version.
+    pub version: u8,
+    /// Type information format version (`0` for bytecode versions without type information).
+    pub types_version: u8,
+    /// String table, referenced by constants and debug information.
+    pub strings: Vec<StdString>,
+    /// All function prototypes, in the order they are stored in the blob.
+    pub protos: Vec<Proto>,
+    /// Index of the main (top level) prototype in [`Bytecode::protos`].
+    pub main: usize,
+}
+
+/// A function prototype.
+#[derive(Clone, Debug)]
+pub struct Proto {
+    /// Index of the prototype within [`Bytecode::protos`].
+    pub id: usize,
+    /// Maximum number of registers used by the function.
+    pub max_stack_size: u8,
+    /// Number of fixed parameters.
+    pub num_params: u8,
+    /// Number of upvalues.
+    pub num_upvalues: u8,
+    /// `true` if the function accepts a variable number of arguments.
+    pub is_vararg: bool,
+    /// Prototype flags (see [`PROTO_NATIVE_MODULE`] and friends).
+    pub flags: u8,
+    /// The line number where the function is defined.
+    pub line_defined: u32,
+    /// Function name, if known.
+    pub debug_name: Option<StdString>,
+    /// Constant table.
+    pub constants: Vec<Constant>,
+    /// Child prototypes (indices into [`Bytecode::protos`]).
+    pub children: Vec<usize>,
+    /// Decoded instructions.
+    pub instructions: Vec<Instruction>,
+    /// Local variables debug information (empty if compiled with `debug_level` < 2).
+    pub locals: Vec<LocalVar>,
+    /// Upvalue names (empty if compiled with `debug_level` < 2).
+    pub upvalue_names: Vec<StdString>,
+}
+
+/// A constant in a prototype constant table.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Constant {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    Vector([f32; 4]),
+    String(StdString),
+    /// An import path, such as `math.floor`.
+    Import(Vec<StdString>),
+    /// A table template with keys and optional values (as constant indices).
+    ///
+    /// Values are never set in parsed bytecode: the bundled Luau VM only supports templates
+    /// without values.
+    Table(Vec<(usize, Option<usize>)>),
+    /// A closure referencing a prototype (index into [`Bytecode::protos`]).
+    Closure(usize),
+}
+
+/// Local variable debug information.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalVar {
+    /// Variable name.
+    pub name: StdString,
+    /// First instruction where the variable is alive.
+    pub start_pc: usize,
+    /// First instruction where the variable is dead.
+    pub end_pc: usize,
+    /// Register holding the variable.
+    pub register: u8,
+}
+
+/// A single decoded instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// Position of the instruction in the code array (in 32-bit words).
+    pub pc: usize,
+    /// Operation code.
+    pub op: OpCode,
+    /// Raw instruction word.
+    pub word: u32,
+    /// Auxiliary word for instructions that have one.
+    pub aux: Option<u32>,
+    /// Source line, if line information is present.
+    pub line: Option<u32>,
+}
+
+/// A call site of an imported global (`GETIMPORT`) or a method call (`NAMECALL`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Site {
+    /// Index of the prototype containing the site.
+    pub proto: usize,
+    /// Position of the instruction.
+    pub pc: usize,
+    /// Source line, if line information is present.
+    pub line: Option<u32>,
+    /// Import path (`math.floor`) or method name.
+    pub name: StdString,
+}
+
+macro_rules! opcodes {
+    ($($name:ident = $val:expr, $str:expr, $aux:expr;)*) => {
+        /// Luau instruction opcode.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[non_exhaustive]
+        pub enum OpCode {
+            $($name = $val,)*
+        }
+
+        impl OpCode {
+            /// Converts raw opcode byte into [`OpCode`].
+            pub fn from_u8(op: u8) -> Option<OpCode> {
+                match op {
+                    $($val => Some(OpCode::$name),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the opcode name as used by the Luau disassembler.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(OpCode::$name => $str,)*
+                }
+            }
+
+            /// Returns `true` if the instruction is followed by an auxiliary word.
+            pub fn has_aux(self) -> bool {
+                match self {
+                    $(OpCode::$name => $aux,)*
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    NOP = 0, "NOP", false;
+    BREAK = 1, "BREAK", false;
+    LOADNIL = 2, "LOADNIL", false;
+    LOADB = 3, "LOADB", false;
+    LOADN = 4, "LOADN", false;
+    LOADK = 5, "LOADK", false;
+    MOVE = 6, "MOVE", false;
+    GETGLOBAL = 7, "GETGLOBAL", true;
+    SETGLOBAL = 8, "SETGLOBAL", true;
+    GETUPVAL = 9, "GETUPVAL", false;
+    SETUPVAL = 10, "SETUPVAL", false;
+    CLOSEUPVALS = 11, "CLOSEUPVALS", false;
+    GETIMPORT = 12, "GETIMPORT", true;
+    GETTABLE = 13, "GETTABLE", false;
+    SETTABLE = 14, "SETTABLE", false;
+    GETTABLEKS = 15, "GETTABLEKS", true;
+    SETTABLEKS = 16, "SETTABLEKS", true;
+    GETTABLEN = 17, "GETTABLEN", false;
+    SETTABLEN = 18, "SETTABLEN", false;
+    NEWCLOSURE = 19, "NEWCLOSURE", false;
+    NAMECALL = 20, "NAMECALL", true;
+    CALL = 21, "CALL", false;
+    RETURN = 22, "RETURN", false;
+    JUMP = 23, "JUMP", false;
+    JUMPBACK = 24, "JUMPBACK", false;
+    JUMPIF = 25, "JUMPIF", false;
+    JUMPIFNOT = 26, "JUMPIFNOT", false;
+    JUMPIFEQ = 27, "JUMPIFEQ", true;
+    JUMPIFLE = 28, "JUMPIFLE", true;
+    JUMPIFLT = 29, "JUMPIFLT", true;
+    JUMPIFNOTEQ = 30, "JUMPIFNOTEQ", true;
+    JUMPIFNOTLE = 31, "JUMPIFNOTLE", true;
+    JUMPIFNOTLT = 32, "JUMPIFNOTLT", true;
+    ADD = 33, "ADD", false;
+    SUB = 34, "SUB", false;
+    MUL = 35, "MUL", false;
+    DIV = 36, "DIV", false;
+    MOD = 37, "MOD", false;
+    POW = 38, "POW", false;
+    ADDK = 39, "ADDK", false;
+    SUBK = 40, "SUBK", false;
+    MULK = 41, "MULK", false;
+    DIVK = 42, "DIVK", false;
+    MODK = 43, "MODK", false;
+    POWK = 44, "POWK", false;
+    AND = 45, "AND", false;
+    OR = 46, "OR", false;
+    ANDK = 47, "ANDK", false;
+    ORK = 48, "ORK", false;
+    CONCAT = 49, "CONCAT", false;
+    NOT = 50, "NOT", false;
+    MINUS = 51, "MINUS", false;
+    LENGTH = 52, "LENGTH", false;
+    NEWTABLE = 53, "NEWTABLE", true;
+    DUPTABLE = 54, "DUPTABLE", false;
+    SETLIST = 55, "SETLIST", true;
+    FORNPREP = 56, "FORNPREP", false;
+    FORNLOOP = 57, "FORNLOOP", false;
+    FORGLOOP = 58, "FORGLOOP", true;
+    FORGPREP_INEXT = 59, "FORGPREP_INEXT", false;
+    FASTCALL3 = 60, "FASTCALL3", true;
+    FORGPREP_NEXT = 61, "FORGPREP_NEXT", false;
+    NATIVECALL = 62, "NATIVECALL", false;
+    GETVARARGS = 63, "GETVARARGS", false;
+    DUPCLOSURE = 64, "DUPCLOSURE", false;
+    PREPVARARGS = 65, "PREPVARARGS", false;
+    LOADKX = 66, "LOADKX", true;
+    JUMPX = 67, "JUMPX", false;
+    FASTCALL = 68, "FASTCALL", false;
+    COVERAGE = 69, "COVERAGE", false;
+    CAPTURE = 70, "CAPTURE", false;
+    SUBRK = 71, "SUBRK", false;
+    DIVRK = 72, "DIVRK", false;
+    FASTCALL1 = 73, "FASTCALL1", false;
+    FASTCALL2 = 74, "FASTCALL2", true;
+    FASTCALL2K = 75, "FASTCALL2K", true;
+    FORGPREP = 76, "FORGPREP", false;
+    JUMPXEQKNIL = 77, "JUMPXEQKNIL", true;
+    JUMPXEQKB = 78, "JUMPXEQKB", true;
+    JUMPXEQKN = 79, "JUMPXEQKN", true;
+    JUMPXEQKS = 80, "JUMPXEQKS", true;
+    IDIV = 81, "IDIV", false;
+    IDIVK = 82, "IDIVK", false;
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Instruction {
+    /// Returns the `A` operand (8 bits).
+    #[inline]
+    pub fn a(&self) -> u8 {
+        (self.word >> 8) as u8
+    }
+
+    /// Returns the `B` operand (8 bits).
+    #[inline]
+    pub fn b(&self) -> u8 {
+        (self.word >> 16) as u8
+    }
+
+    /// Returns the `C` operand (8 bits).
+    #[inline]
+    pub fn c(&self) -> u8 {
+        (self.word >> 24) as u8
+    }
+
+    /// Returns the signed `D` operand (16 bits).
+    #[inline]
+    pub fn d(&self) -> i32 {
+        (self.word as i32) >> 16
+    }
+
+    /// Returns the signed `E` operand (24 bits).
+    #[inline]
+    pub fn e(&self) -> i32 {
+        (self.word as i32) >> 8
+    }
+
+    /// Returns the number of 32-bit words occupied by the instruction.
+    #[inline]
+    pub fn size(&self) -> usize {
+        1 + self.aux.is_some() as usize
+    }
+
+    /// Returns the target of a jump instruction.
+    pub fn jump_target(&self) -> Option<usize> {
+        let offset = match self.op {
+            OpCode::JUMP
+            | OpCode::JUMPBACK
+            | OpCode::JUMPIF
+            | OpCode::JUMPIFNOT
+            | OpCode::JUMPIFEQ
+            | OpCode::JUMPIFLE
+            | OpCode::JUMPIFLT
+            | OpCode::JUMPIFNOTEQ
+            | OpCode::JUMPIFNOTLE
+            | OpCode::JUMPIFNOTLT
+            | OpCode::FORNPREP
+            | OpCode::FORNLOOP
+            | OpCode::FORGLOOP
+            | OpCode::FORGPREP_INEXT
+            | OpCode::FORGPREP_NEXT
+            | OpCode::FORGPREP
+            | OpCode::JUMPXEQKNIL
+            | OpCode::JUMPXEQKB
+            | OpCode::JUMPXEQKN
+            | OpCode::JUMPXEQKS => self.d(),
+            OpCode::JUMPX => self.e(),
+            OpCode::LOADB => self.c() as i32,
+            // Fastcalls point to the corresponding `CALL`, which is skipped on success
+            OpCode::FASTCALL
+            | OpCode::FASTCALL1
+            | OpCode::FASTCALL2
+            | OpCode::FASTCALL2K
+            | OpCode::FASTCALL3 => self.c() as i32,
+            _ => return None,
+        };
+        usize::try_from(self.pc as i64 + 1 + offset as i64).ok()
+    }
+}
+
+impl Bytecode {
+    /// Parses a bytecode blob as produced by [`Compiler::compile`].
+    ///
+    /// Returns [`Error::BytecodeError`] if the blob is malformed or was produced by an
+    /// unsupported Luau version.
+    ///
+    /// [`Compiler::compile`]: crate::Compiler::compile
+    pub fn parse(data: &[u8]) -> Result<Bytecode> {
+        let mut r = Reader { data, pos: 0 };
+
+        let version = r.u8()?;
+        if version == 0 {
+            let message = StdString::from_utf8_lossy(&data[1..]);
+            return Err(Error::BytecodeError(format!(
+                "blob contains a compile error: {message}"
+            )));
+        }
+        if !(VERSION_MIN..=VERSION_MAX).contains(&version) {
+            return Err(Error::BytecodeError(format!(
+                "unsupported bytecode version {version} (expected {VERSION_MIN}..={VERSION_MAX})"
+            )));
+        }
+        let mut types_version = 0;
+        if version >= 4 {
+            types_version = r.u8()?;
+            if !(TYPES_VERSION_MIN..=TYPES_VERSION_MAX).contains(&types_version) {
+                return Err(Error::BytecodeError(format!(
+                    "unsupported type information version {types_version} \
+                    (expected {TYPES_VERSION_MIN}..={TYPES_VERSION_MAX})"
+                )));
+            }
+        }
+
+        let string_count = r.varint()? as usize;
+        let mut strings = Vec::with_capacity(string_count.min(data.len()));
+        for _ in 0..string_count {
+            let len = r.varint()? as usize;
+            strings.push(StdString::from_utf8_lossy(r.bytes(len)?).into_owned());
+        }
+        let string_ref = |id: u32| -> Result<Option<StdString>> {
+            match id {
+                0 => Ok(None),
+                id => match strings.get(id as usize - 1) {
+                    Some(s) => Ok(Some(s.clone())),
+                    None => Err(Error::BytecodeError(format!(
+                        "string reference {id} is out of range"
+                    ))),
+                },
+            }
+        };
+
+        // Userdata type remapping table (type information only)
+        if types_version == 3 {
+            while r.u8()? != 0 {
+                string_ref(r.varint()?)?;
+            }
+        }
+
+        let proto_count = r.varint()? as usize;
+        let mut protos = Vec::with_capacity(proto_count.min(data.len()));
+        for id in 0..proto_count {
+            let max_stack_size = r.u8()?;
+            let num_params = r.u8()?;
+            let num_upvalues = r.u8()?;
+            let is_vararg = r.u8()? != 0;
+
+            let mut flags = 0;
+            if version >= 4 {
+                flags = r.u8()?;
+                let type_size = r.varint()? as usize;
+                r.bytes(type_size)?;
+            }
+
+            let code_size = r.varint()? as usize;
+            let mut code = Vec::with_capacity(code_size.min(data.len() / 4));
+            for _ in 0..code_size {
+                code.push(r.u32()?);
+            }
+
+            let const_count = r.varint()? as usize;
+            let mut constants = Vec::with_capacity(const_count.min(data.len()));
+            let mut imports = Vec::new();
+            for _ in 0..const_count {
+                let constant = match r.u8()? {
+                    0 => Constant::Nil,
+                    1 => Constant::Boolean(r.u8()? != 0),
+                    2 => Constant::Number(r.f64()?),
+                    3 => match string_ref(r.varint()?)? {
+                        Some(s) => Constant::String(s),
+                        None => return Err(r.error("string constant references a null string")),
+                    },
+                    4 => {
+                        imports.push((constants.len(), r.u32()?));
+                        Constant::Import(Vec::new())
+                    }
+                    5 => {
+                        let count = r.varint()? as usize;
+                        let mut keys = Vec::with_capacity(count.min(data.len()));
+                        for _ in 0..count {
+                            keys.push((r.varint()? as usize, None));
+                        }
+                        Constant::Table(keys)
+                    }
+                    6 => Constant::Closure(r.varint()? as usize),
+                    7 => Constant::Vector([r.f32()?, r.f32()?, r.f32()?, r.f32()?]),
+                    // Tables with constant values (tag 8) are not understood by the bundled VM
+                    tag => return Err(r.error(format!("unknown constant type {tag}"))),
+                };
+                constants.push(constant);
+            }
+
+            // Resolve import paths once all constants are known
+            for (idx, id) in imports {
+                let count = (id >> 30) as usize;
+                let ids = [(id >> 20) & 1023, (id >> 10) & 1023, id & 1023];
+                let mut path = Vec::with_capacity(count);
+                for &k in &ids[..count.min(3)] {
+                    // Imports are resolved while loading, so they can only reference previous constants
+                    match constants.get(k as usize).filter(|_| (k as usize) < idx) {
+                        Some(Constant::String(s)) => path.push(s.clone()),
+                        _ => return Err(r.error(format!("import references invalid constant {k}"))),
+                    }
+                }
+                constants[idx] = Constant::Import(path);
+            }
+
+            let child_count = r.varint()? as usize;
+            let mut children = Vec::with_capacity(child_count.min(data.len()));
+            for _ in 0..child_count {
+                let child = r.varint()? as usize;
+                if child >= id {
+                    return Err(r.error(format!("proto {id} references invalid child proto {child}")));
+                }
+                children.push(child);
+            }
+
+            let line_defined = r.varint()?;
+            let debug_name = string_ref(r.varint()?)?;
+
+            let mut lines = None;
+            if r.u8()? != 0 {
+                let gap_log2 = r.u8()?;
+                if gap_log2 > 31 {
+                    return Err(r.error(format!("invalid line gap {gap_log2}")));
+                }
+                let intervals = if code_size > 0 {
+                    ((code_size - 1) >> gap_log2) + 1
+                } else {
+                    0
+                };
+                let mut offsets = Vec::with_capacity(code_size);
+                let mut last_offset = 0u8;
+                for _ in 0..code_size {
+                    last_offset = last_offset.wrapping_add(r.u8()?);
+                    offsets.push(last_offset);
+                }
+                let mut abs_lines = Vec::with_capacity(intervals);
+                let mut last_line = 0i32;
+                for _ in 0..intervals {
+                    last_line = last_line.wrapping_add(r.i32()?);
+                    abs_lines.push(last_line);
+                }
+                lines = Some(
+                    (0..code_size)
+                        .map(|pc| (abs_lines[pc >> gap_log2] + offsets[pc] as i32) as u32)
+                        .collect::<Vec<_>>(),
+                );
+            }
+
+            let mut locals = Vec::new();
+            let mut upvalue_names = Vec::new();
+            if r.u8()? != 0 {
+                let local_count = r.varint()? as usize;
+                for _ in 0..local_count {
+                    let name = string_ref(r.varint()?)?.unwrap_or_default();
+                    let start_pc = r.varint()? as usize;
+                    let end_pc = r.varint()? as usize;
+                    let register = r.u8()?;
+                    locals.push(LocalVar {
+                        name,
+                        start_pc,
+                        end_pc,
+                        register,
+                    });
+                }
+                let upvalue_count = r.varint()? as usize;
+                for _ in 0..upvalue_count {
+                    upvalue_names.push(string_ref(r.varint()?)?.unwrap_or_default());
+                }
+            }
+
+            let instructions = decode(id, &code, lines.as_deref())?;
+            protos.push(Proto {
+                id,
+                max_stack_size,
+                num_params,
+                num_upvalues,
+                is_vararg,
+                flags,
+                line_defined,
+                debug_name,
+                constants,
+                children,
+                instructions,
+                locals,
+                upvalue_names,
+            });
+        }
+
+        let main = r.varint()? as usize;
+        if main >= protos.len() {
+            return Err(r.error(format!("main proto {main} is out of range")));
+        }
+        if r.pos != data.len() {
+            return Err(r.error("unexpected trailing data"));
+        }
+
+        Ok(Bytecode {
+            version,
+            types_version,
+            strings,
+            protos,
+            main,
+        })
+    }
+
+    /// Reads flags of the main prototype, skipping over everything else without decoding it.
+    ///
+    /// Returns `None` if the blob is malformed or has no flags (bytecode version < 4).
+    pub(crate) fn main_proto_flags(data: &[u8]) -> Option<u8> {
+        let mut r = Reader { data, pos: 0 };
+        let mut scan = || -> Result<Option<u8>> {
+            let version = r.u8()?;
+            if !(4..=VERSION_MAX).contains(&version) {
+                return Ok(None);
+            }
+            let types_version = r.u8()?;
+            for _ in 0..r.varint()? {
+                let len = r.varint()? as usize;
+                r.bytes(len)?;
+            }
+            if types_version == 3 {
+                while r.u8()? != 0 {
+                    r.varint()?;
+                }
+            }
+
+            let proto_count = r.varint()? as usize;
+            let mut flags = Vec::with_capacity(proto_count.min(data.len()));
+            for _ in 0..proto_count {
+                r.bytes(4)?; // stack size, parameters, upvalues and vararg flag
+                flags.push(r.u8()?);
+                let type_size = r.varint()? as usize;
+                r.bytes(type_size)?;
+                let code_size = r.varint()? as usize;
+                r.bytes(code_size.saturating_mul(4))?;
+                for _ in 0..r.varint()? {
+                    match r.u8()? {
+                        0 => {}
+                        1 => _ = r.u8()?,
+                        2 => _ = r.bytes(8)?,
+                        3 | 6 => _ = r.varint()?,
+                        4 => _ = r.bytes(4)?,
+                        5 => {
+                            for _ in 0..r.varint()? {
+                                r.varint()?;
+                            }
+                        }
+                        7 => _ = r.bytes(16)?,
+                        _ => return Ok(None),
+                    }
+                }
+                for _ in 0..r.varint()? {
+                    r.varint()?; // child proto
+                }
+                r.varint()?; // line defined
+                r.varint()?; // debug name
+                if r.u8()? != 0 {
+                    let gap_log2 = r.u8()?;
+                    if gap_log2 > 31 {
+                        return Ok(None);
+                    }
+                    let intervals = if code_size > 0 {
+                        ((code_size - 1) >> gap_log2) + 1
+                    } else {
+                        0
+                    };
+                    r.bytes(code_size)?;
+                    r.bytes(intervals.saturating_mul(4))?;
+                }
+                if r.u8()? != 0 {
+                    for _ in 0..r.varint()? {
+                        r.varint()?; // name
+                        r.varint()?; // start pc
+                        r.varint()?; // end pc
+                        r.u8()?; // register
+                    }
+                    for _ in 0..r.varint()? {
+                        r.varint()?; // upvalue name
+                    }
+                }
+            }
+            Ok(flags.get(r.varint()? as usize).copied())
+        };
+        scan().ok().flatten()
+    }
+
+    /// Returns the main (top level) prototype.
+    pub fn main_proto(&self) -> &Proto {
+        &self.protos[self.main]
+    }
+
+    /// Returns all `GETIMPORT` sites, in proto order.
+    pub fn import_sites(&self) -> Vec<Site> {
+        self.sites(OpCode::GETIMPORT)
+    }
+
+    /// Returns all `NAMECALL` (method call) sites, in proto order.
+    pub fn namecall_sites(&self) -> Vec<Site> {
+        self.sites(OpCode::NAMECALL)
+    }
+
+    fn sites(&self, op: OpCode) -> Vec<Site> {
+        let mut sites = Vec::new();
+        for proto in &self.protos {
+            for insn in proto.instructions.iter().filter(|i| i.op == op) {
+                let name = match (op, &proto.constants.get(insn.d() as usize)) {
+                    (OpCode::GETIMPORT, Some(Constant::Import(path))) => path.join("."),
+                    (OpCode::NAMECALL, _) => match proto.constants.get(insn.aux.unwrap_or(0) as usize) {
+                        Some(Constant::String(s)) => s.clone(),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                sites.push(Site {
+                    proto: proto.id,
+                    pc: insn.pc,
+                    line: insn.line,
+                    name,
+                });
+            }
+        }
+        sites
+    }
+
+    /// Returns a textual disassembly of all prototypes.
+    ///
+    /// This is equivalent to formatting the [`Bytecode`] using [`Display`](fmt::Display).
+    pub fn disassemble(&self) -> StdString {
+        self.to_string()
+    }
+}
+
+impl Proto {
+    /// Returns the instruction at the given `pc`, if any.
+    pub fn instruction_at(&self, pc: usize) -> Option<&Instruction> {
+        let idx = self.instructions.binary_search_by_key(&pc, |i| i.pc).ok()?;
+        self.instructions.get(idx)
+    }
+
+    /// Returns the number of instructions with the given opcode.
+    pub fn count(&self, op: OpCode) -> usize {
+        self.instructions.iter().filter(|i| i.op == op).count()
+    }
+}
+
+fn decode(proto: usize, code: &[u32], lines: Option<&[u32]>) -> Result<Vec<Instruction>> {
+    let mut instructions = Vec::with_capacity(code.len());
+    let mut pc = 0;
+    while pc < code.len() {
+        let word = code[pc];
+        let op = OpCode::from_u8(word as u8).ok_or_else(|| {
+            Error::BytecodeError(format!(
+                "unknown opcode {} in proto {proto} at pc {pc}",
+                word as u8
+            ))
+        })?;
+        let aux = match op.has_aux() {
+            true => Some(*code.get(pc + 1).ok_or_else(|| {
+                Error::BytecodeError(format!("missing aux word in proto {proto} at pc {pc}"))
+            })?),
+            false => None,
+        };
+        let line = lines.map(|lines| lines[pc]);
+        let insn = Instruction {
+            pc,
+            op,
+            word,
+            aux,
+            line,
+        };
+        pc += insn.size();
+        instructions.push(insn);
+    }
+    Ok(instructions)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, msg: impl fmt::Display) -> Error {
+        Error::BytecodeError(format!("{msg} (at offset {})", self.pos))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        match self.data.get(self.pos..self.pos.saturating_add(len)) {
+            Some(bytes) => {
+                self.pos += len;
+                Ok(bytes)
+            }
+            None => Err(self.error("unexpected end of data")),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> Result<u32> {
+        let mut result = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= 32 {
+                return Err(self.error("varint is too long"));
+            }
+            result |= ((byte & 127) as u32) << shift;
+            shift += 7;
+            if byte & 128 == 0 {
+                return Ok(result);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constant::Nil => write!(f, "nil"),
+            Constant::Boolean(b) => write!(f, "{b}"),
+            Constant::Number(n) => write!(f, "{n}"),
+            Constant::Vector([x, y, z, w]) => write!(f, "vector({x}, {y}, {z}, {w})"),
+            Constant::String(s) => write!(f, "{s:?}"),
+            Constant::Import(path) => write!(f, "{}", path.join(".")),
+            Constant::Table(keys) => write!(f, "{{...}} ({} keys)", keys.len()),
+            Constant::Closure(id) => write!(f, "closure(proto {id})"),
+        }
+    }
+}
+
+impl Proto {
+    fn fmt_constant(&self, f: &mut fmt::Formatter, idx: usize) -> fmt::Result {
+        match self.constants.get(idx) {
+            Some(k) => write!(f, "K{idx} [{k}]"),
+            None => write!(f, "K{idx} [<invalid>]"),
+        }
+    }
+
+    fn fmt_instruction(&self, f: &mut fmt::Formatter, insn: &Instruction) -> fmt::Result {
+        use OpCode::*;
+
+        let (a, b, c, d) = (insn.a(), insn.b(), insn.c(), insn.d());
+        let aux = insn.aux.unwrap_or(0);
+        write!(f, "{}", insn.op)?;
+        match insn.op {
+            NOP | BREAK | NATIVECALL => Ok(()),
+            LOADNIL | CLOSEUPVALS | PREPVARARGS => write!(f, " R{a}"),
+            LOADB => write!(f, " R{a} {b} {c}"),
+            LOADN => write!(f, " R{a} {d}"),
+            LOADK | DUPTABLE | DUPCLOSURE => {
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, d as usize)
+            }
+            LOADKX => {
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, aux as usize)
+            }
+            MOVE | NOT | MINUS | LENGTH | GETVARARGS => write!(f, " R{a} R{b}"),
+            GETUPVAL | SETUPVAL => write!(f, " R{a} U{b}"),
+            GETGLOBAL | SETGLOBAL => {
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, aux as usize)
+            }
+            GETIMPORT => {
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, d as usize)
+            }
+            GETTABLE | SETTABLE | ADD | SUB | MUL | DIV | MOD | POW | IDIV | AND | OR | CONCAT => {
+                write!(f, " R{a} R{b} R{c}")
+            }
+            GETTABLEKS | SETTABLEKS | NAMECALL => {
+                write!(f, " R{a} R{b} ")?;
+                self.fmt_constant(f, aux as usize)
+            }
+            GETTABLEN | SETTABLEN => write!(f, " R{a} R{b} {}", c as u32 + 1),
+            ADDK | SUBK | MULK | DIVK | MODK | POWK | IDIVK | ANDK | ORK => {
+                write!(f, " R{a} R{b} ")?;
+                self.fmt_constant(f, c as usize)
+            }
+            SUBRK | DIVRK => {
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, b as usize)?;
+                write!(f, " R{c}")
+            }
+            NEWCLOSURE => match self.children.get(d as usize) {
+                Some(id) => write!(f, " R{a} P{d} (proto {id})"),
+                None => write!(f, " R{a} P{d}"),
+            },
+            CALL => write!(f, " R{a} {} {}", b as i32 - 1, c as i32 - 1),
+            RETURN => write!(f, " R{a} {}", b as i32 - 1),
+            CAPTURE => write!(f, " {a} {b}"),
+            NEWTABLE => write!(f, " R{a} {b} {aux}"),
+            SETLIST => write!(f, " R{a} R{b} {} [{aux}]", c as i32 - 1),
+            JUMP | JUMPBACK | JUMPX => write!(f, " L{}", insn.jump_target().unwrap_or(0)),
+            JUMPIF | JUMPIFNOT | FORNPREP | FORNLOOP | FORGPREP | FORGPREP_INEXT | FORGPREP_NEXT => {
+                write!(f, " R{a} L{}", insn.jump_target().unwrap_or(0))
+            }
+            FORGLOOP => write!(f, " R{a} L{} {}", insn.jump_target().unwrap_or(0), aux as i32),
+            JUMPIFEQ | JUMPIFLE | JUMPIFLT | JUMPIFNOTEQ | JUMPIFNOTLE | JUMPIFNOTLT => {
+                write!(f, " R{a} R{aux} L{}", insn.jump_target().unwrap_or(0))
+            }
+            JUMPXEQKNIL => {
+                let not = if aux >> 31 != 0 { " NOT" } else { "" };
+                write!(f, " R{a} L{}{not}", insn.jump_target().unwrap_or(0))
+            }
+            JUMPXEQKB => {
+                let not = if aux >> 31 != 0 { " NOT" } else { "" };
+                write!(
+                    f,
+                    " R{a} {} L{}{not}",
+                    aux & 1 != 0,
+                    insn.jump_target().unwrap_or(0)
+                )
+            }
+            JUMPXEQKN | JUMPXEQKS => {
+                let not = if aux >> 31 != 0 { " NOT" } else { "" };
+                write!(f, " R{a} ")?;
+                self.fmt_constant(f, (aux & 0xffffff) as usize)?;
+                write!(f, " L{}{not}", insn.jump_target().unwrap_or(0))
+            }
+            FASTCALL => write!(f, " {a} L{}", insn.jump_target().unwrap_or(0)),
+            FASTCALL1 => write!(f, " {a} R{b} L{}", insn.jump_target().unwrap_or(0)),
+            FASTCALL2 => write!(f, " {a} R{b} R{aux} L{}", insn.jump_target().unwrap_or(0)),
+            FASTCALL2K => {
+                write!(f, " {a} R{b} ")?;
+                self.fmt_constant(f, aux as usize)?;
+                write!(f, " L{}", insn.jump_target().unwrap_or(0))
+            }
+            FASTCALL3 => write!(
+                f,
+                " {a} R{b} R{} R{} L{}",
+                aux & 0xff,
+                (aux >> 8) & 0xff,
+                insn.jump_target().unwrap_or(0)
+            ),
+            COVERAGE => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Bytecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, proto) in self.protos.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let name = match (&proto.debug_name, proto.id == self.main) {
+                (Some(name), _) => name.as_str(),
+                (None, true) => "main",
+                (None, false) => "<anonymous>",
+            };
+            let vararg = if proto.is_vararg { ", vararg" } else { "" };
+            writeln!(
+                f,
+                "; proto {} `{name}` (line {}): {} params{vararg}, {} upvalues, {} registers",
+                proto.id, proto.line_defined, proto.num_params, proto.num_upvalues, proto.max_stack_size
+            )?;
+            for insn in &proto.instructions {
+                match insn.line {
+                    Some(line) => write!(f, "{:>4} [{line:>3}] ", insn.pc)?,
+                    None => write!(f, "{:>4} ", insn.pc)?,
+                }
+                proto.fmt_instruction(f, insn)?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,492 @@
+use std::fmt;
+
+use super::{Bytecode, Constant, Instruction, OpCode, Proto};
+use crate::error::{Error, Result};
+
+// The VM builtin table always has at least this many entries (known builtins followed by fallback
+// stubs), so fastcalls with ids in `1..=MAX_BUILTIN_ID` never hit an empty slot.
+const MAX_BUILTIN_ID: u8 = 127;
+
+// Capture types used by `CAPTURE` instruction
+const CAPTURE_VAL: u8 = 0;
+const CAPTURE_REF: u8 = 1;
+const CAPTURE_UPVAL: u8 = 2;
+
+impl Bytecode {
+    /// Checks that the bytecode is structurally valid and can be safely loaded into the Luau VM.
+    ///
+    /// The Luau VM trusts the bytecode it is given, so loading a malformed blob can lead to
+    /// undefined behavior. The verifier ensures that:
+    ///
+    /// - every register, upvalue, constant and child prototype reference is in range and points to
+    ///   an entry of the expected kind;
+    /// - every jump lands on an instruction boundary within the function, and control flow never
+    ///   falls off the end of the code;
+    /// - instruction sequences that the VM executes as a unit (`NAMECALL` + `CALL`, closures and
+    ///   their `CAPTURE`s, multiple return values and their consumers) are well formed.
+    ///
+    /// Type information embedded into the bytecode is not validated, so native code generation
+    /// should not be enabled for untrusted bytecode. Verified chunks are not compiled to native code
+    /// unless requested with [`Chunk::set_native`].
+    ///
+    /// [`Chunk::set_native`]: crate::Chunk::set_native
+    ///
+    /// Returns [`Error::BytecodeError`] describing the first problem found.
+    pub fn verify(&self) -> Result<()> {
+        // The VM creates the main closure without upvalues
+        match self.protos.get(self.main) {
+            Some(main) if main.num_upvalues != 0 => {
+                return Err(Error::BytecodeError(format!(
+                    "invalid proto {}: main proto cannot have upvalues",
+                    self.main
+                )));
+            }
+            Some(_) => {}
+            None => {
+                return Err(Error::BytecodeError(format!(
+                    "main proto {} is out of range",
+                    self.main
+                )));
+            }
+        }
+        for proto in &self.protos {
+            Verifier::new(self, proto).verify()?;
+        }
+        Ok(())
+    }
+}
+
+struct Verifier<'a> {
+    bytecode: &'a Bytecode,
+    proto: &'a Proto,
+    // Index of the instruction starting at every pc (`None` for aux words)
+    index: Vec<Option<usize>>,
+    // `true` for every pc that is a target of a jump
+    targets: Vec<bool>,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(bytecode: &'a Bytecode, proto: &'a Proto) -> Self {
+        let code_size = (proto.instructions.last()).map(|i| i.pc + i.size()).unwrap_or(0);
+        let mut index = vec![None; code_size];
+        let mut targets = vec![false; code_size];
+        for (i, insn) in proto.instructions.iter().enumerate() {
+            index[insn.pc] = Some(i);
+            // Fastcalls skip over the CALL instead of jumping to it
+            if let Some(target) = insn.jump_target().filter(|_| !is_fastcall(insn.op)) {
+                if let Some(target) = targets.get_mut(target) {
+                    *target = true;
+                }
+            }
+        }
+        Verifier {
+            bytecode,
+            proto,
+            index,
+            targets,
+        }
+    }
+
+    fn error(&self, insn: &Instruction, msg: impl fmt::Display) -> Error {
+        Error::BytecodeError(format!(
+            "invalid {} in proto {} at pc {}: {msg}",
+            insn.op, self.proto.id, insn.pc
+        ))
+    }
+
+    fn verify(&self) -> Result<()> {
+        let proto = self.proto;
+        let fail = |msg: &str| Error::BytecodeError(format!("invalid proto {}: {msg}", proto.id));
+
+        if proto.num_params > proto.max_stack_size {
+            return Err(fail("number of parameters exceeds the stack size"));
+        }
+        match (proto.instructions.first(), proto.instructions.last()) {
+            (Some(first), Some(last)) => {
+                if proto.is_vararg && (first.op != OpCode::PREPVARARGS || first.a() != proto.num_params) {
+                    return Err(fail("vararg function must start with PREPVARARGS"));
+                }
+                if !matches!(
+                    last.op,
+                    OpCode::RETURN | OpCode::JUMP | OpCode::JUMPBACK | OpCode::JUMPX
+                ) {
+                    return Err(fail("control flow falls off the end of the code"));
+                }
+            }
+            _ => return Err(fail("empty code")),
+        }
+        for local in &proto.locals {
+            if local.register >= proto.max_stack_size
+                || local.start_pc > local.end_pc
+                || local.end_pc > self.index.len()
+            {
+                return Err(fail(&format!("invalid local variable `{}`", local.name)));
+            }
+        }
+
+        for (idx, constant) in proto.constants.iter().enumerate() {
+            match constant {
+                // Constants are created in order, so templates can only reference previous ones
+                Constant::Table(keys) => {
+                    for &(key, value) in keys {
+                        if key >= idx || !is_string(&proto.constants[key]) || value.is_some() {
+                            return Err(fail(&format!("invalid table template K{idx}")));
+                        }
+                    }
+                }
+                Constant::Closure(id) if *id >= proto.id => {
+                    return Err(fail(&format!("closure K{idx} references invalid proto {id}")));
+                }
+                _ => {}
+            }
+        }
+
+        let mut captures = 0;
+        for (i, insn) in proto.instructions.iter().enumerate() {
+            if insn.op == OpCode::CAPTURE {
+                if captures == 0 {
+                    return Err(self.error(insn, "capture outside of closure creation"));
+                }
+                captures -= 1;
+            } else if captures > 0 {
+                return Err(self.error(insn, "missing closure captures"));
+            }
+            captures += self.verify_instruction(i, insn)?;
+        }
+
+        Ok(())
+    }
+
+    // Verifies a single instruction, returning the number of `CAPTURE`s that must follow it
+    fn verify_instruction(&self, i: usize, insn: &Instruction) -> Result<usize> {
+        use OpCode::*;
+
+        let (a, b, c) = (insn.a() as u32, insn.b() as u32, insn.c() as u32);
+        let d = insn.d() as u32; // Only used as an index when non-negative
+        let aux = insn.aux.unwrap_or(0);
+
+        if let Some(target) = insn.jump_target() {
+            self.verify_jump(insn, target)?;
+        }
+
+        match insn.op {
+            NOP | COVERAGE | JUMP | JUMPBACK | JUMPX => {}
+            BREAK | NATIVECALL => return Err(self.error(insn, "instruction is reserved for the VM")),
+            LOADNIL | LOADB | LOADN | CLOSEUPVALS | NEWTABLE | JUMPIF | JUMPIFNOT | JUMPXEQKNIL
+            | JUMPXEQKB => self.register(insn, a)?,
+            LOADK => {
+                self.register(insn, a)?;
+                self.constant(insn, insn.d(), is_value)?;
+            }
+            LOADKX => {
+                self.register(insn, a)?;
+                self.constant(insn, aux as i32, is_value)?;
+            }
+            MOVE | NOT | MINUS | LENGTH | GETTABLEN | SETTABLEN => {
+                self.register(insn, a)?;
+                self.register(insn, b)?;
+            }
+            GETGLOBAL | SETGLOBAL => {
+                self.register(insn, a)?;
+                self.constant(insn, aux as i32, is_string)?;
+            }
+            GETUPVAL | SETUPVAL => {
+                self.register(insn, a)?;
+                self.upvalue(insn, b)?;
+            }
+            GETIMPORT => {
+                self.register(insn, a)?;
+                self.constant(insn, insn.d(), |k| matches!(k, Constant::Import(_)))?;
+                // The import path is also used on the slow path
+                let count = (aux >> 30) as usize;
+                if count == 0 {
+                    return Err(self.error(insn, "empty import path"));
+                }
+                for id in [aux >> 20, aux >> 10, aux].iter().take(count) {
+                    self.constant(insn, (id & 1023) as i32, is_string)?;
+                }
+            }
+            GETTABLE | SETTABLE | ADD | SUB | MUL | DIV | MOD | POW | IDIV | AND | OR => {
+                self.register(insn, a)?;
+                self.register(insn, b)?;
+                self.register(insn, c)?;
+            }
+            GETTABLEKS | SETTABLEKS => {
+                self.register(insn, a)?;
+                self.register(insn, b)?;
+                self.constant(insn, aux as i32, is_string)?;
+            }
+            NAMECALL => {
+                self.registers(insn, a, 2)?;
+                self.register(insn, b)?;
+                self.constant(insn, aux as i32, is_string)?;
+                // NAMECALL falls through directly into the following CALL
+                match self.proto.instructions.get(i + 1) {
+                    Some(next) if next.op == CALL && next.a() as u32 == a => {}
+                    _ => return Err(self.error(insn, "must be followed by CALL")),
+                }
+            }
+            ADDK | SUBK | MULK | DIVK | MODK | POWK | IDIVK => {
+                self.register(insn, a)?;
+                self.register(insn, b)?;
+                self.constant(insn, c as i32, |k| matches!(k, Constant::Number(_)))?;
+            }
+            SUBRK | DIVRK => {
+                self.register(insn, a)?;
+                self.constant(insn, b as i32, |k| matches!(k, Constant::Number(_)))?;
+                self.register(insn, c)?;
+            }
+            ANDK | ORK => {
+                self.register(insn, a)?;
+                self.register(insn, b)?;
+                self.constant(insn, c as i32, is_value)?;
+            }
+            CONCAT => {
+                self.register(insn, a)?;
+                if b > c {
+                    return Err(self.error(insn, "invalid register range"));
+                }
+                self.registers(insn, b, c - b + 1)?;
+            }
+            NEWCLOSURE => {
+                self.register(insn, a)?;
+                let child = match self.proto.children.get(d as usize) {
+                    Some(&child) if insn.d() >= 0 => child,
+                    _ => return Err(self.error(insn, format!("child proto {d} is out of range"))),
+                };
+                return self.closure(i, insn, child, &[CAPTURE_VAL, CAPTURE_REF, CAPTURE_UPVAL]);
+            }
+            DUPCLOSURE => {
+                self.register(insn, a)?;
+                let child = match self.constant(insn, insn.d(), |k| matches!(k, Constant::Closure(_)))? {
+                    Constant::Closure(child) => *child,
+                    _ => unreachable!(),
+                };
+                // Shared closures cannot capture locals by reference
+                return self.closure(i, insn, child, &[CAPTURE_VAL, CAPTURE_UPVAL]);
+            }
+            CAPTURE => {} // Verified together with the closure instruction
+            CALL => {
+                self.register(insn, a)?;
+                match b {
+                    0 => self.multret_consumer(i, insn, a + 1)?,
+                    _ => self.registers(insn, a, b)?,
+                }
+                match c {
+                    0 => self.multret_producer(i, insn)?,
+                    _ => self.registers(insn, a, c - 1)?,
+                }
+            }
+            RETURN => match b {
+                0 => self.multret_consumer(i, insn, a)?,
+                _ => self.registers(insn, a, b - 1)?,
+            },
+            GETVARARGS => {
+                if !self.proto.is_vararg {
+                    return Err(self.error(insn, "function is not vararg"));
+                }
+                self.register(insn, a)?;
+                match b {
+                    0 => self.multret_producer(i, insn)?,
+                    _ => self.registers(insn, a, b - 1)?,
+                }
+            }
+            PREPVARARGS => {
+                if i != 0 || !self.proto.is_vararg {
+                    return Err(self.error(insn, "must be the first instruction of a vararg function"));
+                }
+            }
+            JUMPIFEQ | JUMPIFLE | JUMPIFLT | JUMPIFNOTEQ | JUMPIFNOTLE | JUMPIFNOTLT => {
+                self.register(insn, a)?;
+                self.register(insn, aux)?;
+            }
+            JUMPXEQKN => {
+                self.register(insn, a)?;
+                self.constant(insn, (aux & 0xffffff) as i32, |k| {
+                    matches!(k, Constant::Number(_))
+                })?;
+            }
+            JUMPXEQKS => {
+                self.register(insn, a)?;
+                self.constant(insn, (aux & 0xffffff) as i32, is_string)?;
+            }
+            DUPTABLE => {
+                self.register(insn, a)?;
+                self.constant(insn, insn.d(), |k| matches!(k, Constant::Table(_)))?;
+            }
+            SETLIST => {
+                self.register(insn, a)?;
+                match c {
+                    0 => self.multret_consumer(i, insn, b)?,
+                    _ => self.registers(insn, b, c - 1)?,
+                }
+            }
+            FORNPREP | FORNLOOP => self.registers(insn, a, 3)?,
+            FORGPREP | FORGPREP_INEXT | FORGPREP_NEXT => {
+                self.registers(insn, a, 3)?;
+                // The loop header must refer to the same registers
+                let target = insn.jump_target().and_then(|pc| self.instruction(pc));
+                match target {
+                    Some(target) if target.op == FORGLOOP && target.a() as u32 == a => {}
+                    _ => return Err(self.error(insn, "must jump to the matching FORGLOOP")),
+                }
+            }
+            FORGLOOP => {
+                let count = aux & 0xff;
+                if count == 0 {
+                    return Err(self.error(insn, "loop has no variables"));
+                }
+                self.registers(insn, a, 3 + count)?;
+            }
+            FASTCALL | FASTCALL1 | FASTCALL2 | FASTCALL2K | FASTCALL3 => {
+                if insn.a() == 0 || insn.a() > MAX_BUILTIN_ID {
+                    return Err(self.error(insn, format!("unknown builtin function {a}")));
+                }
+                match insn.op {
+                    FASTCALL => {}
+                    FASTCALL1 => self.register(insn, b)?,
+                    FASTCALL2 => {
+                        self.register(insn, b)?;
+                        self.register(insn, aux & 0xff)?;
+                    }
+                    FASTCALL2K => {
+                        self.register(insn, b)?;
+                        self.constant(insn, aux as i32, is_value)?;
+                    }
+                    _ => {
+                        self.register(insn, b)?;
+                        self.register(insn, aux & 0xff)?;
+                        self.register(insn, (aux >> 8) & 0xff)?;
+                    }
+                }
+                // Arguments and results are taken from the CALL instruction
+                let target = insn.jump_target().and_then(|pc| self.instruction(pc));
+                if target.map(|target| target.op) != Some(CALL) {
+                    return Err(self.error(insn, "must be followed by CALL"));
+                }
+            }
+        }
+
+        Ok(0)
+    }
+
+    fn instruction(&self, pc: usize) -> Option<&'a Instruction> {
+        let i = (*self.index.get(pc)?)?;
+        self.proto.instructions.get(i)
+    }
+
+    fn verify_jump(&self, insn: &Instruction, target: usize) -> Result<()> {
+        match self.instruction(target) {
+            Some(target) if target.op != OpCode::CAPTURE => Ok(()),
+            _ => Err(self.error(insn, format!("invalid jump target {target}"))),
+        }
+    }
+
+    fn register(&self, insn: &Instruction, reg: u32) -> Result<()> {
+        self.registers(insn, reg, 1)
+    }
+
+    fn registers(&self, insn: &Instruction, start: u32, count: u32) -> Result<()> {
+        if start + count > self.proto.max_stack_size as u32 {
+            return Err(self.error(insn, format!("register R{} is out of range", start + count - 1)));
+        }
+        Ok(())
+    }
+
+    fn upvalue(&self, insn: &Instruction, idx: u32) -> Result<()> {
+        if idx >= self.proto.num_upvalues as u32 {
+            return Err(self.error(insn, format!("upvalue U{idx} is out of range")));
+        }
+        Ok(())
+    }
+
+    fn constant(&self, insn: &Instruction, idx: i32, check: fn(&Constant) -> bool) -> Result<&'a Constant> {
+        let constant = usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.proto.constants.get(idx));
+        match constant {
+            Some(k) if check(k) => Ok(k),
+            Some(k) => Err(self.error(insn, format!("unexpected constant K{idx} [{k}]"))),
+            None => Err(self.error(insn, format!("constant K{idx} is out of range"))),
+        }
+    }
+
+    fn closure(&self, i: usize, insn: &Instruction, child: usize, allowed: &[u8]) -> Result<usize> {
+        let num_upvalues = match self.bytecode.protos.get(child) {
+            Some(child) => child.num_upvalues as usize,
+            None => return Err(self.error(insn, format!("proto {child} is out of range"))),
+        };
+        let captures = self.proto.instructions.iter().skip(i + 1).take(num_upvalues);
+        for capture in captures.filter(|capture| capture.op == OpCode::CAPTURE) {
+            match capture.a() {
+                kind if !allowed.contains(&kind) => {
+                    return Err(self.error(capture, format!("unexpected capture type {kind}")));
+                }
+                CAPTURE_UPVAL => self.upvalue(capture, capture.b() as u32)?,
+                _ => self.register(capture, capture.b() as u32)?,
+            }
+        }
+        Ok(num_upvalues)
+    }
+
+    // Instructions with variable number of results set the stack top, that must be consumed by
+    // the following instruction. Only instructions that preserve the stack top (eg. loading the
+    // function to call and fast calls) may be placed in between.
+    fn multret_producer(&self, i: usize, insn: &Instruction) -> Result<()> {
+        let instructions = &self.proto.instructions[i + 1..];
+        let next = instructions.iter().find(|next| !preserves_top(next.op));
+        let consumes = next.is_some_and(|next| match next.op {
+            OpCode::CALL | OpCode::RETURN => next.b() == 0,
+            OpCode::SETLIST => next.c() == 0,
+            _ => false,
+        });
+        if !consumes {
+            return Err(self.error(insn, "variable results are not consumed"));
+        }
+        Ok(())
+    }
+
+    fn multret_consumer(&self, i: usize, insn: &Instruction, start: u32) -> Result<()> {
+        // The producer must place values at or above the first consumed register
+        let instructions = &self.proto.instructions[..i];
+        let prev = instructions.iter().rev().find(|prev| !preserves_top(prev.op));
+        let produces = prev.is_some_and(|prev| match prev.op {
+            OpCode::CALL => prev.c() == 0 && prev.a() as u32 >= start,
+            OpCode::GETVARARGS => prev.b() == 0 && prev.a() as u32 >= start,
+            _ => false,
+        });
+        if !produces || self.targets[insn.pc] {
+            return Err(self.error(insn, "variable arguments are not produced"));
+        }
+        Ok(())
+    }
+}
+
+// Constants that can be loaded into a register as is
+fn is_value(k: &Constant) -> bool {
+    matches!(
+        k,
+        Constant::Nil
+            | Constant::Boolean(_)
+            | Constant::Number(_)
+            | Constant::Vector(_)
+            | Constant::String(_)
+    )
+}
+
+fn is_fastcall(op: OpCode) -> bool {
+    use OpCode::*;
+    matches!(op, FASTCALL | FASTCALL1 | FASTCALL2 | FASTCALL2K | FASTCALL3)
+}
+
+// Instructions that can be placed between a producer and a consumer of variable results
+fn preserves_top(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::NAMECALL | OpCode::GETIMPORT | OpCode::COVERAGE | OpCode::NOP
+    ) || is_fastcall(op)
+}
+
+fn is_string(k: &Constant) -> bool {
+    matches!(k, Constant::String(_))
+}
@@ -1,18 +1,32 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::io::Result as IoResult;
 use std::panic::Location;
 use std::path::{Path, PathBuf};
 use std::string::String as StdString;
 
-use crate::error::{Error, Result};
+use bstr::ByteSlice;
+
+use crate::bytecode::Bytecode;
+use crate::error::{Error, Result, SyntaxDiagnostic};
 use crate::function::Function;
+use crate::luau::{is_native_annotated, NativeCodeStats};
 use crate::state::{Lua, WeakLua};
 use crate::table::Table;
 use crate::traits::{FromLuaMulti, IntoLua, IntoLuaMulti};
+use crate::types::XRc;
 use crate::value::Value;
 
+pub use cache::{ChunkCache, ChunkCacheKey, FileChunkCache};
+
+#[cfg(feature = "bundle")]
+pub use bundle::{Bundle, BUNDLE_SIGNATURE_LEN};
+
+#[cfg(feature = "bundle")]
+mod bundle;
+mod cache;
+
 /// Trait for types [loadable by Lua] and convertible to a [`Chunk`]
 ///
 /// [loadable by Lua]: https://www.lua.org/manual/5.4/manual.html#3.3.2
@@ -37,6 +51,13 @@ pub trait AsChunk {
         None
     }
 
+    /// Returns `true` if the chunk is bytecode produced by the Luau compiler at build time (e.g. by
+    /// the `chunk!` macro), so it is not verified when loaded in binary mode.
+    #[doc(hidden)]
+    fn is_trusted(&self) -> bool {
+        false
+    }
+
     /// Returns chunk data (can be text or binary)
     fn source<'a>(&self) -> IoResult<Cow<'a, [u8]>>
     where
@@ -124,6 +145,10 @@ impl<C: AsChunk + ?Sized> AsChunk for Box<C> {
         (**self).mode()
     }
 
+    fn is_trusted(&self) -> bool {
+        (**self).is_trusted()
+    }
+
     fn source<'a>(&self) -> IoResult<Cow<'a, [u8]>>
     where
         Self: 'a,
@@ -141,6 +166,10 @@ pub struct Chunk<'a> {
     pub(crate) mode: Option<ChunkMode>,
     pub(crate) source: IoResult<Cow<'a, [u8]>>,
     pub(crate) compiler: Option<Compiler>,
+    pub(crate) cache: Option<XRc<dyn ChunkCache>>,
+    pub(crate) verify: bool,
+    pub(crate) trusted: bool,
+    pub(crate) native: Option<bool>,
 }
 
 /// Represents chunk mode (text or binary).
@@ -478,9 +507,11 @@ impl Compiler {
             // The rest of the bytecode is the error message starting with `:`
             // See https://github.com/luau-lang/luau/blob/0.640/Compiler/src/Compiler.cpp#L4336
             let message = StdString::from_utf8_lossy(&bytecode[2..]).into_owned();
+            let diagnostic = SyntaxDiagnostic::from_message(&StdString::from_utf8_lossy(&bytecode[1..]));
             return Err(Error::SyntaxError {
                 incomplete_input: message.ends_with("<eof>"),
                 message,
+                diagnostics: diagnostic.into_iter().collect(),
             });
         }
 
@@ -533,13 +564,33 @@ impl Chunk<'_> {
 
     /// Sets whether the chunk is text or binary (autodetected by default).
     ///
-    /// Be aware, Lua does not check the consistency of the code inside binary chunks.
-    /// Running maliciously crafted bytecode can crash the interpreter.
+    /// Luau does not check the consistency of the code inside binary chunks, so chunks explicitly
+    /// loaded in binary mode are checked using the [bytecode verifier] first. Bytecode that
+    /// fails verification is refused with [`Error::BytecodeError`].
+    ///
+    /// [bytecode verifier]: crate::bytecode::Bytecode::verify
     pub fn set_mode(mut self, mode: ChunkMode) -> Self {
         self.mode = Some(mode);
         self
     }
 
+    /// Sets whether the chunk is compiled to native code.
+    ///
+    /// By default, chunks are compiled to native code if JIT is enabled using [`Lua::enable_jit`],
+    /// or if the chunk is annotated with `--!native` or contains `@native` functions.
+    /// This option overrides both.
+    ///
+    /// Untrusted bytecode (chunks loaded in binary mode or verified because of
+    /// [`Lua::set_allow_unverified_bytecode`]) is never compiled to native code, unless this option
+    /// is explicitly set to `true`. Bytecode embedded at build time, signed bundles and module
+    /// archives are trusted.
+    ///
+    /// Has no effect if native code generation is not supported on the current platform.
+    pub fn set_native(mut self, native: bool) -> Self {
+        self.native = Some(native);
+        self
+    }
+
     /// Sets or overwrites a Luau compiler used for this chunk.
     ///
     /// See [`Compiler`] for details and possible options.
@@ -630,15 +681,128 @@ impl Chunk<'_> {
     ///
     /// This simply compiles the chunk without actually executing it.
     pub fn into_function(mut self) -> Result<Function> {
-        if self.compiler.is_some() {
-            // We don't need to compile source if no compiler set
+        // Bytecode loaded explicitly in binary mode is untrusted, unless it comes from a trusted source
+        let verify = !self.trusted && (self.verify || self.mode == Some(ChunkMode::Binary));
+        let codegen = self.is_codegen_enabled(verify);
+
+        // The debugger needs names of local variables
+        let debugging = unsafe { (*self.lua.lock().extra()).debugger.is_some() };
+        if debugging {
+            let compiler = self.compiler.get_or_insert_with(Default::default);
+            compiler.debug_level = compiler.debug_level.max(2);
+        }
+
+        if self.compiler.is_some() || self.cache.is_some() || self.needs_bytecode(codegen) {
+            // We don't need to compile source if no compiler or cache set, or bytecode is not needed
             self.compile();
         }
 
-        let name = Self::convert_name(self.name)?;
-        self.lua
-            .lock()
-            .load_chunk(Some(&name), self.env?.as_ref(), self.mode, self.source?.as_ref())
+        let name = Self::convert_name(self.name.clone())?;
+        let source = self.source?;
+        if verify {
+            Self::verify_bytecode(&source)?;
+        }
+        let native = if codegen {
+            Self::native_code(&self.lua, self.native, &source)
+        } else {
+            None
+        };
+        Self::load(&self.lua, &name, self.env?.as_ref(), self.mode, &source, native)
+            .map_err(|err| err.with_chunk_name(&self.name))
+    }
+
+    fn load(
+        lua: &WeakLua,
+        name: &CStr,
+        env: Option<&Table>,
+        mode: Option<ChunkMode>,
+        source: &[u8],
+        native: Option<NativeCodeStats>,
+    ) -> Result<Function> {
+        let lua = lua.lock();
+        let (function, is_native) = lua.load_chunk(Some(name), env, mode, source, native.is_some())?;
+        if let Some(stats) = native.filter(|_| is_native) {
+            if let Some(total) = unsafe { &mut (*lua.extra()).native_stats } {
+                *total += stats;
+            }
+        }
+        Ok(function)
+    }
+
+    /// Checks whether the chunk can be compiled to native code.
+    ///
+    /// Unless requested explicitly, this is the case only if JIT is enabled or the chunk might be
+    /// annotated with `--!native` / `@native`, so other chunks are loaded without extra work.
+    fn is_codegen_enabled(&self, verify: bool) -> bool {
+        if unsafe { ffi::luau_codegen_supported() } == 0 {
+            return false;
+        }
+        match self.native {
+            Some(native) => native,
+            // Type information in untrusted bytecode is not verified, so it is compiled to native
+            // code only on explicit request
+            None if verify => false,
+            None => {
+                let enable_jit = unsafe { (*self.lua.lock().extra()).enable_jit };
+                enable_jit
+                    || self
+                        .source
+                        .as_ref()
+                        .is_ok_and(|source| Self::may_be_native(source))
+            }
+        }
+    }
+
+    /// Checks whether the chunk must be compiled before loading to find out if it requests native
+    /// compilation or to collect native code statistics.
+    ///
+    /// Otherwise Luau compiles the source when loading, so it's not compiled twice.
+    fn needs_bytecode(&self, codegen: bool) -> bool {
+        if !codegen {
+            return false;
+        }
+        let lua = self.lua.lock();
+        let (enable_jit, collect_stats) = unsafe {
+            let extra = lua.extra();
+            ((*extra).enable_jit, (*extra).native_stats.is_some())
+        };
+        collect_stats || (self.native.is_none() && !enable_jit)
+    }
+
+    /// Quickly checks whether the source might request native compilation.
+    fn may_be_native(source: &[u8]) -> bool {
+        match source.first() {
+            Some(&b) if b < b'\t' => is_native_annotated(source),
+            _ => source.contains_str("native"),
+        }
+    }
+
+    /// Decides whether the chunk is compiled to native code.
+    ///
+    /// Returns statistics of the functions that will be compiled, or empty statistics if they are
+    /// not collected.
+    fn native_code(lua: &WeakLua, native: Option<bool>, source: &[u8]) -> Option<NativeCodeStats> {
+        let lua = lua.lock();
+        let (enable_jit, collect_stats) = unsafe {
+            let extra = lua.extra();
+            ((*extra).enable_jit, (*extra).native_stats.is_some())
+        };
+        if !native.unwrap_or_else(|| enable_jit || is_native_annotated(source)) {
+            return None;
+        }
+        if !collect_stats {
+            return Some(NativeCodeStats::default());
+        }
+        // Statistics are skipped if the bytecode cannot be parsed (e.g. text source or a newer bytecode
+        // version), this does not affect code generation
+        let stats = Bytecode::parse(source).map(|bytecode| NativeCodeStats::from_bytecode(&bytecode));
+        Some(stats.unwrap_or_default())
+    }
+
+    /// Marks the chunk as coming from a trusted source, so its bytecode is not verified.
+    pub(crate) fn set_trusted(mut self) -> Self {
+        self.trusted = true;
+        self
     }
 
     /// Compiles the chunk and changes mode to binary.
@@ -647,7 +811,12 @@ impl Chunk<'_> {
     fn compile(&mut self) {
         if let Ok(ref source) = self.source {
             if self.detect_mode() == ChunkMode::Text {
-                if let Ok(data) = self.compiler.get_or_insert_with(Default::default).compile(source) {
+                let compiler = self.compiler.get_or_insert_with(Default::default);
+                let data = match self.cache {
+                    Some(ref cache) => Self::compile_cached(&**cache, compiler, source),
+                    None => compiler.compile(source),
+                };
+                if let Ok(data) = data {
                     self.source = Ok(Cow::Owned(data));
                     self.mode = Some(ChunkMode::Binary);
                 }
@@ -655,6 +824,19 @@ impl Chunk<'_> {
         }
     }
 
+    /// Fetches compiled bytecode from the persistent chunk cache.
+    ///
+    /// If not found, compiles the source code and stores it on the cache.
+    fn compile_cached(cache: &dyn ChunkCache, compiler: &Compiler, source: &[u8]) -> Result<Vec<u8>> {
+        let key = ChunkCacheKey::new(source, compiler);
+        if let Some(data) = cache.get(&key) {
+            return Ok(data);
+        }
+        let data = compiler.compile(source)?;
+        cache.put(&key, &data);
+        Ok(data)
+    }
+
     /// Fetches compiled bytecode of this chunk from the cache.
     ///
     /// If not found, compiles the source code and stores it on the cache.
@@ -703,13 +885,22 @@ impl Chunk<'_> {
         let source = source.map_err(Error::runtime)?;
         let source = Self::expression_source(source);
 
-        // We don't need to compile source if no compiler options set
-        let source = self
-            .compiler
-            .as_ref()
-            .map(|c| c.compile(&source))
-            .transpose()?
-            .unwrap_or(source);
+        // We don't need to compile source if no compiler options, cache or code generation set
+        let verify = !self.trusted && self.verify;
+        let codegen = self.is_codegen_enabled(verify);
+        let source = match (&self.cache, &self.compiler) {
+            (Some(cache), compiler) => {
+                let compiler = compiler.as_ref().unwrap_or(const { &Compiler::new() });
+                Self::compile_cached(&**cache, compiler, &source)?
+            }
+            (None, Some(compiler)) => compiler.compile(&source)?,
+            (None, None) if self.needs_bytecode(codegen) => Compiler::new().compile(&source)?,
+            (None, None) => source,
+        };
+
+        if verify {
+            Self::verify_bytecode(&source)?;
+        }
 
         let name = Self::convert_name(self.name.clone())?;
         let env = match &self.env {
@@ -717,7 +908,22 @@ impl Chunk<'_> {
             Ok(None) => None,
             Err(err) => return Err(err.clone()),
         };
-        self.lua.lock().load_chunk(Some(&name), env, None, &source)
+        let native = if codegen {
+            Self::native_code(&self.lua, self.native, &source)
+        } else {
+            None
+        };
+        Self::load(&self.lua, &name, env, None, &source, native)
+    }
+
+    /// Checks binary chunk using the bytecode verifier before loading.
+    ///
+    /// Text chunks are ignored, they are compiled by Luau when loading.
+    fn verify_bytecode(source: &[u8]) -> Result<()> {
+        match source.first() {
+            Some(&b) if b < b'\t' => Bytecode::parse(source)?.verify(),
+            _ => Ok(()),
+        }
     }
 
     fn detect_mode(&self) -> ChunkMode {
@@ -0,0 +1,166 @@
+use std::string::String as StdString;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{Chunk, ChunkMode, Compiler};
+use crate::error::{Error, Result};
+use crate::state::Lua;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const BUNDLE_MAGIC: &[u8; 8] = b"ULUAB\x00\x00\x01";
+
+/// Length of a bundle signature in bytes.
+pub const BUNDLE_SIGNATURE_LEN: usize = 32;
+
+/// Compiled Luau bytecode together with metadata describing how it was produced.
+///
+/// Bundles are signed using HMAC-SHA256 with a secret key. The signature is detached: it is not
+/// part of the serialized bundle and must be stored or transferred alongside it.
+///
+/// # Examples
+///
+/// ```
+/// # use ulua::{Bundle, Compiler, Lua, Result};
+/// # fn main() -> Result<()> {
+/// let key = b"build secret";
+/// let bundle = Bundle::compile(&Compiler::new(), "@main.luau", "return 1 + 2")?;
+/// let (data, signature) = (bundle.to_bytes(), bundle.sign(key));
+///
+/// let lua = Lua::new();
+/// assert_eq!(lua.load_bundle(&data, &signature, key)?.eval::<i32>()?, 3);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Bundle {
+    /// Chunk name used when loading the bundle.
+    pub name: StdString,
+    /// Version of Luau that compiled the bytecode.
+    pub luau_version: StdString,
+    /// Compiler optimization level.
+    pub optimization_level: u8,
+    /// Compiler debug level.
+    pub debug_level: u8,
+    /// Compiler type information level.
+    pub type_info_level: u8,
+    /// Compiler coverage level.
+    pub coverage_level: u8,
+    /// Compiled bytecode.
+    pub bytecode: Vec<u8>,
+}
+
+impl Bundle {
+    /// Compiles the source code into a new bundle using the `compiler`.
+    pub fn compile(
+        compiler: &Compiler,
+        name: impl Into<StdString>,
+        source: impl AsRef<[u8]>,
+    ) -> Result<Self> {
+        Ok(Bundle {
+            name: name.into(),
+            luau_version: ffi::luau_version().unwrap_or_default().to_string(),
+            optimization_level: compiler.optimization_level,
+            debug_level: compiler.debug_level,
+            type_info_level: compiler.type_info_level,
+            coverage_level: compiler.coverage_level,
+            bytecode: compiler.compile(source)?,
+        })
+    }
+
+    /// Serializes the bundle into bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(64 + self.name.len() + self.bytecode.len());
+        data.extend_from_slice(BUNDLE_MAGIC);
+        data.extend_from_slice(&[
+            self.optimization_level,
+            self.debug_level,
+            self.type_info_level,
+            self.coverage_level,
+        ]);
+        for field in [self.luau_version.as_bytes(), self.name.as_bytes(), &self.bytecode] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field);
+        }
+        data
+    }
+
+    /// Deserializes a bundle from bytes, **without** checking its signature.
+    ///
+    /// Use [`Bundle::verify`] for bundles coming from untrusted sources.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let err = |msg: &str| Error::BytecodeError(format!("invalid bundle: {msg}"));
+        let mut data = data.strip_prefix(BUNDLE_MAGIC).ok_or_else(|| err("bad header"))?;
+        let levels = take(&mut data, 4).ok_or_else(|| err("unexpected end of data"))?;
+        let mut field = || {
+            let len = take(&mut data, 4).ok_or_else(|| err("unexpected end of data"))?;
+            let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+            take(&mut data, len).ok_or_else(|| err("unexpected end of data"))
+        };
+        let luau_version = field()?;
+        let name = field()?;
+        let bytecode = field()?.to_vec();
+        if !data.is_empty() {
+            return Err(err("trailing data"));
+        }
+        let to_string = |s: &[u8]| StdString::from_utf8(s.to_vec()).map_err(|_| err("invalid utf-8 string"));
+        Ok(Bundle {
+            name: to_string(name)?,
+            luau_version: to_string(luau_version)?,
+            optimization_level: levels[0],
+            debug_level: levels[1],
+            type_info_level: levels[2],
+            coverage_level: levels[3],
+            bytecode,
+        })
+    }
+
+    /// Computes the detached signature of the serialized bundle using the `key`.
+    pub fn sign(&self, key: &[u8]) -> [u8; BUNDLE_SIGNATURE_LEN] {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+        mac.update(&self.to_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Checks the `signature` of the serialized bundle using the `key` and deserializes it.
+    ///
+    /// Returns [`Error::BytecodeError`] if the signature does not match.
+    pub fn verify(data: &[u8], signature: &[u8], key: &[u8]) -> Result<Self> {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+        mac.update(data);
+        // Comparison is done in constant time
+        if mac.verify_slice(signature).is_err() {
+            return Err(Error::BytecodeError("bundle signature mismatch".into()));
+        }
+        Self::from_bytes(data)
+    }
+}
+
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if data.len() < n {
+        return None;
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Some(head)
+}
+
+impl Lua {
+    /// Loads a signed [`Bundle`] as a Lua chunk.
+    ///
+    /// The signature is checked using the `key` before the bytecode is handed to the [`Chunk`].
+    /// The chunk is loaded in [`ChunkMode::Binary`] mode. Signed bytecode is trusted: it is not
+    /// checked by the [bytecode verifier] and can be compiled to native code.
+    ///
+    /// [bytecode verifier]: crate::bytecode::Bytecode::verify
+    #[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+    #[track_caller]
+    pub fn load_bundle(&self, data: &[u8], signature: &[u8], key: &[u8]) -> Result<Chunk<'static>> {
+        let bundle = Bundle::verify(data, signature, key)?;
+        let chunk = self.load(bundle.bytecode).set_name(bundle.name);
+        Ok(chunk.set_mode(ChunkMode::Binary).set_trusted())
+    }
+}
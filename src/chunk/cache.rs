@@ -0,0 +1,318 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use super::{CompileConstant, Compiler};
+use crate::types::MaybeSend;
+
+/// A persistent storage for compiled Luau chunks.
+///
+/// When set using [`Lua::set_chunk_cache`], every text chunk loaded with [`Lua::load`] is looked up
+/// in the cache by [`ChunkCacheKey`] before compiling. On a miss, the chunk is compiled and the
+/// resulting bytecode is stored in the cache.
+///
+/// Implementations must be infallible from the caller perspective: any storage error should be
+/// treated as a cache miss.
+///
+/// [`Lua::set_chunk_cache`]: crate::Lua::set_chunk_cache
+/// [`Lua::load`]: crate::Lua::load
+pub trait ChunkCache: MaybeSend + 'static {
+    /// Returns the bytecode stored under the given key, if any.
+    fn get(&self, key: &ChunkCacheKey) -> Option<Vec<u8>>;
+
+    /// Stores the bytecode under the given key.
+    fn put(&self, key: &ChunkCacheKey, bytecode: &[u8]);
+}
+
+/// A key identifying a compiled chunk in a [`ChunkCache`].
+///
+/// The key is derived from the source code, all [`Compiler`] options and the Luau version,
+/// so changing any of them results in a different key.
+///
+/// The source code is identified by its length and SHA-256 digest, so different sources cannot
+/// be made to share a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkCacheKey {
+    source_len: u64,
+    source_hash: [u8; 32],
+    compiler_hash: u128,
+}
+
+impl ChunkCacheKey {
+    /// Computes the key for the given source code compiled using the `compiler`.
+    pub fn new(source: impl AsRef<[u8]>, compiler: &Compiler) -> Self {
+        let source = source.as_ref();
+        let source_hash = Sha256::digest(source).into();
+
+        let mut hasher = Fnv128::new();
+        hasher.write_str(ffi::luau_version().unwrap_or_default());
+        compiler.fingerprint(&mut hasher);
+        let compiler_hash = hasher.finish();
+
+        ChunkCacheKey {
+            source_len: source.len() as u64,
+            source_hash,
+            compiler_hash,
+        }
+    }
+}
+
+impl fmt::Display for ChunkCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.source_len)?;
+        self.source_hash.iter().try_for_each(|b| write!(f, "{b:02x}"))?;
+        write!(f, "{:032x}", self.compiler_hash)
+    }
+}
+
+impl Compiler {
+    fn fingerprint(&self, hasher: &mut Fnv128) {
+        hasher.write(&[
+            self.optimization_level,
+            self.debug_level,
+            self.type_info_level,
+            self.coverage_level,
+        ]);
+        for opt in [&self.vector_lib, &self.vector_ctor, &self.vector_type] {
+            hasher.write_opt_str(opt.as_deref());
+        }
+        for list in [
+            &self.mutable_globals,
+            &self.userdata_types,
+            &self.libraries_with_known_members,
+            &self.disabled_builtins,
+        ] {
+            hasher.write_u64(list.len() as u64);
+            list.iter().for_each(|s| hasher.write_str(s));
+        }
+
+        // Library constants are stored in a hash map, so sort them to get a stable order
+        let mut constants = (self.library_constants.iter().flatten()).collect::<Vec<_>>();
+        constants.sort_by_key(|&(key, _)| key);
+        hasher.write_u64(constants.len() as u64);
+        for ((lib, member), value) in constants {
+            hasher.write_str(lib);
+            hasher.write_str(member);
+            match value {
+                CompileConstant::Nil => hasher.write(&[0]),
+                CompileConstant::Boolean(b) => hasher.write(&[1, *b as u8]),
+                CompileConstant::Number(n) => {
+                    hasher.write(&[2]);
+                    hasher.write(&n.to_le_bytes());
+                }
+                CompileConstant::Vector(v) => {
+                    hasher.write(&[3]);
+                    v.0.iter().for_each(|x| hasher.write(&x.to_le_bytes()));
+                }
+                CompileConstant::String(s) => {
+                    hasher.write(&[4]);
+                    hasher.write_str(s);
+                }
+            }
+        }
+    }
+}
+
+/// A [`ChunkCache`] that stores compiled chunks as files in a directory.
+///
+/// Each entry is stored in a separate file named after the [`ChunkCacheKey`]. Entries are
+/// protected by a checksum, so truncated or corrupted files are ignored.
+///
+/// If a maximum size is set, the least recently used entries are evicted once the total size of
+/// the cache exceeds it.
+///
+/// The cache directory must be trusted, unless unverified bytecode is refused using
+/// [`Lua::set_allow_unverified_bytecode`].
+///
+/// [`Lua::set_allow_unverified_bytecode`]: crate::Lua::set_allow_unverified_bytecode
+#[derive(Debug)]
+pub struct FileChunkCache {
+    dir: PathBuf,
+    max_size: Option<u64>,
+    lock: Mutex<()>,
+}
+
+const FILE_MAGIC: &[u8; 8] = b"ULUAC\x00\x00\x01";
+const FILE_EXT: &str = "luauc";
+
+impl FileChunkCache {
+    /// Creates a new cache in the given directory, creating it if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FileChunkCache {
+            dir,
+            max_size: None,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Sets the maximum total size (in bytes) of cached entries.
+    ///
+    /// By default the cache is unbounded.
+    #[must_use]
+    pub fn set_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Returns the cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes all cached entries.
+    pub fn clear(&self) -> io::Result<()> {
+        let _guard = self.lock.lock();
+        for (path, _, _) in self.entries()? {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &ChunkCacheKey) -> PathBuf {
+        self.dir.join(format!("{key}.{FILE_EXT}"))
+    }
+
+    fn entries(&self) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(FILE_EXT) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((path, metadata.len(), modified));
+        }
+        Ok(entries)
+    }
+
+    fn evict(&self, max_size: u64) -> io::Result<()> {
+        let mut entries = self.entries()?;
+        let mut total_size = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+        if total_size <= max_size {
+            return Ok(());
+        }
+        // Remove least recently used entries first
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in entries {
+            if total_size <= max_size {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total_size -= size;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_entry(&self, key: &ChunkCacheKey, bytecode: &[u8]) -> io::Result<()> {
+        let path = self.entry_path(key);
+        // Write to a temporary file first and then atomically move it into place.
+        // The name must be unique across processes and concurrent writers sharing the directory.
+        static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = path.with_extension(format!("{}.{n}.tmp", std::process::id()));
+        let result = (|| {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(FILE_MAGIC)?;
+            file.write_all(&checksum(bytecode).to_le_bytes())?;
+            file.write_all(bytecode)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &path)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+}
+
+impl ChunkCache for FileChunkCache {
+    fn get(&self, key: &ChunkCacheKey) -> Option<Vec<u8>> {
+        let path = self.entry_path(key);
+        let data = fs::read(&path).ok()?;
+        let payload = data.strip_prefix(FILE_MAGIC)?;
+        if payload.len() < 16 {
+            return None;
+        }
+        let (sum, bytecode) = payload.split_at(16);
+        if u128::from_le_bytes(sum.try_into().unwrap()) != checksum(bytecode) {
+            return None;
+        }
+        // Update modification time to track recently used entries
+        if self.max_size.is_some() {
+            if let Ok(file) = File::options().append(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+        }
+        Some(bytecode.to_vec())
+    }
+
+    fn put(&self, key: &ChunkCacheKey, bytecode: &[u8]) {
+        let _guard = self.lock.lock();
+        if self.write_entry(key, bytecode).is_ok() {
+            if let Some(max_size) = self.max_size {
+                let _ = self.evict(max_size);
+            }
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u128 {
+    let mut hasher = Fnv128::new();
+    hasher.write(data);
+    hasher.finish()
+}
+
+/// 128-bit FNV-1a hasher.
+///
+/// Unlike `std` hashers, the output is stable across Rust versions and platforms.
+struct Fnv128(u128);
+
+impl Fnv128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    const fn new() -> Self {
+        Fnv128(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u128;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_u64(s.len() as u64);
+        self.write(s.as_bytes());
+    }
+
+    fn write_opt_str(&mut self, s: Option<&str>) {
+        match s {
+            Some(s) => {
+                self.write(&[1]);
+                self.write_str(s);
+            }
+            None => self.write(&[0]),
+        }
+    }
+
+    const fn finish(&self) -> u128 {
+        self.0
+    }
+}
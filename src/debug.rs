@@ -3,9 +3,12 @@ use std::os::raw::c_int;
 
 use ffi::{lua_Debug, lua_State};
 
+use crate::error::Result;
 use crate::function::Function;
 use crate::state::RawLua;
-use crate::util::{assert_stack, linenumber_to_usize, ptr_to_lossy_str, ptr_to_str, StackGuard};
+use crate::traits::IntoLua;
+use crate::util::{assert_stack, check_stack, linenumber_to_usize, ptr_to_lossy_str, ptr_to_str, StackGuard};
+use crate::value::Value;
 
 /// Contains information about currently executing Lua code.
 ///
@@ -115,6 +118,71 @@ impl<'a> Debug<'a> {
             stack
         }
     }
+
+    /// Returns names and values of the local variables that are active in the function.
+    ///
+    /// Local variables are listed in the order of their declaration. Luau records their names
+    /// only when a chunk is compiled with debug level 2 (see [`Compiler::set_debug_level`]),
+    /// otherwise the list is empty.
+    ///
+    /// [`Compiler::set_debug_level`]: crate::chunk::Compiler::set_debug_level
+    pub fn locals(&self) -> Vec<(String, Value)> {
+        let mut locals = Vec::new();
+        unsafe {
+            let _sg = StackGuard::new(self.state);
+            assert_stack(self.state, 1);
+
+            for n in 1.. {
+                let name = ffi::lua_getlocal(self.state, self.level, n);
+                let Some(name) = ptr_to_lossy_str(name) else {
+                    break;
+                };
+                locals.push((name.into_owned(), self.lua.pop_value(self.state)));
+            }
+        }
+        locals
+    }
+
+    /// Sets the value of an active local variable.
+    ///
+    /// If several active variables have the same name, the innermost one is changed.
+    /// Returns `false` if there is no such variable.
+    pub fn set_local(&self, name: &str, value: impl IntoLua) -> Result<bool> {
+        unsafe {
+            let _sg = StackGuard::new(self.state);
+            check_stack(self.state, 3)?;
+
+            let mut index = None;
+            for n in 1.. {
+                let local_name = ffi::lua_getlocal(self.state, self.level, n);
+                if local_name.is_null() {
+                    break;
+                }
+                ffi::lua_pop(self.state, 1);
+                if ptr_to_str(local_name) == Some(name) {
+                    index = Some(n);
+                }
+            }
+            let Some(n) = index else {
+                return Ok(false);
+            };
+
+            self.lua.push(self.state, value)?;
+            ffi::lua_setlocal(self.state, self.level, n);
+            Ok(true)
+        }
+    }
+
+    /// Returns names and values of the function arguments.
+    ///
+    /// Extra arguments passed to a vararg function are not included. Like [`Debug::locals`], this
+    /// requires debug level 2.
+    pub fn arguments(&self) -> Vec<(String, Value)> {
+        let num_params = self.stack().num_params as usize;
+        let mut arguments = self.locals();
+        arguments.truncate(num_params);
+        arguments
+    }
 }
 
 /// Represents a specific event that triggered the hook.
@@ -2,6 +2,7 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::io::Error as IoError;
 use std::net::AddrParseError;
+use std::ops::Range;
 use std::result::Result as StdResult;
 use std::str::Utf8Error;
 use std::string::String as StdString;
@@ -28,6 +29,11 @@ pub enum Error {
         /// This is useful for implementing REPLs as they can query the user for more input if this
         /// is set.
         incomplete_input: bool,
+        /// Structured information about the error location(s).
+        ///
+        /// Luau stops at the first error, so this usually contains a single diagnostic. It can be
+        /// empty if the error does not refer to a source location (eg. invalid chunk mode).
+        diagnostics: Vec<SyntaxDiagnostic>,
     },
     /// Lua runtime error, aka `LUA_ERRRUN`.
     ///
@@ -42,6 +48,25 @@ pub enum Error {
     MemoryError(StdString),
     /// Potentially unsafe action in safe mode.
     SafetyError(StdString),
+    /// Malformed or unsupported Luau bytecode.
+    ///
+    /// Returned by [`Bytecode::parse`] when the bytecode blob cannot be decoded, or when bytecode
+    /// fails verification or a bundle signature check.
+    ///
+    /// [`Bytecode::parse`]: crate::bytecode::Bytecode::parse
+    BytecodeError(StdString),
+    /// A module was required while it was being loaded.
+    ///
+    /// Returned by `require` functions (see [`Lua::create_require_function`]) instead of
+    /// recursing until the stack overflows.
+    ///
+    /// [`Lua::create_require_function`]: crate::Lua::create_require_function
+    RequireCycle {
+        /// Cache keys of the modules in the cycle, in the order they were required.
+        ///
+        /// The first and the last items refer to the same module.
+        chain: Vec<StdString>,
+    },
     /// Memory control is not available.
     ///
     /// This error can only happen when Lua state was not created by us and does not have the
@@ -204,6 +229,54 @@ pub enum Error {
 /// A specialized `Result` type used by `ulua`'s API.
 pub type Result<T> = StdResult<T, Error>;
 
+/// A single diagnostic produced while parsing or compiling Luau source code.
+///
+/// See [`Error::SyntaxError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxDiagnostic {
+    /// Name of the chunk where the error occurred, if known.
+    ///
+    /// For chunks loaded using [`Lua::load`] this is the chunk name (see [`Chunk::set_name`]).
+    ///
+    /// [`Lua::load`]: crate::Lua::load
+    /// [`Chunk::set_name`]: crate::Chunk::set_name
+    pub chunk_name: Option<StdString>,
+    /// Line number (1-based).
+    pub line: usize,
+    /// Range of columns (1-based, in characters, end exclusive) of the offending token, if known.
+    ///
+    /// The Luau compiler reports only line numbers, so this is `None` for errors returned when
+    /// loading or compiling Luau source code. Errors returned by [`Ast::parse`] have columns.
+    ///
+    /// [`Ast::parse`]: crate::ast::Ast::parse
+    pub columns: Option<Range<usize>>,
+    /// Error message without location prefix.
+    pub message: StdString,
+}
+
+impl SyntaxDiagnostic {
+    /// Parses a Luau error message in the `<chunk>:<line>: <message>` format.
+    pub(crate) fn from_message(message: &str) -> Option<Self> {
+        let mut offset = 0;
+        while let Some(pos) = message[offset..].find(':') {
+            let start = offset + pos;
+            let rest = &message[start + 1..];
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            if digits > 0 && rest[digits..].starts_with(": ") {
+                let prefix = &message[..start];
+                return Some(SyntaxDiagnostic {
+                    chunk_name: (!prefix.is_empty()).then(|| prefix.to_string()),
+                    line: rest[..digits].parse().ok()?,
+                    columns: None,
+                    message: rest[digits + 2..].to_string(),
+                });
+            }
+            offset = start + 1;
+        }
+        None
+    }
+}
+
 #[cfg(not(tarpaulin_include))]
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -216,6 +289,10 @@ impl fmt::Display for Error {
             Error::SafetyError(msg) => {
                 write!(fmt, "safety error: {msg}")
             },
+            Error::BytecodeError(msg) => write!(fmt, "bytecode error: {msg}"),
+            Error::RequireCycle { chain } => {
+                write!(fmt, "require cycle detected: {}", chain.join(" -> "))
+            }
             Error::MemoryControlNotAvailable => {
                 write!(fmt, "memory control is not available")
             }
@@ -375,6 +452,16 @@ impl Error {
         }
     }
 
+    /// Fills in the chunk name of syntax error diagnostics.
+    pub(crate) fn with_chunk_name(mut self, name: &str) -> Self {
+        if let Error::SyntaxError { diagnostics, .. } = &mut self {
+            for diagnostic in diagnostics {
+                diagnostic.chunk_name = Some(name.to_string());
+            }
+        }
+        self
+    }
+
     pub(crate) fn bad_self_argument(to: &str, cause: Error) -> Self {
         Error::BadArgument {
             to: Some(to.to_string()),
@@ -306,6 +306,76 @@ impl Function {
         }
     }
 
+    /// Returns names and values of the function upvalues.
+    ///
+    /// Luau records names of upvalues only when a chunk is compiled with debug level 2
+    /// (see [`Compiler::set_debug_level`]), otherwise the names are empty. Upvalues of Rust/C
+    /// functions are not exposed.
+    ///
+    /// [`Compiler::set_debug_level`]: crate::chunk::Compiler::set_debug_level
+    pub fn upvalues(&self) -> Vec<(String, Value)> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        let mut upvalues = Vec::new();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 2);
+
+            lua.push_ref(&self.0, state);
+            if ffi::lua_iscfunction(state, -1) != 0 {
+                return upvalues;
+            }
+
+            for n in 1.. {
+                let name = ffi::lua_getupvalue(state, -1, n);
+                let Some(name) = ptr_to_lossy_str(name) else {
+                    break;
+                };
+                upvalues.push((name.into_owned(), lua.pop_value(state)));
+            }
+        }
+        upvalues
+    }
+
+    /// Sets the value of an upvalue of the function.
+    ///
+    /// Returns `false` if the function has no upvalue with the given name.
+    ///
+    /// This function does nothing for Rust/C functions.
+    pub fn set_upvalue(&self, name: &str, value: impl IntoLua) -> Result<bool> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 3)?;
+
+            lua.push_ref(&self.0, state);
+            if ffi::lua_iscfunction(state, -1) != 0 {
+                return Ok(false);
+            }
+
+            let mut index = None;
+            for n in 1.. {
+                let upvalue_name = ffi::lua_getupvalue(state, -1, n);
+                if upvalue_name.is_null() {
+                    break;
+                }
+                ffi::lua_pop(state, 1);
+                if ptr_to_str(upvalue_name) == Some(name) {
+                    index = Some(n);
+                    break;
+                }
+            }
+            let Some(n) = index else {
+                return Ok(false);
+            };
+
+            lua.push(state, value)?;
+            ffi::lua_setupvalue(state, -2, n);
+            Ok(true)
+        }
+    }
+
     /// Returns information about the function.
     ///
     /// Corresponds to the `>Sn` what mask for [`lua_getinfo`] when applied to the function.
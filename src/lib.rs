@@ -94,6 +94,8 @@ mod util;
 mod value;
 mod vector;
 
+pub mod ast;
+pub mod bytecode;
 pub mod prelude;
 
 pub use bstr::BString;
@@ -101,7 +103,7 @@ pub use ffi::{self, lua_CFunction, lua_State};
 
 pub use crate::chunk::{AsChunk, Chunk, ChunkMode};
 pub use crate::debug::{Debug, DebugEvent, DebugNames, DebugSource, DebugStack};
-pub use crate::error::{Error, ErrorContext, ExternalError, ExternalResult, Result};
+pub use crate::error::{Error, ErrorContext, ExternalError, ExternalResult, Result, SyntaxDiagnostic};
 pub use crate::function::{Function, FunctionInfo};
 pub use crate::multi::{MultiValue, Variadic};
 pub use crate::scope::Scope;
@@ -124,15 +126,27 @@ pub use crate::value::{Nil, Value};
 
 pub use crate::{
     buffer::Buffer,
-    chunk::{CompileConstant, Compiler},
+    chunk::{ChunkCache, ChunkCacheKey, CompileConstant, Compiler, FileChunkCache},
     function::CoverageInfo,
-    luau::{NavigateError, Require, TextRequirer},
+    luau::{
+        ArchiveRequirer, BreakReason, DebugAction, Debugger, HostModules, HotReloader, LanguageMode,
+        LoadedModule, LuauRc, MemoryRequirer, ModuleArchive, ModuleGraph, ModuleReload, NativeCodeStats,
+        NavigateError, RemoteDebugger, Require, RequirePolicy, RequireRequest, TextRequirer,
+    },
     vector::Vector,
 };
 
+#[cfg(feature = "bundle")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bundle")))]
+pub use crate::chunk::{Bundle, BUNDLE_SIGNATURE_LEN};
+
+#[cfg(feature = "dap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dap")))]
+pub use crate::luau::{DapServer, DapSession};
+
 #[cfg(feature = "async")]
 #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
-pub use crate::{thread::AsyncThread, traits::LuaNativeAsyncFn};
+pub use crate::{luau::AsyncRequire, thread::AsyncThread, traits::LuaNativeAsyncFn};
 
 #[cfg(feature = "serde")]
 #[doc(inline)]
@@ -171,37 +185,89 @@ extern crate ulua_macros;
 /// }
 /// ```
 ///
+/// The chunk is compiled by the Luau compiler during macro expansion, so syntax errors are reported
+/// as Rust compile errors.
+///
+/// ## Embedding bytecode
+///
+/// With the `#![bytecode]` attribute at the beginning of the chunk, the compiled bytecode is
+/// embedded instead of the source code, and no compilation happens at runtime.
+/// The bytecode is compiled with default [`Compiler`] options, custom compiler set for the chunk
+/// is ignored.
+///
+/// ```
+/// use ulua::{Lua, Result, chunk};
+///
+/// fn main() -> Result<()> {
+///     let lua = Lua::new();
+///     let sum: i32 = lua.load(chunk! {
+///         #![bytecode]
+///         return 1 + 2
+///     }).eval()?;
+///     assert_eq!(sum, 3);
+///     Ok(())
+/// }
+/// ```
+///
 /// ## Syntax issues
 ///
-/// Since the Rust tokenizer will tokenize Lua code, this imposes some restrictions.
-/// The main thing to remember is:
+/// The Lua code is taken from the original source text of the macro invocation, so comments
+/// (`--`), the `//` (floor division) operator and escape sequences are kept as written, and line
+/// numbers in error messages match the lines of the chunk (counting from the line with the opening
+/// brace).
+///
+/// However, the code must still be accepted by the Rust tokenizer, which imposes some restrictions:
 ///
 /// - Use double quoted strings (`""`) instead of single quoted strings (`''`).
 ///
 ///   (Single quoted strings only work if they contain a single character, since in Rust,
 ///   `'a'` is a character literal).
 ///
-/// - Using Lua comments `--` is not desirable in **stable** Rust and can have bad side effects.
-///
-///   This is because procedural macros have Line/Column information available only in
-///   **nightly** Rust. Instead, Lua chunks represented as a big single line of code in stable Rust.
+/// - Interpolated strings (`` `...` ``) are not supported.
 ///
-///   As workaround, Rust comments `//` can be used.
+/// - Comments must not contain unbalanced brackets or quotes, as they are tokenized too.
 ///
-/// Other minor limitations:
+/// - The `//` operator comments out the rest of the line for Rust, so the closing brace of the
+///   macro must not be on the same line.
 ///
-/// - Certain escape codes in string literals don't work. (Specifically: `\a`, `\b`, `\f`, `\v`,
-///   `\123` (octal escape codes), `\u`, and `\U`).
+/// - Escape codes in string literals must be valid in Rust: `\\`, `\n`, `\t`, `\r`, `\"`,
+///   `\0`, `\x00`-`\x7F` and `\u{...}`. Lua escapes `\a`, `\b`, `\f`, `\v` and decimal escapes
+///   (e.g. `\123`) are rejected.
 ///
-///   These are accepted: : `\\`, `\n`, `\t`, `\r`, `\xAB` (hex escape codes), and `\0`.
+/// These restrictions cannot be lifted by the macro: the Rust compiler tokenizes the macro input and
+/// reports such errors before the macro runs.
 ///
-/// - The `//` (floor division) operator is unusable, as its start a comment.
+/// If the macro is called from another macro, the original text is not available and the code is
+/// reconstructed from tokens. In this case Lua comments can have bad side effects and `//` is
+/// unusable.
 ///
 /// Everything else should work.
 #[cfg(feature = "macros")]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 pub use ulua_macros::chunk;
 
+/// Include a Luau script file as a precompiled chunk.
+///
+/// The file is read and compiled to bytecode at compile time, so syntax errors fail the build.
+/// The macro returns a type that implements [`AsChunk`], with the compiled bytecode and the
+/// `@path` chunk name.
+///
+/// The path is relative to the crate root (the directory containing `Cargo.toml`).
+/// Compiler options can be set after the path: `optimize`, `debug`, `type_info` and `coverage`
+/// levels (see [`Compiler`] for their meaning).
+///
+/// ```ignore
+/// use ulua::{Lua, Result, include_luau};
+///
+/// fn main() -> Result<()> {
+///     let lua = Lua::new();
+///     lua.load(include_luau!("scripts/main.luau", optimize = 2)).exec()
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use ulua_macros::include_luau;
+
 /// Derive [`FromLua`] for a Rust type.
 ///
 /// Current implementation generate code that takes [`UserData`] value, borrow it (of the Rust type)
@@ -0,0 +1,689 @@
+use std::collections::BTreeSet;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::debug::Debug;
+use crate::error::Result;
+use crate::function::Function;
+use crate::state::{callback_error_ext, interrupt_proc, ExtraData, Lua, RawLua, StateGuard, WeakLua};
+use crate::table::Table;
+use crate::types::{MaybeSend, XRc};
+use crate::util::{check_stack, StackGuard};
+
+#[cfg(feature = "dap")]
+pub use dap::{DapServer, DapSession};
+
+#[cfg(feature = "send")]
+type BreakCallback = XRc<dyn Fn(&Lua, &Debug, BreakReason) -> Result<DebugAction> + Send>;
+
+#[cfg(not(feature = "send"))]
+type BreakCallback = XRc<dyn Fn(&Lua, &Debug, BreakReason) -> Result<DebugAction>>;
+
+// Called with the chunk name, the requested line and the line where a breakpoint was set
+type BreakpointCallback = Box<dyn Fn(&str, usize, usize) + Send>;
+
+/// The reason why execution was stopped by a [`Debugger`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BreakReason {
+    /// A breakpoint was hit.
+    Breakpoint,
+    /// A step requested by [`DebugAction::StepIn`], [`DebugAction::StepOver`] or
+    /// [`DebugAction::StepOut`] was completed.
+    Step,
+    /// A pause was requested using [`RemoteDebugger::pause`].
+    Pause,
+}
+
+/// An action returned by the [`Debugger`] callback to resume execution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Continues execution until the next breakpoint.
+    #[default]
+    Continue,
+    /// Stops at the next line, entering called functions.
+    StepIn,
+    /// Stops at the next line of the current function, or when the function returns.
+    StepOver,
+    /// Stops when the current function returns.
+    StepOut,
+}
+
+#[derive(Clone, Copy)]
+struct Step {
+    thread: *mut ffi::lua_State,
+    action: DebugAction,
+    depth: c_int,
+    line: c_int,
+}
+
+pub(crate) struct DebuggerState {
+    // Functions of the loaded chunks (as weak keys) and their chunk names
+    chunks: Table,
+    breakpoints: BTreeSet<(String, usize)>,
+    // Lines where the breakpoints were set (after moving them to lines with code)
+    breakpoint_lines: BTreeSet<(String, c_int)>,
+    callback: Option<BreakCallback>,
+    breakpoint_callback: Option<BreakpointCallback>,
+    step: Option<Step>,
+    // Functions (and their chunk names) where the breakpoints used for stepping are set
+    step_functions: Vec<(Function, String)>,
+    in_callback: bool,
+    remote: RemoteDebugger,
+}
+
+/// A handle to control Luau code execution using breakpoints and stepping.
+///
+/// Breakpoints are set by chunk name (as passed to [`Chunk::set_name`]) and line. When a
+/// breakpoint is hit, the callback set by [`Debugger::on_break`] is called with the [`Debug`]
+/// frame of the running function, and decides how to resume execution by returning
+/// a [`DebugAction`]. Returning an error from the callback aborts the running code with that error.
+///
+/// Breakpoints apply only to chunks that are loaded while the debugger is attached. Such chunks
+/// are never compiled to native code, since native code cannot be stopped, and source chunks
+/// are compiled with full debug information (names of local variables).
+///
+/// Stepping follows the thread (coroutine) where execution was stopped. Stepping over or out of a
+/// function can stop only in the functions on the call stack of that thread, while stepping in
+/// can stop in any chunk. A step that is not completed when the code returns is completed when
+/// one of these functions runs next in the same thread.
+///
+/// See [`Lua::debugger`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use ulua::{DebugAction, Lua, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let debugger = lua.debugger();
+/// debugger.set_breakpoint("@main.luau", 2)?;
+///
+/// let lines = Arc::new(Mutex::new(Vec::new()));
+/// let lines2 = lines.clone();
+/// debugger.on_break(move |_, debug, _| {
+///     lines2.lock().unwrap().push(debug.current_line().unwrap());
+///     Ok(DebugAction::StepOver)
+/// });
+///
+/// let source = "local t = {}\ntable.insert(t, 1)\ntable.insert(t, 2)";
+/// lua.load(source).set_name("@main.luau").exec()?;
+/// assert_eq!(*lines.lock().unwrap(), vec![2, 3]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Chunk::set_name`]: crate::Chunk::set_name
+#[derive(Clone)]
+pub struct Debugger {
+    lua: WeakLua,
+}
+
+impl Debugger {
+    /// Sets a breakpoint at the given line of the chunk.
+    ///
+    /// If the line has no code, the breakpoint is moved to the next line that has.
+    pub fn set_breakpoint(&self, chunk_name: &str, line: usize) -> Result<()> {
+        let lua = self.lua.lock();
+        unsafe {
+            apply_remote(&lua)?;
+            add_breakpoint(&lua, chunk_name, line)
+        }
+    }
+
+    /// Replaces all breakpoints of the chunk with breakpoints at the given lines.
+    pub fn set_breakpoints(&self, chunk_name: &str, lines: &[usize]) -> Result<()> {
+        let lua = self.lua.lock();
+        unsafe {
+            apply_remote(&lua)?;
+            replace_breakpoints(&lua, chunk_name, lines)
+        }
+    }
+
+    /// Clears a breakpoint previously set at the given line of the chunk.
+    ///
+    /// Returns `true` if the breakpoint was set.
+    pub fn clear_breakpoint(&self, chunk_name: &str, line: usize) -> Result<bool> {
+        let lua = self.lua.lock();
+        unsafe {
+            apply_remote(&lua)?;
+            remove_breakpoint(&lua, chunk_name, line)
+        }
+    }
+
+    /// Clears all breakpoints.
+    pub fn clear_breakpoints(&self) -> Result<()> {
+        for (chunk_name, line) in self.breakpoints() {
+            self.clear_breakpoint(&chunk_name, line)?;
+        }
+        Ok(())
+    }
+
+    /// Returns all breakpoints as `(chunk_name, line)` pairs.
+    ///
+    /// Breakpoints set using [`RemoteDebugger`] are returned after they are applied.
+    pub fn breakpoints(&self) -> Vec<(String, usize)> {
+        let lua = self.lua.lock();
+        match unsafe { debugger_state(&lua) } {
+            Some(debugger) => debugger.breakpoints.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sets a callback that is called when execution is stopped.
+    ///
+    /// Breakpoints and steps are ignored while the callback is running, so it can safely run
+    /// Lua code (e.g. to evaluate expressions).
+    pub fn on_break<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, &Debug, BreakReason) -> Result<DebugAction> + MaybeSend + 'static,
+    {
+        let lua = self.lua.lock();
+        if let Some(debugger) = unsafe { debugger_state(&lua) } {
+            debugger.callback = Some(XRc::new(callback));
+        }
+    }
+
+    /// Sets a callback that is called when a breakpoint is set in a loaded chunk.
+    #[cfg(feature = "dap")]
+    fn on_breakpoint_set(&self, callback: impl Fn(&str, usize, usize) + Send + 'static) {
+        let lua = self.lua.lock();
+        if let Some(debugger) = unsafe { debugger_state(&lua) } {
+            debugger.breakpoint_callback = Some(Box::new(callback));
+        }
+    }
+
+    /// Returns a [`RemoteDebugger`] handle that can be used from other threads.
+    pub fn remote(&self) -> RemoteDebugger {
+        let lua = self.lua.lock();
+        match unsafe { debugger_state(&lua) } {
+            Some(debugger) => debugger.remote.clone(),
+            None => RemoteDebugger::default(),
+        }
+    }
+
+    /// Returns `true` if the debugger is attached to the Lua state.
+    pub fn is_attached(&self) -> bool {
+        let lua = self.lua.lock();
+        unsafe { debugger_state(&lua).is_some() }
+    }
+
+    /// Detaches the debugger from the Lua state, clearing all breakpoints.
+    ///
+    /// Methods of the detached handle have no effect. The debugger can be attached again using
+    /// [`Lua::debugger`].
+    pub fn detach(&self) -> Result<()> {
+        let lua = self.lua.lock();
+        unsafe {
+            if let Some(debugger) = debugger_state(&lua) {
+                debugger.breakpoints.clear();
+                clear_step_breakpoints(&lua)?;
+            }
+            (*lua.extra()).debugger = None;
+            let callbacks = ffi::lua_callbacks(lua.main_state());
+            (*callbacks).debugbreak = None;
+            if (*lua.extra()).interrupt_callback.is_none() {
+                (*callbacks).interrupt = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A handle to a [`Debugger`] that can be used from any thread, including while Lua code is
+/// running in another thread.
+///
+/// Requests are applied by the thread that runs Lua code, at the next function call, loop
+/// iteration or chunk load. Requests made when the debugger is detached have no effect.
+///
+/// See [`Debugger::remote`].
+#[derive(Clone, Default)]
+pub struct RemoteDebugger {
+    shared: Arc<RemoteState>,
+}
+
+#[derive(Default)]
+struct RemoteState {
+    pending: AtomicBool,
+    pause: AtomicBool,
+    breakpoints: Mutex<Vec<(String, Vec<usize>)>>,
+}
+
+impl RemoteDebugger {
+    /// Requests to stop the running code, calling the [`Debugger::on_break`] callback with
+    /// [`BreakReason::Pause`].
+    ///
+    /// If no code is running, execution is stopped when Lua code runs next.
+    pub fn pause(&self) {
+        self.shared.pause.store(true, Ordering::Relaxed);
+        self.shared.pending.store(true, Ordering::Release);
+    }
+
+    /// Replaces all breakpoints of the chunk with breakpoints at the given lines.
+    ///
+    /// See [`Debugger::set_breakpoints`].
+    pub fn set_breakpoints(&self, chunk_name: &str, lines: &[usize]) {
+        let mut breakpoints = self.shared.breakpoints.lock();
+        breakpoints.push((chunk_name.to_string(), lines.to_vec()));
+        self.shared.pending.store(true, Ordering::Release);
+    }
+}
+
+impl Lua {
+    /// Attaches a [`Debugger`] to the Lua state, or returns a handle to the attached one.
+    pub fn debugger(&self) -> Debugger {
+        let lua = self.lock();
+        unsafe {
+            if (*lua.extra()).debugger.is_none() {
+                let chunks = ulua_expect!(create_chunks_table(self), "cannot create debugger table");
+                (*lua.extra()).debugger = Some(DebuggerState {
+                    chunks,
+                    breakpoints: BTreeSet::new(),
+                    breakpoint_lines: BTreeSet::new(),
+                    callback: None,
+                    breakpoint_callback: None,
+                    step: None,
+                    step_functions: Vec::new(),
+                    in_callback: false,
+                    remote: RemoteDebugger::default(),
+                });
+                let callbacks = ffi::lua_callbacks(lua.main_state());
+                (*callbacks).debugbreak = Some(debugbreak_proc);
+                // Interrupts are used to pause execution
+                (*callbacks).interrupt = Some(interrupt_proc);
+            }
+        }
+        Debugger { lua: self.weak() }
+    }
+}
+
+fn create_chunks_table(lua: &Lua) -> Result<Table> {
+    let chunks = lua.create_table()?;
+    chunks.set_metatable(Some(lua.create_table_from([("__mode", "k")])?))?;
+    Ok(chunks)
+}
+
+#[inline]
+unsafe fn debugger_state<'a>(lua: &RawLua) -> Option<&'a mut DebuggerState> {
+    (*lua.extra()).debugger.as_mut()
+}
+
+unsafe fn chunk_functions(lua: &RawLua) -> Result<Vec<(Function, String)>> {
+    match debugger_state(lua) {
+        Some(debugger) => debugger.chunks.clone().pairs().collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+unsafe fn add_breakpoint(lua: &RawLua, chunk_name: &str, line: usize) -> Result<()> {
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(());
+    };
+    debugger.breakpoints.insert((chunk_name.to_string(), line));
+    for (function, name) in chunk_functions(lua)? {
+        if name == chunk_name {
+            set_breakpoint_line(lua, &function, &name, line, true)?;
+        }
+    }
+    Ok(())
+}
+
+unsafe fn remove_breakpoint(lua: &RawLua, chunk_name: &str, line: usize) -> Result<bool> {
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(false);
+    };
+    if !debugger.breakpoints.remove(&(chunk_name.to_string(), line)) {
+        return Ok(false);
+    }
+    for (function, name) in chunk_functions(lua)? {
+        if name == chunk_name {
+            set_breakpoint_line(lua, &function, &name, line, false)?;
+            // Other breakpoints could be moved to the same line
+            set_chunk_breakpoints(lua, &function, &name)?;
+        }
+    }
+    Ok(true)
+}
+
+unsafe fn replace_breakpoints(lua: &RawLua, chunk_name: &str, lines: &[usize]) -> Result<()> {
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(());
+    };
+    let old_lines = (debugger.breakpoints.iter())
+        .filter(|(name, line)| name == chunk_name && !lines.contains(line))
+        .map(|&(_, line)| line)
+        .collect::<Vec<_>>();
+    for line in old_lines {
+        remove_breakpoint(lua, chunk_name, line)?;
+    }
+    for &line in lines {
+        add_breakpoint(lua, chunk_name, line)?;
+    }
+    Ok(())
+}
+
+/// Applies breakpoints set using [`RemoteDebugger`].
+unsafe fn apply_remote(lua: &RawLua) -> Result<()> {
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(());
+    };
+    let requests = mem::take(&mut *debugger.remote.shared.breakpoints.lock());
+    for (chunk_name, lines) in requests {
+        replace_breakpoints(lua, &chunk_name, &lines)?;
+    }
+    Ok(())
+}
+
+/// Enables or disables a breakpoint in the function, returning the line where it was set.
+unsafe fn function_breakpoint(
+    lua: &RawLua,
+    function: &Function,
+    line: c_int,
+    enabled: bool,
+) -> Result<c_int> {
+    let state = lua.state();
+    let _sg = StackGuard::new(state);
+    check_stack(state, 3)?;
+
+    lua.push_ref(&function.0, state);
+    protect_lua!(state, 1, 0, |state| {
+        ffi::lua_breakpoint(state, -1, line, enabled as c_int)
+    })
+}
+
+unsafe fn set_breakpoint_line(
+    lua: &RawLua,
+    function: &Function,
+    chunk_name: &str,
+    line: usize,
+    enabled: bool,
+) -> Result<()> {
+    let target = function_breakpoint(lua, function, line.min(c_int::MAX as usize) as c_int, enabled)?;
+    if let Some(debugger) = debugger_state(lua).filter(|_| target >= 0) {
+        let key = (chunk_name.to_string(), target);
+        match enabled {
+            true => debugger.breakpoint_lines.insert(key),
+            false => debugger.breakpoint_lines.remove(&key),
+        };
+        if let Some(callback) = debugger.breakpoint_callback.as_ref().filter(|_| enabled) {
+            callback(chunk_name, line, target as usize);
+        }
+    }
+    Ok(())
+}
+
+/// Sets all breakpoints of the chunk in the function.
+unsafe fn set_chunk_breakpoints(lua: &RawLua, function: &Function, chunk_name: &str) -> Result<()> {
+    let lines = match debugger_state(lua) {
+        Some(debugger) => (debugger.breakpoints.iter())
+            .filter(|(name, _)| name == chunk_name)
+            .map(|&(_, line)| line)
+            .collect::<Vec<_>>(),
+        None => return Ok(()),
+    };
+    for line in lines {
+        set_breakpoint_line(lua, function, chunk_name, line, true)?;
+    }
+    Ok(())
+}
+
+/// Enables or disables breakpoints on every line of the function, that are used for stepping.
+///
+/// Luau supports single stepping only when the VM is entered, so steps are implemented using
+/// breakpoints instead. Disabling them restores the user breakpoints.
+unsafe fn set_function_step_breakpoints(
+    lua: &RawLua,
+    function: &Function,
+    chunk_name: &str,
+    enabled: bool,
+) -> Result<()> {
+    let mut line = 1;
+    loop {
+        let target = function_breakpoint(lua, function, line, enabled)?;
+        if target < 0 || target == c_int::MAX {
+            break;
+        }
+        line = target + 1;
+    }
+    if !enabled {
+        set_chunk_breakpoints(lua, function, chunk_name)?;
+    }
+    Ok(())
+}
+
+/// Sets breakpoints used for the step in the thread, replacing the ones of the previous step.
+///
+/// Stepping over or out of a function completes in a function on the call stack, so only these
+/// functions get the breakpoints. Stepping in can enter any function, so all chunks get them.
+unsafe fn set_step_breakpoints(lua: &RawLua, state: *mut ffi::lua_State, action: DebugAction) -> Result<()> {
+    clear_step_breakpoints(lua)?;
+    let functions = match action {
+        DebugAction::StepIn => chunk_functions(lua)?,
+        _ => stack_functions(lua, state)?,
+    };
+    for (function, name) in &functions {
+        set_function_step_breakpoints(lua, function, name, true)?;
+    }
+    if let Some(debugger) = debugger_state(lua) {
+        debugger.step_functions = functions;
+    }
+    Ok(())
+}
+
+unsafe fn clear_step_breakpoints(lua: &RawLua) -> Result<()> {
+    let functions = match debugger_state(lua) {
+        Some(debugger) => mem::take(&mut debugger.step_functions),
+        None => return Ok(()),
+    };
+    for (function, name) in &functions {
+        set_function_step_breakpoints(lua, function, name, false)?;
+    }
+    Ok(())
+}
+
+/// Returns functions of the loaded chunks that are on the call stack of the thread.
+unsafe fn stack_functions(lua: &RawLua, state: *mut ffi::lua_State) -> Result<Vec<(Function, String)>> {
+    let chunk_names = (chunk_functions(lua)?.into_iter())
+        .map(|(_, name)| name)
+        .collect::<BTreeSet<_>>();
+
+    let _sg = StackGuard::new(state);
+    check_stack(state, 2)?;
+    let mut functions = Vec::new();
+    let mut pointers = Vec::new();
+    let mut ar: ffi::lua_Debug = mem::zeroed();
+    for level in 0.. {
+        if ffi::lua_getinfo(state, level, cstr!("sf"), &mut ar) == 0 {
+            break;
+        }
+        let pointer = ffi::lua_topointer(state, -1);
+        let function = Function(lua.pop_ref());
+        if CStr::from_ptr(ar.what) == c"C" || ar.source.is_null() || pointers.contains(&pointer) {
+            continue;
+        }
+        let chunk_name = CStr::from_ptr(ar.source).to_string_lossy();
+        if chunk_names.contains(&*chunk_name) {
+            pointers.push(pointer);
+            functions.push((function, chunk_name.into_owned()));
+        }
+    }
+    Ok(functions)
+}
+
+/// Records a chunk loaded while the debugger is attached, and sets its breakpoints.
+pub(crate) unsafe fn track_chunk(lua: &RawLua, function: &Function, chunk_name: &CStr) -> Result<()> {
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(());
+    };
+    let chunk_name = chunk_name.to_string_lossy();
+    debugger.chunks.raw_set(function, &*chunk_name)?;
+    if debugger
+        .step
+        .is_some_and(|step| step.action == DebugAction::StepIn)
+    {
+        set_function_step_breakpoints(lua, function, &chunk_name, true)?;
+        (debugger.step_functions).push((function.clone(), chunk_name.to_string()));
+    }
+    apply_remote(lua)?;
+    set_chunk_breakpoints(lua, function, &chunk_name)
+}
+
+/// Handles requests made using [`RemoteDebugger`], called from the interrupt callback.
+pub(crate) unsafe fn debugger_interrupt(state: *mut ffi::lua_State) {
+    let extra = ExtraData::get(state);
+    let Some(debugger) = (*extra).debugger.as_ref() else {
+        return;
+    };
+    let remote = &debugger.remote.shared;
+    if debugger.in_callback || !remote.pending.swap(false, Ordering::Acquire) {
+        return;
+    }
+
+    if remote.pause.load(Ordering::Relaxed) {
+        let mut ar: ffi::lua_Debug = mem::zeroed();
+        if ffi::lua_getinfo(state, 0, cstr!("l"), &mut ar) != 0 && ar.currentline >= 0 {
+            stop(state, BreakReason::Pause);
+            return;
+        }
+        // Interrupted in a C function, try again later
+        remote.pending.store(true, Ordering::Relaxed);
+    }
+
+    let rawlua = (*extra).raw_lua();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _guard = StateGuard::new(rawlua, state);
+        apply_remote(rawlua)
+    }));
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => callback_error_ext(state, extra, false, move |_, _| Err::<(), _>(err)),
+        Err(panic) => callback_error_ext(state, extra, false, move |_, _| -> Result<()> {
+            resume_unwind(panic)
+        }),
+    }
+}
+
+unsafe extern "C-unwind" fn debugbreak_proc(state: *mut ffi::lua_State, ar: *mut ffi::lua_Debug) {
+    let extra = ExtraData::get(state);
+    let Some(debugger) = (*extra).debugger.as_ref() else {
+        return;
+    };
+    if debugger.in_callback {
+        return;
+    }
+
+    let line = (*ar).currentline;
+    let reason = match debugger.step {
+        Some(step) if step.thread == state && step.is_completed(ffi::lua_stackdepth(state), line) => {
+            BreakReason::Step
+        }
+        // Breakpoint used for stepping
+        Some(_) if !is_breakpoint(state, debugger, line) => return,
+        _ => BreakReason::Breakpoint,
+    };
+    stop(state, reason);
+}
+
+impl Step {
+    fn is_completed(&self, depth: c_int, line: c_int) -> bool {
+        match self.action {
+            DebugAction::Continue => false,
+            DebugAction::StepIn => depth != self.depth || line != self.line,
+            DebugAction::StepOver => depth < self.depth || (depth == self.depth && line != self.line),
+            DebugAction::StepOut => depth < self.depth,
+        }
+    }
+}
+
+unsafe fn is_breakpoint(state: *mut ffi::lua_State, debugger: &DebuggerState, line: c_int) -> bool {
+    let mut ar: ffi::lua_Debug = mem::zeroed();
+    if ffi::lua_getinfo(state, 0, cstr!("s"), &mut ar) == 0 || ar.source.is_null() {
+        return false;
+    }
+    let chunk_name = CStr::from_ptr(ar.source).to_string_lossy();
+    (debugger.breakpoint_lines).contains(&(chunk_name.into_owned(), line))
+}
+
+unsafe fn stop(state: *mut ffi::lua_State, reason: BreakReason) {
+    let extra = ExtraData::get(state);
+    let rawlua = (*extra).raw_lua();
+    let callback = match debugger_state(rawlua) {
+        Some(debugger) => {
+            // Stopping for any reason completes a requested pause
+            debugger.remote.shared.pause.store(false, Ordering::Relaxed);
+            debugger.in_callback = true;
+            debugger.callback.clone()
+        }
+        None => return,
+    };
+
+    // The callback is called directly (not using `callback_error_ext`), since the stack of the
+    // running function must stay intact while it's inspected
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _guard = StateGuard::new(rawlua, state);
+        apply_remote(rawlua)?;
+        let action = match callback {
+            Some(callback) => {
+                let mut ar: ffi::lua_Debug = mem::zeroed();
+                ffi::lua_getinfo(state, 0, cstr!(""), &mut ar);
+                callback(rawlua.lua(), &Debug::new(rawlua, 0, &mut ar), reason)?
+            }
+            None => DebugAction::Continue,
+        };
+        resume(rawlua, state, action)
+    }));
+
+    // The debugger could be detached by the callback
+    if let Some(debugger) = debugger_state(rawlua) {
+        debugger.in_callback = false;
+    }
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => {
+            let _ = catch_unwind(AssertUnwindSafe(|| resume(rawlua, state, DebugAction::Continue)));
+            callback_error_ext(state, extra, false, move |_, _| Err::<(), _>(err));
+        }
+        Err(panic) => {
+            let _ = catch_unwind(AssertUnwindSafe(|| resume(rawlua, state, DebugAction::Continue)));
+            callback_error_ext(state, extra, false, move |_, _| -> Result<()> {
+                resume_unwind(panic)
+            });
+        }
+    }
+}
+
+/// Prepares the debugger to resume execution with the given action.
+unsafe fn resume(lua: &RawLua, state: *mut ffi::lua_State, action: DebugAction) -> Result<()> {
+    let _guard = StateGuard::new(lua, state);
+    apply_remote(lua)?;
+    let Some(debugger) = debugger_state(lua) else {
+        return Ok(());
+    };
+    if action == DebugAction::Continue {
+        if debugger.step.take().is_some() {
+            clear_step_breakpoints(lua)?;
+        }
+        return Ok(());
+    }
+
+    let mut ar: ffi::lua_Debug = mem::zeroed();
+    ffi::lua_getinfo(state, 0, cstr!("l"), &mut ar);
+    debugger.step = Some(Step {
+        thread: state,
+        action,
+        depth: ffi::lua_stackdepth(state),
+        line: ar.currentline,
+    });
+    // Breakpoints could be changed by the callback, so they are always set again
+    set_step_breakpoints(lua, state, action)
+}
+
+#[cfg(feature = "dap")]
+mod dap;
@@ -0,0 +1,645 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, BufReader, Read, Result as IoResult, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::{env, fmt, mem, thread};
+
+use parking_lot::{Condvar, Mutex};
+use serde_json::{json, Value as JsonValue};
+
+use super::{BreakReason, DebugAction, Debugger, RemoteDebugger};
+use crate::error::{Error, Result};
+use crate::multi::MultiValue;
+use crate::state::Lua;
+use crate::table::Table;
+use crate::value::Value;
+
+// Luau code is debugged as a single thread
+const THREAD_ID: i64 = 1;
+
+// Maximum size of a message body accepted from the client
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// A [Debug Adapter Protocol] server, that lets DAP clients (e.g. VS Code) debug Luau code.
+///
+/// The server attaches a [`Debugger`] to the Lua state and sets its [`Debugger::on_break`]
+/// callback. While the code is stopped, client requests (stack traces, variables, expression
+/// evaluation, stepping) are handled by the thread that runs the code. Breakpoints and pause
+/// requests are handled at any time.
+///
+/// Chunk names starting with `@` are mapped to file paths relative to the source root (the current
+/// directory by default). A breakpoint in file `<root>/src/main.luau` is set for the chunks named
+/// `@src/main.luau` and `@src/main`, and for `init` files also for the directory name, matching the
+/// chunk names used by [`TextRequirer`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use ulua::{DapServer, Lua, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let session = DapServer::new(&lua).listen("127.0.0.1:4711")?;
+/// session.wait_for_configuration();
+///
+/// lua.load(std::path::Path::new("main.luau")).exec()?;
+/// session.terminate();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [Debug Adapter Protocol]: https://microsoft.github.io/debug-adapter-protocol/
+/// [`TextRequirer`]: crate::TextRequirer
+pub struct DapServer {
+    debugger: Debugger,
+    source_root: Option<PathBuf>,
+}
+
+/// A connection of a DAP client to a [`DapServer`].
+pub struct DapSession {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    writer: Mutex<Box<dyn Write + Send>>,
+    seq: AtomicI64,
+    stopped: AtomicBool,
+    status: Mutex<Status>,
+    status_changed: Condvar,
+    remote: RemoteDebugger,
+    sources: SourceMap,
+    // Breakpoints set by the client, by chunk name and requested line
+    breakpoints: Mutex<BTreeMap<(String, usize), Breakpoint>>,
+    next_breakpoint_id: AtomicI64,
+}
+
+struct Breakpoint {
+    id: i64,
+    // Line where the breakpoint was set, once its chunk is loaded
+    line: Option<usize>,
+}
+
+#[derive(Default)]
+struct Status {
+    configured: bool,
+    disconnected: bool,
+}
+
+struct Request {
+    seq: i64,
+    command: String,
+    arguments: JsonValue,
+}
+
+impl DapServer {
+    /// Creates a new DAP server for the Lua state.
+    pub fn new(lua: &Lua) -> Self {
+        DapServer {
+            debugger: lua.debugger(),
+            source_root: None,
+        }
+    }
+
+    /// Sets the directory that chunk names starting with `@` are relative to.
+    pub fn with_source_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.source_root = Some(root.into());
+        self
+    }
+
+    /// Waits for a client to connect to the given (local) TCP address.
+    pub fn listen(self, addr: impl ToSocketAddrs) -> IoResult<DapSession> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        let writer = stream.try_clone()?;
+        Ok(self.connect(stream, writer))
+    }
+
+    /// Communicates with a client using the standard input and output.
+    pub fn connect_stdio(self) -> DapSession {
+        self.connect(io::stdin(), io::stdout())
+    }
+
+    /// Communicates with a client using the given reader and writer.
+    pub fn connect(
+        self,
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> DapSession {
+        let root = (self.source_root)
+            .or_else(|| env::current_dir().ok())
+            .unwrap_or_default();
+        let root = match root.is_relative() {
+            true => env::current_dir().map(|dir| dir.join(&root)).unwrap_or(root),
+            false => root,
+        };
+        let shared = Arc::new(Shared {
+            writer: Mutex::new(Box::new(writer)),
+            seq: AtomicI64::new(1),
+            stopped: AtomicBool::new(false),
+            status: Mutex::new(Status::default()),
+            status_changed: Condvar::new(),
+            remote: self.debugger.remote(),
+            sources: SourceMap { root },
+            breakpoints: Mutex::new(BTreeMap::new()),
+            next_breakpoint_id: AtomicI64::new(1),
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Mutex::new(receiver);
+        self.debugger.on_break({
+            let shared = shared.clone();
+            move |lua, _, reason| on_break(lua, &shared, &receiver, reason)
+        });
+        self.debugger.on_breakpoint_set({
+            let shared = shared.clone();
+            move |chunk_name, line, target| shared.breakpoint_set(chunk_name, line, target)
+        });
+
+        thread::spawn({
+            let shared = shared.clone();
+            move || read_loop(reader, &shared, sender)
+        });
+
+        DapSession { shared }
+    }
+}
+
+impl DapSession {
+    /// Blocks until the client finishes configuration (e.g. sets initial breakpoints).
+    ///
+    /// Returns `false` if the client has disconnected.
+    pub fn wait_for_configuration(&self) -> bool {
+        let mut status = self.shared.status.lock();
+        while !status.configured && !status.disconnected {
+            self.shared.status_changed.wait(&mut status);
+        }
+        !status.disconnected
+    }
+
+    /// Returns `true` if the client is connected.
+    pub fn is_connected(&self) -> bool {
+        !self.shared.status.lock().disconnected
+    }
+
+    /// Notifies the client that debugging has finished.
+    pub fn terminate(&self) {
+        self.shared.event("terminated", json!({}));
+    }
+}
+
+impl Shared {
+    fn send(&self, mut message: JsonValue) {
+        let mut writer = self.writer.lock();
+        message["seq"] = self.seq.fetch_add(1, Ordering::Relaxed).into();
+        let body = message.to_string();
+        // Errors are ignored, the reader notices when the client is gone
+        let _ = write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len()).and_then(|_| writer.flush());
+    }
+
+    fn respond(&self, request: &Request, body: JsonValue) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "success": true,
+            "command": request.command,
+            "body": body,
+        }));
+    }
+
+    fn respond_error(&self, request: &Request, message: impl fmt::Display) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "success": false,
+            "command": request.command,
+            "message": message.to_string(),
+        }));
+    }
+
+    fn event(&self, event: &str, body: JsonValue) {
+        self.send(json!({"type": "event", "event": event, "body": body}));
+    }
+
+    fn set_status(&self, f: impl FnOnce(&mut Status)) {
+        f(&mut self.status.lock());
+        self.status_changed.notify_all();
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.status.lock().disconnected
+    }
+
+    fn disconnect(&self) {
+        self.set_status(|status| status.disconnected = true);
+        let chunk_names = (mem::take(&mut *self.breakpoints.lock()).into_keys())
+            .map(|(chunk_name, _)| chunk_name)
+            .collect::<BTreeSet<_>>();
+        for chunk_name in chunk_names {
+            self.remote.set_breakpoints(&chunk_name, &[]);
+        }
+    }
+
+    /// Reports the line where a breakpoint was set in a loaded chunk, if it has changed.
+    fn breakpoint_set(&self, chunk_name: &str, line: usize, target: usize) {
+        let mut breakpoints = self.breakpoints.lock();
+        let id = match breakpoints.get_mut(&(chunk_name.to_string(), line)) {
+            Some(breakpoint) if breakpoint.line != Some(target) => {
+                breakpoint.line = Some(target);
+                breakpoint.id
+            }
+            _ => return,
+        };
+        drop(breakpoints);
+        let breakpoint = json!({"id": id, "verified": true, "line": target});
+        self.event(
+            "breakpoint",
+            json!({"reason": "changed", "breakpoint": breakpoint}),
+        );
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> IoResult<Option<JsonValue>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim_end().split_once(':') {
+            Some((name, value)) if name.eq_ignore_ascii_case("Content-Length") => {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+            None if line.trim_end().is_empty() && content_length.is_some() => break,
+            _ => {}
+        }
+    }
+    let content_length = content_length.unwrap_or_default();
+    if content_length > MAX_MESSAGE_SIZE {
+        let message = format!("message size {content_length} exceeds the limit of {MAX_MESSAGE_SIZE} bytes");
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Reads client messages, handling requests that don't need the Lua state.
+///
+/// Other requests are sent to the thread that runs the code, if it's stopped.
+fn read_loop(reader: impl Read, shared: &Shared, sender: Sender<Request>) {
+    let mut reader = BufReader::new(reader);
+    while let Ok(Some(message)) = read_message(&mut reader) {
+        if message["type"] != "request" {
+            continue;
+        }
+        let request = Request {
+            seq: message["seq"].as_i64().unwrap_or_default(),
+            command: message["command"].as_str().unwrap_or_default().to_string(),
+            arguments: message["arguments"].clone(),
+        };
+        match request.command.as_str() {
+            "initialize" => {
+                let capabilities = json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsEvaluateForHovers": true,
+                });
+                shared.respond(&request, capabilities);
+                shared.event("initialized", json!({}));
+            }
+            "attach" | "launch" => shared.respond(&request, json!({})),
+            "setBreakpoints" => set_breakpoints(shared, &request),
+            "setExceptionBreakpoints" => shared.respond(&request, json!({"breakpoints": []})),
+            "configurationDone" => {
+                shared.respond(&request, json!({}));
+                shared.set_status(|status| status.configured = true);
+            }
+            "threads" => shared.respond(&request, json!({"threads": [{"id": THREAD_ID, "name": "main"}]})),
+            "pause" => {
+                if !shared.stopped.load(Ordering::SeqCst) {
+                    shared.remote.pause();
+                }
+                shared.respond(&request, json!({}));
+            }
+            "disconnect" | "terminate" => {
+                shared.respond(&request, json!({}));
+                break;
+            }
+            "stackTrace" | "scopes" | "variables" | "evaluate" | "continue" | "next" | "stepIn"
+            | "stepOut" => {
+                if !shared.stopped.load(Ordering::SeqCst) {
+                    shared.respond_error(&request, "execution is not stopped");
+                } else if let Err(mpsc::SendError(request)) = sender.send(request) {
+                    shared.respond_error(&request, "execution is not stopped");
+                }
+            }
+            command => shared.respond_error(&request, format!("unsupported request '{command}'")),
+        }
+    }
+    // Dropping the sender resumes the stopped code
+    shared.disconnect();
+}
+
+fn set_breakpoints(shared: &Shared, request: &Request) {
+    let args = &request.arguments;
+    let lines = (args["breakpoints"].as_array().into_iter().flatten())
+        .filter_map(|breakpoint| breakpoint["line"].as_u64())
+        .map(|line| line as usize)
+        .collect::<Vec<_>>();
+    let source = &args["source"];
+    let chunk_names = shared
+        .sources
+        .chunk_names(source["path"].as_str(), source["name"].as_str());
+
+    // Breakpoints are verified (and moved to lines with code) when their chunk is loaded, which is
+    // reported using `breakpoint` events
+    let ids = (lines.iter())
+        .map(|_| shared.next_breakpoint_id.fetch_add(1, Ordering::Relaxed))
+        .collect::<Vec<_>>();
+    let mut breakpoints = shared.breakpoints.lock();
+    for chunk_name in &chunk_names {
+        breakpoints.retain(|(name, _), _| name != chunk_name);
+        for (&line, &id) in lines.iter().zip(&ids) {
+            breakpoints.insert((chunk_name.clone(), line), Breakpoint { id, line: None });
+        }
+    }
+    drop(breakpoints);
+
+    let breakpoints = (lines.iter().zip(&ids))
+        .map(|(line, id)| json!({"id": id, "verified": false, "line": line}))
+        .collect::<Vec<_>>();
+    shared.respond(request, json!({"breakpoints": breakpoints}));
+
+    // The response must precede the events
+    for chunk_name in &chunk_names {
+        shared.remote.set_breakpoints(chunk_name, &lines);
+    }
+}
+
+/// Maps chunk names to source files and back.
+struct SourceMap {
+    root: PathBuf,
+}
+
+impl SourceMap {
+    fn chunk_names(&self, path: Option<&str>, name: Option<&str>) -> Vec<String> {
+        let Some(path) = path else {
+            return name.map(|name| vec![name.to_string()]).unwrap_or_default();
+        };
+        let path = Path::new(path);
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        let path = path.to_string_lossy().replace('\\', "/");
+        let mut names = vec![format!("@{path}")];
+        if let Some(module) = path.strip_suffix(".luau").or_else(|| path.strip_suffix(".lua")) {
+            names.push(format!("@{module}"));
+            if let Some(dir) = module.strip_suffix("/init") {
+                names.push(format!("@{dir}"));
+            }
+        }
+        names
+    }
+
+    fn source(&self, chunk_name: &str) -> JsonValue {
+        let Some(path) = chunk_name.strip_prefix('@') else {
+            return json!({"name": chunk_name.trim_start_matches('=')});
+        };
+        let mut path = self.root.join(path);
+        if path.extension().is_none() {
+            let candidates = [
+                path.with_extension("luau"),
+                path.with_extension("lua"),
+                path.join("init.luau"),
+                path.join("init.lua"),
+            ];
+            if let Some(candidate) = candidates.into_iter().find(|path| path.is_file()) {
+                path = candidate;
+            }
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        json!({"name": name, "path": path.to_string_lossy()})
+    }
+}
+
+fn on_break(
+    lua: &Lua,
+    shared: &Shared,
+    receiver: &Mutex<Receiver<Request>>,
+    reason: BreakReason,
+) -> Result<DebugAction> {
+    if shared.is_disconnected() {
+        return Ok(DebugAction::Continue);
+    }
+    let receiver = receiver.lock();
+    // Requests sent while the code was running
+    while let Ok(request) = receiver.try_recv() {
+        shared.respond_error(&request, "execution is not stopped");
+    }
+
+    let reason = match reason {
+        BreakReason::Breakpoint => "breakpoint",
+        BreakReason::Step => "step",
+        _ => "pause",
+    };
+    shared.stopped.store(true, Ordering::SeqCst);
+    shared.event(
+        "stopped",
+        json!({"reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true}),
+    );
+
+    let mut frame = StoppedState {
+        lua,
+        shared,
+        handles: Vec::new(),
+    };
+    let action = loop {
+        let Ok(request) = receiver.recv() else {
+            break DebugAction::Continue;
+        };
+        match frame.handle(&request) {
+            Ok(Some(action)) => break action,
+            Ok(None) => {}
+            Err(err) => shared.respond_error(&request, err),
+        }
+    };
+    shared.stopped.store(false, Ordering::SeqCst);
+    Ok(action)
+}
+
+enum Handle {
+    Locals(usize),
+    Upvalues(usize),
+    Globals,
+    Table(Table),
+}
+
+/// Handles requests while the code is stopped.
+struct StoppedState<'a> {
+    lua: &'a Lua,
+    shared: &'a Shared,
+    // Values that can be expanded by the client, referenced by index + 1
+    handles: Vec<Handle>,
+}
+
+impl StoppedState<'_> {
+    fn handle(&mut self, request: &Request) -> Result<Option<DebugAction>> {
+        let args = &request.arguments;
+        let body = match request.command.as_str() {
+            "stackTrace" => self.stack_trace(),
+            "scopes" => {
+                let level = (args["frameId"].as_u64().unwrap_or(1) as usize).saturating_sub(1);
+                let locals = self.add_handle(Handle::Locals(level));
+                let upvalues = self.add_handle(Handle::Upvalues(level));
+                let globals = self.add_handle(Handle::Globals);
+                json!({"scopes": [
+                    {"name": "Locals", "variablesReference": locals, "expensive": false},
+                    {"name": "Upvalues", "variablesReference": upvalues, "expensive": false},
+                    {"name": "Globals", "variablesReference": globals, "expensive": true},
+                ]})
+            }
+            "variables" => self.variables(args["variablesReference"].as_u64().unwrap_or_default())?,
+            "evaluate" => {
+                let level = args["frameId"].as_u64().map(|id| (id as usize).saturating_sub(1));
+                self.evaluate(args["expression"].as_str().unwrap_or_default(), level)?
+            }
+            command => {
+                let action = match command {
+                    "next" => DebugAction::StepOver,
+                    "stepIn" => DebugAction::StepIn,
+                    "stepOut" => DebugAction::StepOut,
+                    _ => DebugAction::Continue,
+                };
+                self.shared.stopped.store(false, Ordering::SeqCst);
+                self.shared.respond(request, json!({"allThreadsContinued": true}));
+                return Ok(Some(action));
+            }
+        };
+        self.shared.respond(request, body);
+        Ok(None)
+    }
+
+    fn add_handle(&mut self, handle: Handle) -> usize {
+        self.handles.push(handle);
+        self.handles.len()
+    }
+
+    fn stack_trace(&self) -> JsonValue {
+        let mut frames = Vec::new();
+        for level in 0.. {
+            let frame = self.lua.inspect_stack(level, |debug| {
+                let source = debug.source();
+                let name = match debug.names().name {
+                    Some(name) => name.into_owned(),
+                    None if source.what == "main" => "main chunk".to_string(),
+                    None => "anonymous function".to_string(),
+                };
+                let mut frame = json!({
+                    "id": level + 1,
+                    "name": name,
+                    "line": debug.current_line().unwrap_or_default(),
+                    "column": 0,
+                });
+                match source.source {
+                    Some(chunk_name) if source.what != "C" => {
+                        frame["source"] = self.shared.sources.source(&chunk_name);
+                        frame["column"] = 1.into();
+                    }
+                    _ => frame["presentationHint"] = "subtle".into(),
+                }
+                frame
+            });
+            match frame {
+                Some(frame) => frames.push(frame),
+                None => break,
+            }
+        }
+        json!({"totalFrames": frames.len(), "stackFrames": frames})
+    }
+
+    fn variables(&mut self, reference: u64) -> Result<JsonValue> {
+        let entries = match self.handles.get((reference as usize).wrapping_sub(1)) {
+            Some(&Handle::Locals(level)) => (self.lua.inspect_stack(level, |debug| debug.locals()))
+                .unwrap_or_default()
+                .into_iter()
+                // Skip internal variables (e.g. loop state)
+                .filter(|(name, _)| !name.starts_with('('))
+                .collect(),
+            Some(&Handle::Upvalues(level)) => (self.lua.inspect_stack(level, |debug| debug.function()))
+                .map(|function| function.upvalues())
+                .unwrap_or_default(),
+            Some(Handle::Globals) => table_entries(&self.lua.globals())?,
+            Some(Handle::Table(table)) => table_entries(table)?,
+            None => return Err(Error::runtime("invalid variables reference")),
+        };
+        let variables = (entries.into_iter())
+            .map(|(name, value)| self.variable(name, value))
+            .collect::<Vec<_>>();
+        Ok(json!({"variables": variables}))
+    }
+
+    fn variable(&mut self, name: String, value: Value) -> JsonValue {
+        let (value, type_name, reference) = self.describe(value);
+        json!({"name": name, "value": value, "type": type_name, "variablesReference": reference})
+    }
+
+    /// Returns the displayed value, type name and variables reference of a value.
+    fn describe(&mut self, value: Value) -> (String, &'static str, usize) {
+        let display = display_value(&value);
+        let type_name = value.type_name();
+        let reference = match value {
+            Value::Table(table) => self.add_handle(Handle::Table(table)),
+            _ => 0,
+        };
+        (display, type_name, reference)
+    }
+
+    /// Evaluates an expression (or a statement) with access to the local variables of the frame.
+    fn evaluate(&mut self, expression: &str, level: Option<usize>) -> Result<JsonValue> {
+        let lua = self.lua;
+        let env = lua.create_table()?;
+        let locals = level.and_then(|level| lua.inspect_stack(level, |debug| debug.locals()));
+        for (name, value) in locals.unwrap_or_default() {
+            env.raw_set(name, value)?;
+        }
+        env.set_metatable(Some(lua.create_table_from([("__index", lua.globals())])?))?;
+
+        let chunk = |source: String| {
+            (lua.load(source))
+                .set_name("=evaluate")
+                .set_environment(env.clone())
+                .into_function()
+        };
+        let function = match chunk(format!("return {expression}")) {
+            Ok(function) => function,
+            Err(_) => chunk(expression.to_string())?,
+        };
+        let value = function.call::<MultiValue>(())?.into_iter().next();
+        let (result, type_name, reference) = self.describe(value.unwrap_or(Value::Nil));
+        Ok(json!({"result": result, "type": type_name, "variablesReference": reference}))
+    }
+}
+
+fn table_entries(table: &Table) -> Result<Vec<(String, Value)>> {
+    (table.pairs::<Value, Value>())
+        .map(|pair| {
+            let (key, value) = pair?;
+            let name = match key {
+                Value::String(key) => key.to_string_lossy(),
+                key => format!("[{}]", display_value(&key)),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s.to_string_lossy()),
+        value => (value.to_string()).unwrap_or_else(|_| value.type_name().to_string()),
+    }
+}
@@ -0,0 +1,289 @@
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::string::String as StdString;
+
+use rustc_hash::FxHashMap;
+
+use crate::error::{Error, Result};
+use crate::state::Lua;
+use crate::table::Table;
+use crate::userdata::MetaMethod;
+use crate::value::Value;
+
+// Nesting limit for inferring types of registered module tables
+const MAX_TABLE_DEPTH: usize = 4;
+
+/// Shape of a userdata type collected from its registry, used to generate type definitions.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UserDataDefinition {
+    // Full Rust type name, used to tell apart types with the same short name
+    pub(crate) type_name: StdString,
+    pub(crate) class_name: StdString,
+    pub(crate) fields: Vec<StdString>,
+    pub(crate) methods: Vec<StdString>,
+    pub(crate) functions: Vec<StdString>,
+    pub(crate) meta_methods: Vec<StdString>,
+    pub(crate) field_types: FxHashMap<StdString, StdString>,
+    pub(crate) method_types: FxHashMap<StdString, StdString>,
+}
+
+impl UserDataDefinition {
+    pub(crate) fn new(type_name: &str, short_type_name: &str) -> Self {
+        UserDataDefinition {
+            type_name: type_name.to_string(),
+            class_name: class_name(short_type_name),
+            ..Default::default()
+        }
+    }
+
+    // Checks that type hints refer to registered fields and methods
+    fn check_type_hints(&self, class_name: &str) -> Result<()> {
+        if let Some(name) = (self.field_types.keys()).find(|name| !self.fields.contains(name)) {
+            let msg = format!("type hint for unknown field '{name}' of '{class_name}'");
+            return Err(Error::runtime(msg));
+        }
+        let is_registered = |name: &StdString| {
+            [&self.methods, &self.functions, &self.meta_methods]
+                .iter()
+                .any(|names| names.contains(name))
+        };
+        if let Some(name) = self.method_types.keys().find(|name| !is_registered(name)) {
+            let msg = format!("type hint for unknown method '{name}' of '{class_name}'");
+            return Err(Error::runtime(msg));
+        }
+        Ok(())
+    }
+
+    fn write(&self, class_name: &str, out: &mut StdString) {
+        let _ = writeln!(out, "declare class {class_name}");
+        let mut seen = Vec::new();
+        for name in &self.fields {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name);
+            let ty = self.field_types.get(name).map(|s| s.as_str()).unwrap_or("any");
+            let _ = writeln!(out, "\t{}: {ty}", property_name(name));
+        }
+        for name in &self.functions {
+            let ty = (self.method_types.get(name).map(|s| s.as_str())).unwrap_or("(...any) -> ...any");
+            let _ = writeln!(out, "\t{}: {ty}", property_name(name));
+        }
+        let metamethods = self
+            .meta_methods
+            .iter()
+            .filter(|name| !is_internal_metamethod(name));
+        for name in self.methods.iter().chain(metamethods) {
+            if !is_identifier(name) {
+                continue;
+            }
+            let sig = (self.method_types.get(name).map(|s| s.as_str())).unwrap_or("(self, ...any): ...any");
+            let _ = writeln!(out, "\tfunction {name}{sig}");
+        }
+        out.push_str("end\n");
+    }
+}
+
+impl Lua {
+    /// Generates a Luau type definition file (`.d.luau`) describing the host API.
+    ///
+    /// The output contains a `declare class` block for every userdata type that has been
+    /// registered using [`Lua::register_userdata_type`] or created at least once, and a type alias
+    /// for every module registered using [`Lua::register_module`].
+    ///
+    /// Userdata fields and methods are typed as `any` unless a type hint was provided using
+    /// [`UserDataFields::set_field_type`] or [`UserDataMethods::set_method_type`]. Module types are
+    /// inferred from their current values.
+    ///
+    /// Classes are named after the Rust types. If several types have the same name (e.g.
+    /// `a::Config` and `b::Config`), their classes are named after the full type paths instead
+    /// (e.g. `a_Config` and `b_Config`).
+    ///
+    /// Returns an error if a type hint refers to a field or method that is not registered.
+    ///
+    /// [`UserDataFields::set_field_type`]: crate::UserDataFields::set_field_type
+    /// [`UserDataMethods::set_method_type`]: crate::UserDataMethods::set_method_type
+    pub fn type_definitions(&self) -> Result<StdString> {
+        let (classes, class_names) = {
+            let lua = self.lock();
+            let definitions = unsafe { &(*lua.extra()).userdata_definitions };
+            // Wrapped types (eg. `Arc<T>`) share the definition with the inner type
+            let mut types = BTreeMap::new();
+            let mut short_names = FxHashMap::default();
+            for def in definitions.values() {
+                if types.insert(def.type_name.as_str(), def).is_none() {
+                    *short_names.entry(def.class_name.as_str()).or_insert(0) += 1;
+                }
+            }
+            let mut classes = BTreeMap::new();
+            let mut type_classes = FxHashMap::default();
+            for (type_name, def) in types {
+                let mut name = match short_names[def.class_name.as_str()] {
+                    1 => def.class_name.clone(),
+                    _ => class_name(type_name),
+                };
+                // Names built from type paths are unlikely to clash, but must be unique anyway
+                let base_len = name.len();
+                for i in 2.. {
+                    if !classes.contains_key(&name) {
+                        break;
+                    }
+                    name.truncate(base_len);
+                    let _ = write!(name, "_{i}");
+                }
+                type_classes.insert(type_name, name.clone());
+                classes.insert(name, def.clone());
+            }
+            let class_names = (definitions.iter())
+                .map(|(&type_id, def)| (type_id, type_classes[def.type_name.as_str()].clone()))
+                .collect::<FxHashMap<_, _>>();
+            (classes, class_names)
+        };
+
+        let mut out = StdString::from("-- Type definitions generated by ulua\n");
+        for (name, def) in &classes {
+            def.check_type_hints(name)?;
+            out.push('\n');
+            def.write(name, &mut out);
+        }
+
+        let modules = self.named_registry_value::<Option<Table>>("_REGISTEREDMODULES")?;
+        let mut modules = match modules {
+            Some(modules) => modules.pairs::<StdString, Value>().collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        modules.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, value) in modules {
+            let mut ty = StdString::new();
+            infer_type(&value, &class_names, 0, &mut ty)?;
+            // The name is escaped, so it cannot end the comment
+            let _ = write!(
+                out,
+                "\n-- require({name:?})\ntype {} = {ty}\n",
+                module_type_name(&name)
+            );
+        }
+
+        Ok(out)
+    }
+}
+
+fn infer_type(
+    value: &Value,
+    class_names: &FxHashMap<TypeId, StdString>,
+    depth: usize,
+    out: &mut StdString,
+) -> Result<()> {
+    let ty = match value {
+        Value::Nil => "nil",
+        Value::Boolean(_) => "boolean",
+        Value::LightUserData(_) => "any",
+        Value::Integer(_) | Value::Number(_) => "number",
+        Value::Vector(_) => "vector",
+        Value::String(_) => "string",
+        Value::Function(_) => "(...any) -> ...any",
+        Value::Thread(_) => "thread",
+        Value::Buffer(_) => "buffer",
+        Value::UserData(ud) => match ud.type_id().and_then(|type_id| class_names.get(&type_id)) {
+            Some(name) => name.as_str(),
+            None => "any",
+        },
+        Value::Table(t) if depth < MAX_TABLE_DEPTH => return infer_table_type(t, class_names, depth, out),
+        _ => "any",
+    };
+    out.push_str(ty);
+    Ok(())
+}
+
+fn infer_table_type(
+    table: &Table,
+    class_names: &FxHashMap<TypeId, StdString>,
+    depth: usize,
+    out: &mut StdString,
+) -> Result<()> {
+    let mut fields = Vec::new();
+    for pair in table.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        if let Value::String(key) = key {
+            if let Ok(key) = key.to_str() {
+                fields.push((key.to_string(), value));
+            }
+        }
+    }
+    if fields.is_empty() {
+        out.push_str(if table.raw_len() > 0 {
+            "{ any }"
+        } else {
+            "{ [any]: any }"
+        });
+        return Ok(());
+    }
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let indent = "\t".repeat(depth + 1);
+    out.push_str("{\n");
+    for (key, value) in fields {
+        let _ = write!(out, "{indent}{}: ", property_name(&key));
+        infer_type(&value, class_names, depth + 1, out)?;
+        out.push_str(",\n");
+    }
+    out.push_str(&indent[1..]);
+    out.push('}');
+    Ok(())
+}
+
+// Converts a Rust type name (eg. `Foo<Bar>`) into a valid Luau type name
+fn class_name(type_name: &str) -> StdString {
+    let name = type_name
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    match name.chars().next() {
+        Some(c) if !c.is_ascii_digit() => name,
+        _ => format!("_{name}"),
+    }
+}
+
+// Converts a module name (eg. `@host/net`) into a type alias name (eg. `HostNetModule`)
+fn module_type_name(modname: &str) -> StdString {
+    let mut name = StdString::new();
+    for part in modname.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            name.push(c.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name + "Module"
+}
+
+fn property_name(name: &str) -> StdString {
+    if is_identifier(name) {
+        return name.to_string();
+    }
+    format!("[{name:?}]")
+}
+
+fn is_identifier(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+        "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+// Metamethods that are used internally and not exposed as part of the class interface
+fn is_internal_metamethod(name: &str) -> bool {
+    [MetaMethod::Index, MetaMethod::NewIndex, MetaMethod::Type]
+        .iter()
+        .any(|mm| mm.name() == name)
+        || matches!(name, "__namecall" | "__gc" | "__metatable" | "__mode" | "__name")
+}
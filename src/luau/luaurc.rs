@@ -0,0 +1,661 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::{fmt, fs, str};
+
+use crate::error::{Error, Result, SyntaxDiagnostic};
+
+// Names of the Luau lints (see `Luau/LinterConfig.h`)
+const LINT_NAMES: &[&str] = &[
+    "UnknownGlobal",
+    "DeprecatedGlobal",
+    "GlobalUsedAsLocal",
+    "LocalShadow",
+    "SameLineStatement",
+    "MultiLineStatement",
+    "LocalUnused",
+    "FunctionUnused",
+    "ImportUnused",
+    "BuiltinGlobalWrite",
+    "PlaceholderRead",
+    "UnreachableCode",
+    "UnknownType",
+    "ForRange",
+    "UnbalancedAssignment",
+    "ImplicitReturn",
+    "DuplicateLocal",
+    "FormatString",
+    "TableLiteral",
+    "UninitializedLocal",
+    "DuplicateFunction",
+    "DeprecatedApi",
+    "TableOperations",
+    "DuplicateCondition",
+    "MisleadingAndOr",
+    "CommentDirective",
+    "IntegerParsing",
+    "ComparisonPrecedence",
+    "RedundantNativeAttribute",
+];
+
+/// Type checking mode of Luau modules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LanguageMode {
+    /// Type checking is disabled.
+    NoCheck,
+    /// Types are inferred, but only obvious errors are reported.
+    NonStrict,
+    /// All type errors are reported.
+    Strict,
+}
+
+impl LanguageMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            LanguageMode::NoCheck => "nocheck",
+            LanguageMode::NonStrict => "nonstrict",
+            LanguageMode::Strict => "strict",
+        }
+    }
+}
+
+/// A Luau configuration file (`.luaurc`).
+///
+/// Configuration files define aliases for the `require-by-string` system, and settings of the Luau
+/// type checker and linter. Options that are not set are inherited from configuration files in
+/// parent directories (see [`LuauRc::inherit`]).
+///
+/// The configuration can be parsed from a file or constructed programmatically and then
+/// serialized (using [`Display`]) to provide it from a custom [`Require`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// # use ulua::{LanguageMode, LuauRc, Result};
+/// # fn main() -> Result<()> {
+/// let config = LuauRc::parse(r#"{ "languageMode": "strict", "aliases": { "Utils": "./utils" } }"#)?;
+/// assert_eq!(config.language_mode(), Some(LanguageMode::Strict));
+/// assert_eq!(config.alias("utils"), Some("./utils"));
+///
+/// let config = LuauRc::new().add_alias("lib", "./lib").set_lint("LocalUnused", false);
+/// config.validate()?;
+/// assert_eq!(LuauRc::parse(config.to_string())?, config);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Display`]: fmt::Display
+/// [`Require`]: crate::Require
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LuauRc {
+    language_mode: Option<LanguageMode>,
+    lints: BTreeMap<String, bool>,
+    lint_errors: Option<bool>,
+    type_errors: Option<bool>,
+    globals: Vec<String>,
+    /// Aliases keyed by lowercase name
+    aliases: BTreeMap<String, Alias>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Alias {
+    name: String,
+    path: String,
+}
+
+impl LuauRc {
+    /// The name of Luau configuration files.
+    pub const FILE_NAME: &str = ".luaurc";
+
+    /// Creates an empty configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses and validates the configuration file contents.
+    ///
+    /// Invalid contents are reported as [`Error::SyntaxError`] with a diagnostic pointing at the
+    /// invalid option.
+    pub fn parse(contents: impl AsRef<[u8]>) -> Result<Self> {
+        Self::parse_named(Self::FILE_NAME, contents.as_ref())
+    }
+
+    /// Reads and parses the configuration file at the given path.
+    ///
+    /// Errors are reported in the same way as in [`LuauRc::parse`], with the file path as the
+    /// chunk name.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read(path)
+            .map_err(|err| Error::runtime(format!("cannot read '{}': {err}", path.display())))?;
+        Self::parse_named(&path.display().to_string(), &contents)
+    }
+
+    fn parse_named(name: &str, contents: &[u8]) -> Result<Self> {
+        let mut config = LuauRc::new();
+        let mut parser = Parser::new(contents);
+        parser
+            .parse(&mut config)
+            .map_err(|(pos, message)| parser.error(name, pos, message))?;
+        Ok(config)
+    }
+
+    /// Checks that the configuration contains only valid alias and lint names.
+    pub fn validate(&self) -> Result<()> {
+        for alias in self.aliases.values() {
+            check_alias(&alias.name).map_err(Error::runtime)?;
+        }
+        for name in self.lints.keys() {
+            check_lint(name).map_err(Error::runtime)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the type checking mode (`languageMode` option).
+    pub fn language_mode(&self) -> Option<LanguageMode> {
+        self.language_mode
+    }
+
+    /// Sets the type checking mode (`languageMode` option).
+    pub fn set_language_mode(mut self, mode: LanguageMode) -> Self {
+        self.language_mode = Some(mode);
+        self
+    }
+
+    /// Returns whether the given lint is enabled (`lint` option).
+    ///
+    /// Returns `None` if the lint is not configured.
+    pub fn lint(&self, name: &str) -> Option<bool> {
+        (self.lints.get(name).or_else(|| self.lints.get("*"))).copied()
+    }
+
+    /// Returns an iterator over the configured lints and their state.
+    ///
+    /// The `*` name refers to all lints.
+    pub fn lints(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.lints.iter().map(|(name, &enabled)| (name.as_str(), enabled))
+    }
+
+    /// Enables or disables the given lint (`lint` option).
+    ///
+    /// The `*` name can be used to set all lints, overriding previous settings.
+    pub fn set_lint(mut self, name: impl Into<String>, enabled: bool) -> Self {
+        self.insert_lint(name.into(), enabled);
+        self
+    }
+
+    fn insert_lint(&mut self, name: String, enabled: bool) {
+        if name == "*" {
+            self.lints.clear();
+        }
+        self.lints.insert(name, enabled);
+    }
+
+    /// Returns whether lint warnings are reported as errors (`lintErrors` option).
+    pub fn lint_errors(&self) -> Option<bool> {
+        self.lint_errors
+    }
+
+    /// Sets whether lint warnings are reported as errors (`lintErrors` option).
+    pub fn set_lint_errors(mut self, enabled: bool) -> Self {
+        self.lint_errors = Some(enabled);
+        self
+    }
+
+    /// Returns whether type errors are reported (`typeErrors` option).
+    pub fn type_errors(&self) -> Option<bool> {
+        self.type_errors
+    }
+
+    /// Sets whether type errors are reported (`typeErrors` option).
+    pub fn set_type_errors(mut self, enabled: bool) -> Self {
+        self.type_errors = Some(enabled);
+        self
+    }
+
+    /// Returns the extra global names known to the type checker (`globals` option).
+    pub fn globals(&self) -> &[String] {
+        &self.globals
+    }
+
+    /// Adds a global name known to the type checker (`globals` option).
+    pub fn add_global(mut self, global: impl Into<String>) -> Self {
+        self.globals.push(global.into());
+        self
+    }
+
+    /// Returns the path of the alias with the given name (without the `@` prefix).
+    ///
+    /// Alias names are case-insensitive.
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        let alias = self.aliases.get(&name.to_ascii_lowercase())?;
+        Some(&alias.path)
+    }
+
+    /// Returns an iterator over the aliases (in their original case) and their paths.
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        (self.aliases.values()).map(|alias| (alias.name.as_str(), alias.path.as_str()))
+    }
+
+    /// Adds an alias (without the `@` prefix) pointing to the given path.
+    ///
+    /// Relative paths are resolved relative to the directory of the configuration file.
+    /// Aliases are case-insensitive, an existing alias with the same name is replaced.
+    pub fn add_alias(mut self, name: impl Into<String>, path: impl Into<String>) -> Self {
+        let (name, path) = (name.into(), path.into());
+        self.aliases
+            .insert(name.to_ascii_lowercase(), Alias { name, path });
+        self
+    }
+
+    /// Merges options of the configuration in a parent directory into this configuration.
+    ///
+    /// Options set in this configuration take precedence, in the same way as the `require`
+    /// function prefers aliases defined closer to the requiring module.
+    ///
+    /// Alias paths are not rebased: as in `require`, a relative path inherited from the parent
+    /// remains relative to the directory of the parent configuration file.
+    pub fn inherit(&mut self, parent: &LuauRc) {
+        self.language_mode = self.language_mode.or(parent.language_mode);
+        if !self.lints.contains_key("*") {
+            for (name, &enabled) in &parent.lints {
+                self.lints.entry(name.clone()).or_insert(enabled);
+            }
+        }
+        self.lint_errors = self.lint_errors.or(parent.lint_errors);
+        self.type_errors = self.type_errors.or(parent.type_errors);
+        let mut globals = (parent.globals.iter())
+            .filter(|global| !self.globals.contains(global))
+            .cloned()
+            .collect::<Vec<_>>();
+        globals.append(&mut self.globals);
+        self.globals = globals;
+        for (key, alias) in &parent.aliases {
+            self.aliases.entry(key.clone()).or_insert_with(|| alias.clone());
+        }
+    }
+
+    // Applies a single option value, located by its keys
+    fn set_option(&mut self, keys: &[String], value: &str) -> StdResult<(), String> {
+        let parse_bool = |value: &str| match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!(
+                "bad setting '{value}' (valid options are true and false)"
+            )),
+        };
+
+        match keys {
+            [key] if key == "languageMode" => {
+                self.language_mode = Some(match value {
+                    "nocheck" => LanguageMode::NoCheck,
+                    "nonstrict" => LanguageMode::NonStrict,
+                    "strict" => LanguageMode::Strict,
+                    _ => {
+                        return Err(format!(
+                            "bad mode \"{value}\" (valid options are nocheck, nonstrict and strict)"
+                        ))
+                    }
+                });
+            }
+            [key, name] if key == "lint" => {
+                let enabled = parse_bool(value).map_err(|err| format!("in key {name}: {err}"))?;
+                self.insert_lint(name.clone(), enabled);
+            }
+            [key] if key == "lintErrors" => self.lint_errors = Some(parse_bool(value)?),
+            [key] if key == "typeErrors" => self.type_errors = Some(parse_bool(value)?),
+            [key] if key == "globals" => self.globals.push(value.to_string()),
+            [key, name] if key == "aliases" => {
+                // The first definition wins
+                let alias = Alias {
+                    name: name.clone(),
+                    path: value.to_string(),
+                };
+                self.aliases.entry(name.to_ascii_lowercase()).or_insert(alias);
+            }
+            _ => return Err(format!("unknown key {}", keys.join("/"))),
+        }
+        Ok(())
+    }
+}
+
+// Checks the last key of an option path
+fn check_key(keys: &[String]) -> StdResult<(), String> {
+    const OPTIONS: &[&str] = &[
+        "languageMode",
+        "lint",
+        "lintErrors",
+        "typeErrors",
+        "globals",
+        "aliases",
+    ];
+    match keys {
+        [key] if OPTIONS.contains(&key.as_str()) => Ok(()),
+        [key, name] if key == "lint" => check_lint(name),
+        [key, name] if key == "aliases" => check_alias(name),
+        _ => Err(format!("unknown key {}", keys.join("/"))),
+    }
+}
+
+fn check_alias(name: &str) -> StdResult<(), String> {
+    let is_valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if name.is_empty() || name == "." || name == ".." || !name.chars().all(is_valid_char) {
+        return Err(format!("invalid alias {name}"));
+    }
+    Ok(())
+}
+
+fn check_lint(name: &str) -> StdResult<(), String> {
+    if name != "*" && !LINT_NAMES.contains(&name) {
+        return Err(format!("unknown lint {name}"));
+    }
+    Ok(())
+}
+
+impl fmt::Display for LuauRc {
+    /// Formats the configuration as a `.luaurc` file contents (in JSON format).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+            write!(f, "\"")?;
+            for c in s.chars() {
+                match c {
+                    '"' => write!(f, "\\\"")?,
+                    '\\' => write!(f, "\\\\")?,
+                    '\n' => write!(f, "\\n")?,
+                    '\r' => write!(f, "\\r")?,
+                    '\t' => write!(f, "\\t")?,
+                    c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                    c => write!(f, "{c}")?,
+                }
+            }
+            write!(f, "\"")
+        }
+
+        write!(f, "{{")?;
+        let mut sep = "";
+        let mut key = |f: &mut fmt::Formatter<'_>, name: &str| {
+            let result = write!(f, "{sep}\n  \"{name}\": ");
+            sep = ",";
+            result
+        };
+        if let Some(mode) = self.language_mode {
+            key(f, "languageMode")?;
+            string(f, mode.as_str())?;
+        }
+        if !self.lints.is_empty() {
+            key(f, "lint")?;
+            write!(f, "{{")?;
+            for (i, (name, enabled)) in self.lints.iter().enumerate() {
+                write!(f, "{}\n    ", if i > 0 { "," } else { "" })?;
+                string(f, name)?;
+                write!(f, ": {enabled}")?;
+            }
+            write!(f, "\n  }}")?;
+        }
+        if let Some(enabled) = self.lint_errors {
+            key(f, "lintErrors")?;
+            write!(f, "{enabled}")?;
+        }
+        if let Some(enabled) = self.type_errors {
+            key(f, "typeErrors")?;
+            write!(f, "{enabled}")?;
+        }
+        if !self.globals.is_empty() {
+            key(f, "globals")?;
+            write!(f, "[")?;
+            for (i, global) in self.globals.iter().enumerate() {
+                write!(f, "{}", if i > 0 { ", " } else { "" })?;
+                string(f, global)?;
+            }
+            write!(f, "]")?;
+        }
+        if !self.aliases.is_empty() {
+            key(f, "aliases")?;
+            write!(f, "{{")?;
+            for (i, alias) in self.aliases.values().enumerate() {
+                write!(f, "{}\n    ", if i > 0 { "," } else { "" })?;
+                string(f, &alias.name)?;
+                write!(f, ": ")?;
+                string(f, &alias.path)?;
+            }
+            write!(f, "\n  }}")?;
+        }
+        write!(f, "{}}}", if sep.is_empty() { "" } else { "\n" })
+    }
+}
+
+type ParseResult<T> = StdResult<T, (usize, String)>;
+
+/// A parser of the JSON subset supported by Luau configuration files.
+///
+/// Values are strings, booleans, objects and arrays of strings. Comments (`//` and `--`) and
+/// trailing commas are allowed.
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Parser { input, pos: 0 }
+    }
+
+    // Returns 1-based line and column of the byte position
+    fn location(&self, pos: usize) -> (usize, usize) {
+        let before = &self.input[..pos.min(self.input.len())];
+        let line = before.iter().filter(|&&c| c == b'\n').count() + 1;
+        let line_start = before.iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+        let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+        (line, column)
+    }
+
+    // Builds a syntax error pointing at the token at the byte position
+    fn error(&self, name: &str, pos: usize, message: String) -> Error {
+        let (line, column) = self.location(pos);
+        let len = self.token(pos).chars().count().max(1);
+        Error::SyntaxError {
+            message: format!("{name}:{line}:{column}: {message}"),
+            incomplete_input: pos >= self.input.len(),
+            diagnostics: vec![SyntaxDiagnostic {
+                chunk_name: Some(name.to_string()),
+                line,
+                columns: Some(column..column + len),
+                message,
+            }],
+        }
+    }
+
+    fn parse(&mut self, config: &mut LuauRc) -> ParseResult<()> {
+        if let Err(err) = str::from_utf8(self.input) {
+            return Err((err.valid_up_to(), "invalid UTF-8 sequence".to_string()));
+        }
+        self.expect(b'{', "'{'")?;
+        self.parse_object(config, &mut Vec::new())?;
+        self.skip_whitespace();
+        if self.pos < self.input.len() {
+            return Err(self.unexpected("end of file"));
+        }
+        Ok(())
+    }
+
+    fn parse_object(&mut self, config: &mut LuauRc, keys: &mut Vec<String>) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(b'"' | b'\'') => {}
+                _ => return Err(self.unexpected("field key")),
+            }
+            let key_pos = self.pos;
+            keys.push(self.parse_string()?);
+            check_key(keys).map_err(|err| (key_pos, err))?;
+            self.expect(b':', "':'")?;
+
+            self.skip_whitespace();
+            let value_pos = self.pos;
+            match self.peek() {
+                Some(b'{') => {
+                    self.pos += 1;
+                    self.parse_object(config, keys)?;
+                }
+                Some(b'[') => {
+                    self.pos += 1;
+                    self.parse_array(config, keys)?;
+                }
+                Some(b'"' | b'\'') => {
+                    let value = self.parse_string()?;
+                    config.set_option(keys, &value).map_err(|err| (value_pos, err))?;
+                }
+                _ if self.eat_word("true") => {
+                    config.set_option(keys, "true").map_err(|err| (value_pos, err))?
+                }
+                _ if self.eat_word("false") => {
+                    config.set_option(keys, "false").map_err(|err| (value_pos, err))?
+                }
+                _ => return Err(self.unexpected("field value")),
+            }
+            keys.pop();
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {}
+                _ => return Err(self.unexpected("',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, config: &mut LuauRc, keys: &[String]) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            let value_pos = self.pos;
+            match self.peek() {
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(b'"' | b'\'') => {
+                    let value = self.parse_string()?;
+                    config.set_option(keys, &value).map_err(|err| (value_pos, err))?;
+                }
+                _ => return Err(self.unexpected("array element or ']'")),
+            }
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(self.unexpected("',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> ParseResult<String> {
+        let start = self.pos;
+        let quote = self.input[self.pos];
+        self.pos += 1;
+        let mut value = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err((start, "unfinished string".to_string())),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    // The input is valid UTF-8 and escapes produce valid characters
+                    return Ok(String::from_utf8(value).unwrap());
+                }
+                Some(b'\\') => {
+                    let escape_pos = self.pos;
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'b') => '\x08',
+                        Some(b'f') => '\x0c',
+                        Some(c @ (b'"' | b'\'' | b'\\' | b'/')) => c as char,
+                        Some(b'u') => {
+                            let hex = self.input.get(self.pos + 1..self.pos + 5);
+                            let code = hex
+                                .and_then(|hex| str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| (escape_pos, "invalid unicode escape".to_string()))?;
+                            self.pos += 4;
+                            code
+                        }
+                        _ => return Err((escape_pos, "invalid escape sequence".to_string())),
+                    };
+                    self.pos += 1;
+                    value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            let rest = &self.input[self.pos..];
+            if c.is_ascii_whitespace() {
+                self.pos += 1;
+            } else if rest.starts_with(b"//") || rest.starts_with(b"--") {
+                let len = rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+                self.pos += len;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let rest = &self.input[self.pos..];
+        let is_word_end = !(rest.get(word.len())).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_');
+        if rest.starts_with(word.as_bytes()) && is_word_end {
+            self.pos += word.len();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: u8, expected: &str) -> ParseResult<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected(expected));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    // Returns the token (a string, a word or a single character) at the byte position
+    fn token(&self, pos: usize) -> String {
+        let rest = String::from_utf8_lossy(self.input.get(pos..).unwrap_or_default());
+        let rest = rest.split('\n').next().unwrap_or_default();
+        let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '-');
+        let len = match rest.chars().next() {
+            Some(c @ ('"' | '\'')) => rest[1..].find(c).map_or(rest.len(), |i| i + 2),
+            Some(c) if is_word(c) => rest.find(|c| !is_word(c)).unwrap_or(rest.len()),
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+        rest[..len].to_string()
+    }
+
+    fn unexpected(&self, expected: &str) -> (usize, String) {
+        let found = if self.pos < self.input.len() {
+            format!("'{}'", self.token(self.pos).chars().take(32).collect::<String>())
+        } else {
+            "end of file".to_string()
+        };
+        (self.pos, format!("expected {expected}, got {found}"))
+    }
+}
@@ -9,7 +9,20 @@ use crate::state::{callback_error_ext, ExtraData, Lua};
 use crate::traits::{FromLuaMulti, IntoLua};
 use crate::types::MaybeSend;
 
-pub use require::{NavigateError, Require, TextRequirer};
+pub(crate) use debugger::{debugger_interrupt, track_chunk, DebuggerState};
+pub use debugger::{BreakReason, DebugAction, Debugger, RemoteDebugger};
+#[cfg(feature = "dap")]
+pub use debugger::{DapServer, DapSession};
+pub(crate) use definitions::UserDataDefinition;
+pub use luaurc::{LanguageMode, LuauRc};
+pub(crate) use native::is_native_annotated;
+pub use native::NativeCodeStats;
+#[cfg(feature = "async")]
+pub use require::AsyncRequire;
+pub use require::{
+    ArchiveRequirer, HostModules, HotReloader, LoadedModule, MemoryRequirer, ModuleArchive, ModuleGraph,
+    ModuleReload, NavigateError, Require, RequirePolicy, RequireRequest, TextRequirer,
+};
 
 // Since Luau has some missing standard functions, we re-implement them here
 
@@ -17,7 +30,60 @@ impl Lua {
     /// Create a custom Luau `require` function using provided [`Require`] implementation to find
     /// and load modules.
     pub fn create_require_function<R: Require + MaybeSend + 'static>(&self, require: R) -> Result<Function> {
-        require::create_require_function(self, require)
+        require::create_require_function(self, require, None)
+    }
+
+    /// Create a custom Luau `require` function using provided [`Require`] implementation, that
+    /// checks every `require` call against the given [`RequirePolicy`].
+    ///
+    /// Denied calls raise an error in the calling code, with the reason returned by the policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ulua::{Lua, MemoryRequirer, RequireRequest, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// let requirer = MemoryRequirer::new()
+    ///     .with_module("shared/log.luau", "return {}")
+    ///     .with_module("secret.luau", "return {}");
+    /// let policy = |request: &RequireRequest| match request.cache_key.ends_with("/secret.luau") {
+    ///     true => Err("access denied".to_string()),
+    ///     false => Ok(()),
+    /// };
+    /// let require = lua.create_require_function_with_policy(requirer, policy)?;
+    /// lua.globals().set("require", require)?;
+    ///
+    /// lua.load("require('./shared/log')").exec()?;
+    /// assert!(lua.load("require('./secret')").exec().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_require_function_with_policy<R: Require + MaybeSend + 'static>(
+        &self,
+        require: R,
+        policy: impl RequirePolicy,
+    ) -> Result<Function> {
+        require::create_require_function(self, require, Some(Box::new(policy)))
+    }
+
+    /// Create a custom Luau `require` function using provided [`AsyncRequire`] implementation to
+    /// find and load modules asynchronously.
+    ///
+    /// The returned function must be called from an async context (see [`Function::call_async`]),
+    /// as it suspends the calling coroutine while the module is resolved and loaded.
+    ///
+    /// Unlike [`Lua::create_require_function`], host modules ([`Require::host_module`]) and
+    /// [`RequirePolicy`] are not supported. Navigation is also replayed from the start each time
+    /// an operation needs to be awaited, so resolving a path takes a number of steps quadratic in
+    /// its length. See [`AsyncRequire`] for details.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub fn create_async_require_function<R: AsyncRequire + MaybeSend + 'static>(
+        &self,
+        require: R,
+    ) -> Result<Function> {
+        require::create_async_require_function(self, require)
     }
 
     pub(crate) unsafe fn configure_luau(&self) -> Result<()> {
@@ -56,4 +122,8 @@ unsafe extern "C-unwind" fn lua_loadstring(state: *mut ffi::lua_State) -> c_int
     })
 }
 
+mod debugger;
+mod definitions;
+mod luaurc;
+mod native;
 mod require;
@@ -0,0 +1,135 @@
+use std::ops::AddAssign;
+
+use crate::bytecode::{Bytecode, PROTO_NATIVE_COLD, PROTO_NATIVE_FUNCTION, PROTO_NATIVE_MODULE};
+use crate::error::Result;
+use crate::function::Function;
+use crate::state::Lua;
+use crate::util::{check_stack, StackGuard};
+
+/// Statistics of native code generation.
+///
+/// Returned by [`Lua::native_code_stats`] when enabled using [`Lua::enable_native_code_stats`].
+///
+/// Statistics describe the bytecode submitted to the code generator. The amount of memory used by
+/// the generated native code is not included: the Luau code generator allocates it in its own
+/// executable memory blocks and does not report their usage through the C API.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NativeCodeStats {
+    /// Number of chunks compiled to native code.
+    pub chunks: usize,
+    /// Number of functions submitted to the native code generator.
+    pub functions: usize,
+    /// Number of bytecode instructions in the natively compiled functions.
+    pub instructions: usize,
+    /// Size of bytecode (in bytes) of the natively compiled functions.
+    pub bytecode_size: usize,
+}
+
+impl NativeCodeStats {
+    /// Collects statistics of functions that the code generator selects for compilation.
+    ///
+    /// Follows the Luau rules: if the chunk contains `@native` functions, only they are compiled.
+    /// Otherwise every function except the ones marked as cold is compiled.
+    pub(crate) fn from_bytecode(bytecode: &Bytecode) -> Self {
+        let main = bytecode.main_proto();
+        let has_native_functions = main.flags & PROTO_NATIVE_FUNCTION != 0;
+        let mut stats = NativeCodeStats::default();
+        let mut visited = vec![false; bytecode.protos.len()];
+        let mut stack = vec![(bytecode.main, true)];
+        while let Some((id, is_root)) = stack.pop() {
+            let Some(proto) = bytecode.protos.get(id) else {
+                continue;
+            };
+            if std::mem::replace(&mut visited[id], true) {
+                continue;
+            }
+            let selected = match has_native_functions {
+                true => !is_root && proto.flags & PROTO_NATIVE_FUNCTION != 0,
+                false => proto.flags & PROTO_NATIVE_COLD == 0,
+            };
+            if selected {
+                let words = (proto.instructions.iter()).map(|insn| 1 + insn.aux.is_some() as usize);
+                stats.functions += 1;
+                stats.instructions += proto.instructions.len();
+                stats.bytecode_size += words.sum::<usize>() * 4;
+            }
+            stack.extend(proto.children.iter().map(|&child| (child, false)));
+        }
+        // Chunks without selected functions are skipped by the code generator
+        stats.chunks = (stats.functions > 0) as usize;
+        stats
+    }
+}
+
+impl AddAssign for NativeCodeStats {
+    fn add_assign(&mut self, other: Self) {
+        self.chunks += other.chunks;
+        self.functions += other.functions;
+        self.instructions += other.instructions;
+        self.bytecode_size += other.bytecode_size;
+    }
+}
+
+// Returns `true` if the chunk requests native compilation using `--!native` or `@native`
+pub(crate) fn is_native_annotated(bytecode: &[u8]) -> bool {
+    let flags = Bytecode::main_proto_flags(bytecode).unwrap_or(0);
+    flags & (PROTO_NATIVE_MODULE | PROTO_NATIVE_FUNCTION) != 0
+}
+
+impl Lua {
+    /// Enables or disables collecting statistics of native code generation.
+    ///
+    /// Collecting is disabled by default, because it requires compiling and parsing bytecode of every
+    /// chunk compiled to native code before loading it. Disabling it resets the statistics.
+    pub fn enable_native_code_stats(&self, enable: bool) {
+        let lua = self.lock();
+        let stats = unsafe { &mut (*lua.extra()).native_stats };
+        match enable {
+            true => *stats = Some(stats.unwrap_or_default()),
+            false => *stats = None,
+        }
+    }
+
+    /// Returns statistics of native code generation for chunks loaded by this Lua instance.
+    ///
+    /// Only chunks compiled to native code when loading while collecting is enabled using
+    /// [`Lua::enable_native_code_stats`] are accounted. Functions compiled later using
+    /// [`Function::compile_native`] are not included.
+    ///
+    /// Memory used by the generated native code is not reported (it is not included in
+    /// [`Lua::used_memory`] either).
+    pub fn native_code_stats(&self) -> NativeCodeStats {
+        let lua = self.lock();
+        unsafe { (*lua.extra()).native_stats.unwrap_or_default() }
+    }
+}
+
+impl Function {
+    /// Compiles this function and all functions defined inside it to native code.
+    ///
+    /// This allows to opt individual hot functions or modules into native code generation when
+    /// JIT is disabled using [`Lua::enable_jit`]. Already compiled functions are skipped.
+    ///
+    /// Returns `false` if native code generation is not supported on the current platform or the
+    /// function is not a Luau function.
+    pub fn compile_native(&self) -> Result<bool> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        unsafe {
+            if ffi::luau_codegen_supported() == 0 {
+                return Ok(false);
+            }
+
+            let _sg = StackGuard::new(state);
+            check_stack(state, 1)?;
+
+            lua.push_ref(&self.0, state);
+            if ffi::lua_iscfunction(state, -1) != 0 {
+                return Ok(false);
+            }
+            ffi::luau_codegen_compile(state, -1);
+        }
+        Ok(true)
+    }
+}
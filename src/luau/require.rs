@@ -14,6 +14,19 @@ use crate::state::{callback_error_ext, Lua};
 use crate::table::Table;
 use crate::types::MaybeSend;
 
+pub use archive::{ArchiveRequirer, ModuleArchive};
+pub use cache::LoadedModule;
+pub use graph::ModuleGraph;
+pub use host::HostModules;
+pub use memory::MemoryRequirer;
+pub use policy::{RequirePolicy, RequireRequest};
+#[cfg(feature = "async")]
+pub use r#async::AsyncRequire;
+pub use reload::{HotReloader, ModuleReload};
+
+#[cfg(feature = "async")]
+pub(super) use r#async::create_async_require_function;
+
 /// An error that can occur during navigation in the Luau `require-by-string` system.
 #[derive(Debug, Clone)]
 pub enum NavigateError {
@@ -88,6 +101,26 @@ pub trait Require {
     /// Loader can be sync or async.
     /// This function is only called if `has_module` returns true.
     fn loader(&self, lua: &Lua) -> Result<Function>;
+
+    /// Returns the path of the file that contains the current module, if any.
+    ///
+    /// It's used by [`HotReloader`] to detect changes of the module and reported by
+    /// [`Lua::loaded_modules`].
+    /// This function is only called if `has_module` returns true.
+    fn module_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the cache key and loader of a module provided by the host (Rust code), if the
+    /// `require` path refers to one.
+    ///
+    /// It's called before navigation for every `require` call. Modules returned by this function
+    /// are not resolved using navigation methods, but otherwise are handled like other modules.
+    /// See [`HostModules`].
+    fn host_module(&self, lua: &Lua, path: &str) -> Result<Option<(String, Function)>> {
+        let _ = (lua, path);
+        Ok(None)
+    }
 }
 
 impl fmt::Debug for dyn Require {
@@ -301,21 +334,44 @@ impl Require for TextRequirer {
             .set_name(name)
             .into_function()
     }
+
+    fn module_path(&self) -> Option<PathBuf> {
+        self.resolved_path.clone()
+    }
 }
 
-struct Context(Box<dyn Require>);
+struct Context {
+    require: Box<dyn Require>,
+    policy: Option<Box<dyn RequirePolicy>>,
+    /// Chunk name of the requirer in the current navigation
+    requirer: String,
+    /// Whether the policy has been checked when loading the resolved module
+    policy_checked: bool,
+}
+
+impl Context {
+    // Checks the policy for the module resolved by the current navigation
+    fn check_policy(&self, path: &str) -> Option<String> {
+        let request = RequireRequest {
+            chunk_name: &self.requirer,
+            path,
+            cache_key: &self.cache_key(),
+        };
+        policy::check_request(&**self.policy.as_ref()?, &request)
+    }
+}
 
 impl Deref for Context {
     type Target = dyn Require;
 
     fn deref(&self) -> &Self::Target {
-        &*self.0
+        &*self.require
     }
 }
 
 impl DerefMut for Context {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut *self.0
+        &mut *self.require
     }
 }
 
@@ -363,6 +419,8 @@ pub(super) unsafe extern "C-unwind" fn init_config(config: *mut ffi::luarequire_
     ) -> ffi::luarequire_NavigateResult {
         let mut this = try_borrow_mut!(state, ctx);
         let chunk_name = CStr::from_ptr(requirer_chunkname).to_string_lossy();
+        this.requirer = chunk_name.to_string();
+        this.policy_checked = false;
         callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
             this.reset(&chunk_name).into_nav_result()
         })
@@ -459,15 +517,33 @@ pub(super) unsafe extern "C-unwind" fn init_config(config: *mut ffi::luarequire_
     unsafe extern "C-unwind" fn load(
         state: *mut ffi::lua_State,
         ctx: *mut c_void,
-        _path: *const c_char,
+        path: *const c_char,
         _chunkname: *const c_char,
         _loadname: *const c_char,
     ) -> c_int {
-        let this = try_borrow!(state, ctx);
+        // The policy is checked before the module is read
+        let mut this = try_borrow_mut!(state, ctx);
+        this.policy_checked = false;
+        let path = CStr::from_ptr(path).to_string_lossy();
+        let denied = callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            Ok(this.check_policy(&path))
+        });
+        if let Some(denied) = denied {
+            ffi::luaL_where(state, 2);
+            ffi::lua_pushlstring(state, denied.as_ptr() as *const _, denied.len());
+            drop(denied);
+            ffi::lua_concat(state, 2);
+            ffi::lua_error(state);
+        }
+
+        let mut this = try_borrow_mut!(state, ctx);
         callback_error_ext(state, ptr::null_mut(), true, move |extra, _| {
             let rawlua = (*extra).raw_lua();
             let loader = this.loader(rawlua.lua())?;
+            cache::record_module(rawlua, this.cache_key(), this.module_path());
             rawlua.push(state, loader)?;
+            // Only a successfully loaded module skips the check in the require wrapper
+            this.policy_checked = true;
             Ok(1)
         })
     }
@@ -510,6 +586,100 @@ unsafe fn write_to_buffer(
 pub(super) fn create_require_function<R: Require + MaybeSend + 'static>(
     lua: &Lua,
     require: R,
+    policy: Option<Box<dyn RequirePolicy>>,
+) -> Result<Function> {
+    unsafe extern "C-unwind" fn get_cache_key(state: *mut ffi::lua_State) -> c_int {
+        let ctx = ffi::lua_touserdata(state, ffi::lua_upvalueindex(1));
+        let ctx = try_borrow!(state, ctx);
+        let cache_key = ctx.cache_key();
+        ffi::lua_pushlstring(state, cache_key.as_ptr() as *const _, cache_key.len());
+        1
+    }
+
+    unsafe extern "C-unwind" fn host_module(state: *mut ffi::lua_State) -> c_int {
+        let path = CStr::from_ptr(ffi::luaL_checkstring(state, 1)).to_string_lossy();
+        let ctx = ffi::lua_touserdata(state, ffi::lua_upvalueindex(1));
+        let mut ctx = try_borrow_mut!(state, ctx);
+        // Called at the start of every require, so a check left over from a failed require is discarded
+        ctx.policy_checked = false;
+        callback_error_ext(state, ptr::null_mut(), true, move |extra, _| {
+            let rawlua = (*extra).raw_lua();
+            match ctx.host_module(rawlua.lua(), &path)? {
+                Some((cache_key, loader)) => {
+                    rawlua.push(state, cache_key)?;
+                    rawlua.push(state, loader)?;
+                    Ok(2)
+                }
+                None => Ok(0),
+            }
+        })
+    }
+
+    unsafe extern "C-unwind" fn check_policy(state: *mut ffi::lua_State) -> c_int {
+        let chunk_name = CStr::from_ptr(ffi::luaL_checkstring(state, 1)).to_string_lossy();
+        let path = CStr::from_ptr(ffi::luaL_checkstring(state, 2)).to_string_lossy();
+        let cache_key = CStr::from_ptr(ffi::luaL_checkstring(state, 3)).to_string_lossy();
+        let ctx = ffi::lua_touserdata(state, ffi::lua_upvalueindex(1));
+        let mut ctx = try_borrow_mut!(state, ctx);
+        // Skip the check if it was done when loading the module
+        if mem::take(&mut ctx.policy_checked) {
+            return 0;
+        }
+        callback_error_ext(state, ptr::null_mut(), true, move |extra, _| {
+            let Some(policy) = &ctx.policy else {
+                return Ok(0);
+            };
+            let request = RequireRequest {
+                chunk_name: &chunk_name,
+                path: &path,
+                cache_key: &cache_key,
+            };
+            match policy::check_request(&**policy, &request) {
+                Some(denied) => (*extra).raw_lua().push(state, denied).map(|_| 1),
+                None => Ok(0),
+            }
+        })
+    }
+
+    let has_policy = policy.is_some();
+    let (get_cache_key, host_module, check_policy, proxyrequire) = unsafe {
+        lua.exec_raw::<(Function, Function, Function, Function)>((), move |state| {
+            let context = Context {
+                require: Box::new(require),
+                policy,
+                requirer: String::new(),
+                policy_checked: false,
+            };
+            let context_ptr = ffi::lua_newuserdata_t(state, RefCell::new(context));
+            ffi::lua_pushvalue(state, -1);
+            ffi::lua_pushcclosured(state, get_cache_key, cstr!("get_cache_key"), 1);
+            ffi::lua_pushvalue(state, -2);
+            ffi::lua_pushcclosured(state, host_module, cstr!("host_module"), 1);
+            ffi::lua_pushvalue(state, -3);
+            ffi::lua_pushcclosured(state, check_policy, cstr!("check_policy"), 1);
+            ffi::luarequire_pushproxyrequire(state, init_config, context_ptr as *mut _);
+            ffi::lua_remove(state, -5);
+        })
+    }?;
+
+    let check_policy = has_policy.then_some(check_policy);
+    create_require_wrapper(lua, get_cache_key, proxyrequire, Some(host_module), check_policy)
+}
+
+// Creates the "require" function on top of `proxyrequire(path, chunk_name)`, that returns the module
+// loader, and `get_cache_key()`, that returns the cache key of the last resolved module.
+//
+// The optional `host_module(path)` function returns the cache key and loader of a module provided by
+// the host, bypassing `proxyrequire`.
+// The optional `policy(chunk_name, path, cache_key)` function returns an error message if the module
+// must not be required. For modules loaded by `proxyrequire` it's expected to be checked before the
+// module is loaded, in which case `policy` is a no-op.
+fn create_require_wrapper(
+    lua: &Lua,
+    get_cache_key: Function,
+    proxyrequire: Function,
+    host_module: Option<Function>,
+    policy: Option<Function>,
 ) -> Result<Function> {
     unsafe extern "C-unwind" fn find_current_file(state: *mut ffi::lua_State) -> c_int {
         let mut ar: ffi::lua_Debug = mem::zeroed();
@@ -525,28 +695,18 @@ pub(super) fn create_require_function<R: Require + MaybeSend + 'static>(
         1
     }
 
-    unsafe extern "C-unwind" fn get_cache_key(state: *mut ffi::lua_State) -> c_int {
-        let ctx = ffi::lua_touserdata(state, ffi::lua_upvalueindex(1));
-        let ctx = try_borrow!(state, ctx);
-        let cache_key = ctx.cache_key();
-        ffi::lua_pushlstring(state, cache_key.as_ptr() as *const _, cache_key.len());
-        1
-    }
-
-    let (get_cache_key, find_current_file, proxyrequire, registered_modules, loader_cache) = unsafe {
-        lua.exec_raw::<(Function, Function, Function, Table, Table)>((), move |state| {
-            let context = Context(Box::new(require));
-            let context_ptr = ffi::lua_newuserdata_t(state, RefCell::new(context));
-            ffi::lua_pushcclosured(state, get_cache_key, cstr!("get_cache_key"), 1);
+    let (find_current_file, registered_modules, lazy_modules, loader_cache) = unsafe {
+        lua.exec_raw::<(Function, Table, Table, Table)>((), move |state| {
             ffi::lua_pushcfunctiond(state, find_current_file, cstr!("find_current_file"));
-            ffi::luarequire_pushproxyrequire(state, init_config, context_ptr as *mut _);
             ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, ffi::LUA_REGISTERED_MODULES_TABLE);
+            ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, cstr!("__ULUA_LAZY_MODULES"));
             ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, cstr!("__ULUA_LOADER_CACHE"));
         })
     }?;
 
     unsafe extern "C-unwind" fn error(state: *mut ffi::lua_State) -> c_int {
-        ffi::luaL_where(state, 1);
+        let level = ffi::luaL_optinteger(state, 2, 1);
+        ffi::luaL_where(state, level as c_int);
         ffi::lua_pushvalue(state, 1);
         ffi::lua_concat(state, 2);
         ffi::lua_error(state);
@@ -580,12 +740,19 @@ pub(super) fn create_require_function<R: Require + MaybeSend + 'static>(
         })
     }?;
 
+    let (add_dependency, enter_module) = graph::create_tracking_functions(lua)?;
+
     // Prepare environment for the "require" function
-    let env = lua.create_table_with_capacity(0, 7)?;
+    let env = lua.create_table_with_capacity(0, 13)?;
     env.raw_set("get_cache_key", get_cache_key)?;
     env.raw_set("find_current_file", find_current_file)?;
     env.raw_set("proxyrequire", proxyrequire)?;
+    env.raw_set("host_module", host_module)?;
+    env.raw_set("add_dependency", add_dependency)?;
+    env.raw_set("enter_module", enter_module)?;
+    env.raw_set("check_policy", policy)?;
     env.raw_set("REGISTERED_MODULES", registered_modules)?;
+    env.raw_set("LAZY_MODULES", lazy_modules)?;
     env.raw_set("LOADER_CACHE", loader_cache)?;
     env.raw_set("error", error)?;
     env.raw_set("type", r#type)?;
@@ -599,20 +766,49 @@ pub(super) fn create_require_function<R: Require + MaybeSend + 'static>(
         end
 
         -- Check if the module (path) is explicitly registered
-        local maybe_result = REGISTERED_MODULES[to_lowercase(path)]
+        local modname = to_lowercase(path)
+        local maybe_result = REGISTERED_MODULES[modname]
         if maybe_result ~= nil then
             return maybe_result
         end
 
-        local loader = proxyrequire(path, find_current_file())
-        local cache_key = get_cache_key()
+        -- Construct the lazily registered module on first use
+        local init = LAZY_MODULES[modname]
+        if init ~= nil then
+            local result = init()
+            if result == nil then
+                result = true
+            end
+            REGISTERED_MODULES[modname] = result
+            LAZY_MODULES[modname] = nil
+            return result
+        end
+
+        local current_file = find_current_file()
+        local cache_key, loader
+        if host_module then
+            cache_key, loader = host_module(path)
+        end
+        if cache_key == nil then
+            loader = proxyrequire(path, current_file)
+            cache_key = get_cache_key()
+        end
+        if check_policy then
+            local denied = check_policy(current_file, path, cache_key)
+            if denied then
+                error(denied, 2)
+            end
+        end
+        add_dependency(current_file, cache_key)
         -- Check if the loader result is already cached
         local result = LOADER_CACHE[cache_key]
         if result ~= nil then
             return result
         end
 
-        -- Call the loader function and cache the result
+        -- Call the loader function (unless the module is already loading) and cache the result
+        -- (keep in sync with `HotReloader`)
+        enter_module(cache_key, loader)
         result = loader()
         if result == nil then
             result = true
@@ -627,6 +823,17 @@ pub(super) fn create_require_function<R: Require + MaybeSend + 'static>(
     .into_function()
 }
 
+mod archive;
+#[cfg(feature = "async")]
+mod r#async;
+mod cache;
+mod graph;
+mod host;
+mod memory;
+mod policy;
+mod reload;
+mod vfs;
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
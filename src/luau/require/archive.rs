@@ -0,0 +1,272 @@
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::sync::Arc;
+use std::{fmt, fs};
+
+use super::vfs::{config_path, unique_name, Files, VirtualNavigator, CONFIG_FILE};
+use super::{NavigateError, Require, TextRequirer};
+use crate::bytecode::Bytecode;
+use crate::chunk::{ChunkMode, Compiler};
+use crate::error::{Error, ErrorContext, Result};
+use crate::function::Function;
+use crate::state::Lua;
+
+const ARCHIVE_MAGIC: &[u8; 8] = b"ULUAM\x00\x00\x01";
+
+/// A tree of precompiled Luau modules packed into a single file.
+///
+/// The archive keeps the directory layout of the modules, including `.luaurc` configuration files,
+/// so modules can be required from it using [`ArchiveRequirer`] in the same way as from the file
+/// system using [`TextRequirer`].
+///
+/// Paths in the archive are relative to the archive root and always use `/` as a separator.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ModuleArchive {
+    // Module bytecode and configuration files, keyed by path
+    files: Files,
+}
+
+impl fmt::Debug for ModuleArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.files.keys()).finish()
+    }
+}
+
+impl ModuleArchive {
+    /// Creates a new empty archive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles all modules found in the directory `dir` (recursively) into a new archive.
+    ///
+    /// Files with `.luau` and `.lua` extensions are compiled using the `compiler`, and `.luaurc`
+    /// configuration files are stored as is. Other files are ignored.
+    pub fn from_dir(dir: impl AsRef<Path>, compiler: &Compiler) -> Result<Self> {
+        let mut archive = Self::new();
+        archive.add_dir(dir.as_ref(), "", compiler)?;
+        Ok(archive)
+    }
+
+    fn add_dir(&mut self, dir: &Path, prefix: &str, compiler: &Compiler) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = (entry.file_name().into_string())
+                .map_err(|name| Error::runtime(format!("invalid file name {name:?}")))?;
+            let path = format!("{prefix}{name}");
+            if entry.file_type()?.is_dir() {
+                self.add_dir(&entry.path(), &format!("{path}/"), compiler)?;
+            } else if name == CONFIG_FILE {
+                self.files.insert(path, fs::read(entry.path())?);
+            } else if is_module_file(&name) {
+                let source = fs::read(entry.path())?;
+                let bytecode = (compiler.compile(source)).context(format!("failed to compile {path}"))?;
+                self.files.insert(path, bytecode);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds compiled module bytecode to the archive under the given path (with extension).
+    ///
+    /// The bytecode is checked using the [bytecode verifier], so modules are loaded from the archive
+    /// without verifying them again.
+    ///
+    /// [bytecode verifier]: crate::bytecode::Bytecode::verify
+    pub fn add_module(&mut self, path: impl Into<String>, bytecode: impl Into<Vec<u8>>) -> Result<()> {
+        let (path, bytecode) = (path.into(), bytecode.into());
+        verify_module(&path, &bytecode)?;
+        self.files.insert(path, bytecode);
+        Ok(())
+    }
+
+    /// Adds a `.luaurc` configuration file to the archive in the given directory.
+    pub fn add_config(&mut self, dir: impl AsRef<str>, config: impl Into<Vec<u8>>) {
+        let path = config_path(dir.as_ref().trim_matches('/'));
+        self.files.insert(path, config.into());
+    }
+
+    /// Returns an iterator over the paths of all files in the archive.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(|path| path.as_str())
+    }
+
+    /// Returns the content of the file (module bytecode or configuration) at the given path.
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(|data| data.as_slice())
+    }
+
+    /// Serializes the archive into bytes.
+    ///
+    /// The output is deterministic: archives with the same content produce the same bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = ARCHIVE_MAGIC.to_vec();
+        data.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for (path, content) in &self.files {
+            for field in [path.as_bytes(), content] {
+                data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+                data.extend_from_slice(field);
+            }
+        }
+        data
+    }
+
+    /// Deserializes an archive from bytes.
+    ///
+    /// Module bytecode is checked using the [bytecode verifier].
+    ///
+    /// [bytecode verifier]: crate::bytecode::Bytecode::verify
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let err = || Error::BytecodeError("invalid module archive: unexpected end of data".into());
+        let mut data = (data.strip_prefix(ARCHIVE_MAGIC))
+            .ok_or_else(|| Error::BytecodeError("invalid module archive: bad header".into()))?;
+        let count = read_u32(&mut data).ok_or_else(err)?;
+        let mut archive = Self::new();
+        for _ in 0..count {
+            let path = read_field(&mut data).ok_or_else(err)?;
+            let path = std::str::from_utf8(path)
+                .map_err(|_| Error::BytecodeError("invalid module archive: invalid path".into()))?;
+            let content = read_field(&mut data).ok_or_else(err)?;
+            if is_module_file(path) {
+                verify_module(path, content)?;
+            }
+            archive.files.insert(path.to_string(), content.to_vec());
+        }
+        Ok(archive)
+    }
+
+    /// Writes the archive to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> IoResult<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Reads the archive from a file.
+    pub fn open(path: impl AsRef<Path>) -> IoResult<Self> {
+        let data = fs::read(path)?;
+        Self::from_bytes(&data).map_err(|err| IoError::new(IoErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+fn read_u32(data: &mut &[u8]) -> Option<u32> {
+    let n = u32::from_le_bytes(data.get(..4)?.try_into().unwrap());
+    *data = &data[4..];
+    Some(n)
+}
+
+fn read_field<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = read_u32(data)? as usize;
+    let field = data.get(..len)?;
+    *data = &data[len..];
+    Some(field)
+}
+
+fn verify_module(path: &str, bytecode: &[u8]) -> Result<()> {
+    match Bytecode::parse(bytecode).and_then(|bytecode| bytecode.verify()) {
+        Err(Error::BytecodeError(msg)) => {
+            Err(Error::BytecodeError(format!("invalid module '{path}': {msg}")))
+        }
+        res => res,
+    }
+}
+
+fn is_module_file(name: &str) -> bool {
+    (name.rsplit_once('.')).is_some_and(|(_, ext)| TextRequirer::FILE_EXTENSIONS.contains(&ext))
+}
+
+/// An implementation of Luau `require-by-string` navigation that loads modules from a
+/// [`ModuleArchive`].
+///
+/// Modules are resolved in the same way as by [`TextRequirer`], using the archive root instead of
+/// the current directory. Module bytecode is verified before loading.
+///
+/// Module cache keys are the module paths in the archive prefixed with the requirer name
+/// (e.g. `archive-1/utils/init.luau`), see [`ArchiveRequirer::with_name`].
+#[derive(Clone, Debug)]
+pub struct ArchiveRequirer {
+    archive: Arc<ModuleArchive>,
+    name: String,
+    navigator: VirtualNavigator,
+}
+
+impl ArchiveRequirer {
+    /// Creates a new `ArchiveRequirer` serving modules from the `archive`.
+    ///
+    /// The requirer gets a name unique within the process (`archive-<n>`).
+    pub fn new(archive: impl Into<Arc<ModuleArchive>>) -> Self {
+        ArchiveRequirer {
+            archive: archive.into(),
+            name: unique_name("archive"),
+            navigator: VirtualNavigator::default(),
+        }
+    }
+
+    /// Sets the name used as a prefix of module cache keys.
+    ///
+    /// Requirers with the same name share cached modules, so the name should identify the archive
+    /// content.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Returns the name used as a prefix of module cache keys.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the archive used by this requirer.
+    pub fn archive(&self) -> &Arc<ModuleArchive> {
+        &self.archive
+    }
+}
+
+impl Require for ArchiveRequirer {
+    fn is_require_allowed(&self, chunk_name: &str) -> bool {
+        chunk_name.starts_with(TextRequirer::CHUNK_PREFIX)
+    }
+
+    fn reset(&mut self, chunk_name: &str) -> StdResult<(), NavigateError> {
+        self.navigator.reset(&self.archive.files, chunk_name)
+    }
+
+    fn jump_to_alias(&mut self, path: &str) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_alias(&self.archive.files, path)
+    }
+
+    fn to_parent(&mut self) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_parent(&self.archive.files)
+    }
+
+    fn to_child(&mut self, name: &str) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_child(&self.archive.files, name)
+    }
+
+    fn has_module(&self) -> bool {
+        self.navigator.resolved_path().is_some()
+    }
+
+    fn cache_key(&self) -> String {
+        format!("{}/{}", self.name, self.navigator.resolved_path().unwrap())
+    }
+
+    fn has_config(&self) -> bool {
+        self.navigator.config(&self.archive.files).is_some()
+    }
+
+    fn config(&self) -> IoResult<Vec<u8>> {
+        (self.navigator.config(&self.archive.files))
+            .map(|config| config.to_vec())
+            .ok_or_else(|| IoError::from(IoErrorKind::NotFound))
+    }
+
+    fn loader(&self, lua: &Lua) -> Result<Function> {
+        let path = self.navigator.resolved_path().unwrap();
+        lua.load(&self.archive.files[path])
+            .set_name(self.navigator.chunk_name())
+            .set_mode(ChunkMode::Binary)
+            // Bytecode is verified when added to the archive
+            .set_trusted()
+            .into_function()
+    }
+}
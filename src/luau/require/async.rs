@@ -0,0 +1,401 @@
+use std::ffi::CStr;
+use std::future::Future;
+use std::io::Result as IoResult;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::PathBuf;
+use std::ptr;
+use std::result::Result as StdResult;
+use std::sync::Arc;
+
+use futures_util::lock::Mutex as AsyncMutex;
+use parking_lot::Mutex;
+
+use super::{cache, create_require_wrapper, write_to_buffer, IntoNavigateResult, NavigateError, WriteResult};
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::state::{callback_error_ext, Lua};
+use crate::types::MaybeSend;
+
+/// An async variant of the [`Require`] trait.
+///
+/// Navigation, configuration and module loading can be non-blocking, which allows to fetch modules
+/// from remote or async storages. The `require` function created by
+/// [`Lua::create_async_require_function`] suspends the calling coroutine while waiting, so it must be
+/// called from an async context (e.g. [`Function::call_async`]).
+///
+/// Methods have the same meaning as in [`Require`].
+///
+/// # Limitations
+///
+/// Luau navigates synchronously, so every time navigation reaches an operation that has to be
+/// awaited, it is aborted and replayed from the beginning once the operation completes. Each
+/// navigation method is therefore called once, but resolving a path of `n` components takes
+/// `O(n²)` replayed steps.
+///
+/// Host modules (see [`Require::host_module`]) and [`RequirePolicy`] are not supported by the async `require`.
+///
+/// [`Require`]: super::Require
+/// [`Require::host_module`]: super::Require::host_module
+/// [`RequirePolicy`]: super::RequirePolicy
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub trait AsyncRequire {
+    /// Returns `true` if "require" is permitted for the given chunk name.
+    fn is_require_allowed(&self, chunk_name: &str) -> bool;
+
+    /// Resets the internal state to point at the requirer module.
+    fn reset(&mut self, chunk_name: &str) -> impl Future<Output = StdResult<(), NavigateError>> + MaybeSend;
+
+    /// Resets the internal state to point at an aliased module.
+    ///
+    /// It's only called when an alias's path cannot be resolved relative to its configuration file.
+    fn jump_to_alias(&mut self, path: &str)
+        -> impl Future<Output = StdResult<(), NavigateError>> + MaybeSend;
+
+    /// Navigate to parent directory.
+    fn to_parent(&mut self) -> impl Future<Output = StdResult<(), NavigateError>> + MaybeSend;
+
+    /// Navigate to the given child directory.
+    fn to_child(&mut self, name: &str) -> impl Future<Output = StdResult<(), NavigateError>> + MaybeSend;
+
+    /// Returns whether the context is currently pointing at a module.
+    fn has_module(&self) -> bool;
+
+    /// Provides a cache key representing the current module.
+    ///
+    /// This function is only called if `has_module` returns true.
+    fn cache_key(&self) -> String;
+
+    /// Returns whether a configuration is present in the current context.
+    fn has_config(&mut self) -> impl Future<Output = bool> + MaybeSend;
+
+    /// Returns the contents of the configuration file in the current context.
+    ///
+    /// This function is only called if `has_config` returns true.
+    fn config(&mut self) -> impl Future<Output = IoResult<Vec<u8>>> + MaybeSend;
+
+    /// Returns a loader function for the current module, that when called, loads the module
+    /// and returns the result.
+    ///
+    /// Loader can be sync or async.
+    /// This function is only called if `has_module` returns true.
+    fn loader(&mut self, lua: &Lua) -> impl Future<Output = Result<Function>> + MaybeSend;
+
+    /// Returns the path of the file that contains the current module, if any.
+    ///
+    /// This function is only called if `has_module` returns true.
+    fn module_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+// Luau navigates synchronously, so the navigation is replayed every time it reaches an operation
+// that has not been completed yet. The operation is then executed asynchronously and its outcome
+// recorded, until the navigation succeeds.
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operation {
+    IsRequireAllowed(String),
+    Reset(String),
+    JumpToAlias(String),
+    ToParent,
+    ToChild(String),
+    HasModule,
+    CacheKey,
+    HasConfig,
+    Config,
+    Loader,
+}
+
+#[derive(Clone)]
+enum Outcome {
+    Bool(bool),
+    Navigate(StdResult<(), NavigateError>),
+    CacheKey(String),
+    Config(Result<Vec<u8>>),
+    Loader(Result<Function>),
+}
+
+impl Operation {
+    async fn run<R: AsyncRequire>(self, requirer: &mut R, lua: &Lua) -> Outcome {
+        match self {
+            Operation::IsRequireAllowed(chunk_name) => {
+                Outcome::Bool(requirer.is_require_allowed(&chunk_name))
+            }
+            Operation::Reset(chunk_name) => Outcome::Navigate(requirer.reset(&chunk_name).await),
+            Operation::JumpToAlias(path) => Outcome::Navigate(requirer.jump_to_alias(&path).await),
+            Operation::ToParent => Outcome::Navigate(requirer.to_parent().await),
+            Operation::ToChild(name) => Outcome::Navigate(requirer.to_child(&name).await),
+            Operation::HasModule => Outcome::Bool(requirer.has_module()),
+            Operation::CacheKey => Outcome::CacheKey(requirer.cache_key()),
+            Operation::HasConfig => Outcome::Bool(requirer.has_config().await),
+            Operation::Config => Outcome::Config(requirer.config().await.map_err(Error::from)),
+            Operation::Loader => {
+                let loader = requirer.loader(lua).await;
+                if loader.is_ok() {
+                    cache::record_module(&lua.lock(), requirer.cache_key(), requirer.module_path());
+                }
+                Outcome::Loader(loader)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Replay {
+    /// Completed operations, in order
+    log: Vec<(Operation, Outcome)>,
+    /// Position of the next operation in the log
+    position: usize,
+    /// Operation that must be executed before the navigation can continue
+    pending: Option<Operation>,
+    /// Cache key of the last resolved module
+    cache_key: String,
+}
+
+impl Replay {
+    fn next(&mut self, op: Operation) -> Result<Outcome> {
+        match self.log.get(self.position) {
+            Some((logged, outcome)) if *logged == op => {
+                self.position += 1;
+                Ok(outcome.clone())
+            }
+            Some(_) => Err(Error::runtime("require navigation is not deterministic")),
+            None => {
+                self.pending = Some(op);
+                Err(Error::runtime("require operation is not completed"))
+            }
+        }
+    }
+
+    fn next_bool(&mut self, op: Operation) -> Result<bool> {
+        match self.next(op)? {
+            Outcome::Bool(value) => Ok(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn next_navigate(&mut self, op: Operation) -> Result<ffi::luarequire_NavigateResult> {
+        match self.next(op)? {
+            Outcome::Navigate(result) => result.into_nav_result(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.log.clear();
+        self.position = 0;
+        self.pending = None;
+    }
+}
+
+unsafe fn replay<'a>(ctx: *mut c_void) -> parking_lot::MutexGuard<'a, Replay> {
+    (*(ctx as *const Mutex<Replay>)).lock()
+}
+
+unsafe extern "C-unwind" fn init_config(config: *mut ffi::luarequire_Configuration) {
+    if config.is_null() {
+        return;
+    }
+
+    unsafe extern "C-unwind" fn is_require_allowed(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        requirer_chunkname: *const c_char,
+    ) -> bool {
+        if requirer_chunkname.is_null() {
+            return false;
+        }
+
+        let chunk_name = CStr::from_ptr(requirer_chunkname).to_string_lossy();
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_bool(Operation::IsRequireAllowed(chunk_name.into_owned()))
+        })
+    }
+
+    unsafe extern "C-unwind" fn reset(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        requirer_chunkname: *const c_char,
+    ) -> ffi::luarequire_NavigateResult {
+        let chunk_name = CStr::from_ptr(requirer_chunkname).to_string_lossy();
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_navigate(Operation::Reset(chunk_name.into_owned()))
+        })
+    }
+
+    unsafe extern "C-unwind" fn jump_to_alias(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        path: *const c_char,
+    ) -> ffi::luarequire_NavigateResult {
+        let path = CStr::from_ptr(path).to_string_lossy();
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_navigate(Operation::JumpToAlias(path.into_owned()))
+        })
+    }
+
+    unsafe extern "C-unwind" fn to_parent(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+    ) -> ffi::luarequire_NavigateResult {
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_navigate(Operation::ToParent)
+        })
+    }
+
+    unsafe extern "C-unwind" fn to_child(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        name: *const c_char,
+    ) -> ffi::luarequire_NavigateResult {
+        let name = CStr::from_ptr(name).to_string_lossy();
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_navigate(Operation::ToChild(name.into_owned()))
+        })
+    }
+
+    unsafe extern "C-unwind" fn is_module_present(state: *mut ffi::lua_State, ctx: *mut c_void) -> bool {
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_bool(Operation::HasModule)
+        })
+    }
+
+    unsafe extern "C-unwind" fn get_chunkname(
+        _state: *mut ffi::lua_State,
+        _ctx: *mut c_void,
+        buffer: *mut c_char,
+        buffer_size: usize,
+        size_out: *mut usize,
+    ) -> WriteResult {
+        write_to_buffer(buffer, buffer_size, size_out, &[])
+    }
+
+    unsafe extern "C-unwind" fn get_loadname(
+        _state: *mut ffi::lua_State,
+        _ctx: *mut c_void,
+        buffer: *mut c_char,
+        buffer_size: usize,
+        size_out: *mut usize,
+    ) -> WriteResult {
+        write_to_buffer(buffer, buffer_size, size_out, &[])
+    }
+
+    unsafe extern "C-unwind" fn get_cache_key(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        buffer: *mut c_char,
+        buffer_size: usize,
+        size_out: *mut usize,
+    ) -> WriteResult {
+        let cache_key = callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            let mut replay = replay(ctx);
+            match replay.next(Operation::CacheKey)? {
+                Outcome::CacheKey(cache_key) => {
+                    replay.cache_key.clone_from(&cache_key);
+                    Ok(cache_key)
+                }
+                _ => unreachable!(),
+            }
+        });
+        write_to_buffer(buffer, buffer_size, size_out, cache_key.as_bytes())
+    }
+
+    unsafe extern "C-unwind" fn is_config_present(state: *mut ffi::lua_State, ctx: *mut c_void) -> bool {
+        callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            replay(ctx).next_bool(Operation::HasConfig)
+        })
+    }
+
+    unsafe extern "C-unwind" fn get_config(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        buffer: *mut c_char,
+        buffer_size: usize,
+        size_out: *mut usize,
+    ) -> WriteResult {
+        let config = callback_error_ext(state, ptr::null_mut(), true, move |_, _| {
+            match replay(ctx).next(Operation::Config)? {
+                Outcome::Config(config) => config,
+                _ => unreachable!(),
+            }
+        });
+        write_to_buffer(buffer, buffer_size, size_out, &config)
+    }
+
+    unsafe extern "C-unwind" fn load(
+        state: *mut ffi::lua_State,
+        ctx: *mut c_void,
+        _path: *const c_char,
+        _chunkname: *const c_char,
+        _loadname: *const c_char,
+    ) -> c_int {
+        callback_error_ext(state, ptr::null_mut(), true, move |extra, _| {
+            let loader = match replay(ctx).next(Operation::Loader)? {
+                Outcome::Loader(loader) => loader?,
+                _ => unreachable!(),
+            };
+            (*extra).raw_lua().push(state, loader)?;
+            Ok(1)
+        })
+    }
+
+    (*config).is_require_allowed = is_require_allowed;
+    (*config).reset = reset;
+    (*config).jump_to_alias = jump_to_alias;
+    (*config).to_parent = to_parent;
+    (*config).to_child = to_child;
+    (*config).is_module_present = is_module_present;
+    (*config).get_chunkname = get_chunkname;
+    (*config).get_loadname = get_loadname;
+    (*config).get_cache_key = get_cache_key;
+    (*config).is_config_present = is_config_present;
+    (*config).get_alias = None;
+    (*config).get_config = Some(get_config);
+    (*config).load = load;
+}
+
+pub(crate) fn create_async_require_function<R: AsyncRequire + MaybeSend + 'static>(
+    lua: &Lua,
+    require: R,
+) -> Result<Function> {
+    let requirer = Arc::new(AsyncMutex::new(require));
+    let replay = Arc::new(Mutex::new(Replay::default()));
+
+    // The context is kept alive by the `resolve` function, which is the only caller of `proxyrequire`
+    let context_ptr = Arc::as_ptr(&replay) as *mut c_void;
+    let proxyrequire = unsafe {
+        lua.exec_raw::<Function>((), move |state| {
+            ffi::luarequire_pushproxyrequire(state, init_config, context_ptr);
+        })
+    }?;
+
+    let replay2 = replay.clone();
+    let resolve = lua.create_async_function(move |lua, (path, chunk_name): (String, String)| {
+        let (requirer, replay, proxyrequire) = (requirer.clone(), replay2.clone(), proxyrequire.clone());
+        async move {
+            // Navigation state is shared, so modules are resolved one at a time
+            let mut requirer = requirer.lock().await;
+            replay.lock().clear();
+            loop {
+                let result = proxyrequire.call::<Function>((path.as_str(), chunk_name.as_str()));
+                let pending = replay.lock().pending.take();
+                match (result, pending) {
+                    (Err(_), Some(op)) => {
+                        let outcome = op.clone().run(&mut *requirer, &lua).await;
+                        let mut replay = replay.lock();
+                        replay.log.push((op, outcome));
+                        replay.position = 0;
+                    }
+                    (result, _) => {
+                        replay.lock().clear();
+                        return result;
+                    }
+                }
+            }
+        }
+    })?;
+
+    let get_cache_key = lua.create_function(move |_, ()| Ok(replay.lock().cache_key.clone()))?;
+
+    create_require_wrapper(lua, get_cache_key, resolve, None, None)
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::error::Result;
+use crate::state::{Lua, RawLua};
+use crate::table::Table;
+use crate::value::Value;
+
+// Registry table with results of module loaders (see `create_require_function`)
+const LOADER_CACHE: &str = "__ULUA_LOADER_CACHE";
+
+/// Information about a module loaded by a `require` function.
+///
+/// See [`Lua::loaded_modules`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct LoadedModule {
+    /// The cache key of the module.
+    pub cache_key: String,
+    /// The path of the file that contains the module, if known (see [`Require::module_path`]).
+    ///
+    /// [`Require::module_path`]: super::Require::module_path
+    pub path: Option<PathBuf>,
+    /// The time when the module was loaded.
+    ///
+    /// It's `None` for values added to the cache without calling a module loader.
+    pub loaded_at: Option<SystemTime>,
+}
+
+// Paths and load times of modules, keyed by cache key
+#[derive(Default)]
+struct ModulesInfo(BTreeMap<String, (Option<PathBuf>, SystemTime)>);
+
+/// Records a module that is about to be loaded.
+pub(super) fn record_module(lua: &RawLua, cache_key: String, path: Option<PathBuf>) {
+    let info = (path, SystemTime::now());
+    match lua.priv_app_data_mut::<ModulesInfo>() {
+        Some(mut modules) => {
+            modules.0.insert(cache_key, info);
+        }
+        None => {
+            let mut modules = ModulesInfo::default();
+            modules.0.insert(cache_key, info);
+            lua.set_priv_app_data(modules);
+        }
+    }
+}
+
+impl Lua {
+    /// Returns the modules loaded by `require` functions, ordered by cache key.
+    ///
+    /// Only modules that are currently cached are returned (see [`Lua::evict_module`]).
+    pub fn loaded_modules(&self) -> Result<Vec<LoadedModule>> {
+        let Some(cache) = self.named_registry_value::<Option<Table>>(LOADER_CACHE)? else {
+            return Ok(Vec::new());
+        };
+        let lua = self.lock();
+        let info = lua.priv_app_data_ref::<ModulesInfo>();
+        let mut modules = Vec::new();
+        for pair in cache.pairs::<String, Value>() {
+            let (cache_key, _) = pair?;
+            let info = info.as_ref().and_then(|info| info.0.get(&cache_key));
+            modules.push(LoadedModule {
+                path: info.and_then(|(path, _)| path.clone()),
+                loaded_at: info.map(|&(_, loaded_at)| loaded_at),
+                cache_key,
+            });
+        }
+        modules.sort_by(|a, b| a.cache_key.cmp(&b.cache_key));
+        Ok(modules)
+    }
+
+    /// Returns the cached value of the module with the given cache key.
+    ///
+    /// This is the value returned by `require` for the module.
+    pub fn cached_module(&self, cache_key: &str) -> Result<Option<Value>> {
+        let Some(cache) = self.named_registry_value::<Option<Table>>(LOADER_CACHE)? else {
+            return Ok(None);
+        };
+        cache.raw_get(cache_key)
+    }
+
+    /// Removes the module with the given cache key from the cache, so the next `require` call
+    /// loads it again.
+    ///
+    /// Values of the module already returned by `require` are not affected.
+    /// Returns `true` if the module was cached.
+    pub fn evict_module(&self, cache_key: &str) -> Result<bool> {
+        let mut cached = false;
+        if let Some(cache) = self.named_registry_value::<Option<Table>>(LOADER_CACHE)? {
+            cached = cache.contains_key(cache_key)?;
+            cache.raw_remove(cache_key)?;
+        }
+        unsafe {
+            // Loaders are cached by Luau too
+            self.exec_raw::<()>(cache_key, |state| {
+                ffi::luarequire_clearcacheentry(state);
+            })?;
+        }
+        if let Some(mut info) = self.lock().priv_app_data_mut::<ModulesInfo>() {
+            info.0.remove(cache_key);
+        }
+        Ok(cached)
+    }
+
+    /// Removes all modules from the cache, so the next `require` calls load them again.
+    ///
+    /// Modules registered using [`Lua::register_module`] are not affected.
+    pub fn clear_module_cache(&self) -> Result<()> {
+        // The cache table is shared with `require` functions, so it's cleared in place
+        if let Some(cache) = self.named_registry_value::<Option<Table>>(LOADER_CACHE)? {
+            cache.clear()?;
+        }
+        unsafe {
+            self.exec_raw::<()>((), |state| {
+                ffi::luarequire_clearcache(state);
+            })?;
+        }
+        let lua = self.lock();
+        if let Some(mut info) = lua.priv_app_data_mut::<ModulesInfo>() {
+            info.0.clear();
+        }
+        super::graph::clear_graph(&lua);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeSet;
+
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::state::{Lua, RawLua};
+use crate::table::Table;
+
+/// A graph of dependencies between modules, recorded by `require` functions.
+///
+/// Edges point from the chunk name of the requiring code (e.g. `@src/main`) to the cache key of
+/// the required module (see [`Require::cache_key`]). Modules registered using
+/// [`Lua::register_module`] are not tracked.
+///
+/// See [`Lua::module_graph`].
+///
+/// [`Require::cache_key`]: super::Require::cache_key
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModuleGraph {
+    edges: BTreeSet<(String, String)>,
+}
+
+impl ModuleGraph {
+    /// Returns an iterator over all edges of the graph, as `(chunk_name, cache_key)` pairs.
+    ///
+    /// Edges are ordered by chunk name and then by cache key.
+    pub fn edges(&self) -> impl Iterator<Item = (&str, &str)> {
+        (self.edges.iter()).map(|(chunk_name, cache_key)| (chunk_name.as_str(), cache_key.as_str()))
+    }
+
+    /// Returns cache keys of the modules required by the chunk with the given name.
+    pub fn dependencies<'a>(&'a self, chunk_name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        (self.edges())
+            .filter(move |&(from, _)| from == chunk_name)
+            .map(|(_, to)| to)
+    }
+
+    /// Returns chunk names of the code that required the module with the given cache key.
+    pub fn dependents<'a>(&'a self, cache_key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        (self.edges())
+            .filter(move |&(_, to)| to == cache_key)
+            .map(|(from, _)| from)
+    }
+
+    /// Returns the number of edges in the graph.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns `true` if the graph has no edges.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+/// Records that the chunk with the given name has required a module.
+fn add_dependency(lua: &RawLua, chunk_name: String, cache_key: String) {
+    match lua.priv_app_data_mut::<ModuleGraph>() {
+        Some(mut graph) => {
+            graph.edges.insert((chunk_name, cache_key));
+        }
+        None => {
+            let mut graph = ModuleGraph::default();
+            graph.edges.insert((chunk_name, cache_key));
+            lua.set_priv_app_data(graph);
+        }
+    }
+}
+
+/// Removes all edges from the graph.
+pub(super) fn clear_graph(lua: &RawLua) {
+    if let Some(mut graph) = lua.priv_app_data_mut::<ModuleGraph>() {
+        graph.edges.clear();
+    }
+}
+
+/// Creates the functions used by the "require" wrapper to record dependencies and detect cycles.
+///
+/// Loaders of modules that are being loaded are tracked in a table with weak keys. Before a module
+/// loader is called, the call stack is inspected for active loaders, which gives the chain of
+/// modules that are currently loading in this thread.
+pub(super) fn create_tracking_functions(lua: &Lua) -> Result<(Function, Function)> {
+    let add_dependency = lua.create_function(|lua, (chunk_name, cache_key): (String, String)| {
+        add_dependency(&lua.lock(), chunk_name, cache_key);
+        Ok(())
+    })?;
+
+    let loaders = lua.create_table()?;
+    loaders.set_metatable(Some(lua.create_table_from([("__mode", "k")])?))?;
+    let enter_module = lua.create_function(move |lua, (cache_key, loader): (String, Function)| {
+        check_cycle(lua, &loaders, &cache_key)?;
+        loaders.raw_set(loader, cache_key)
+    })?;
+
+    Ok((add_dependency, enter_module))
+}
+
+fn check_cycle(lua: &Lua, loaders: &Table, cache_key: &str) -> Result<()> {
+    let mut chain = Vec::new();
+    let mut level = 1;
+    while let Some(function) = lua.inspect_stack(level, |debug| debug.function()) {
+        if let Some(key) = loaders.raw_get::<Option<String>>(function)? {
+            chain.push(key);
+        }
+        level += 1;
+    }
+    chain.reverse();
+    if let Some(pos) = chain.iter().position(|key| key == cache_key) {
+        chain.drain(..pos);
+        chain.push(cache_key.to_string());
+        return Err(Error::RequireCycle { chain });
+    }
+    Ok(())
+}
+
+impl Lua {
+    /// Returns the graph of dependencies between modules loaded by `require` functions.
+    ///
+    /// The graph contains all `require` calls made since the state was created or since the last
+    /// call to [`Lua::clear_module_cache`].
+    pub fn module_graph(&self) -> ModuleGraph {
+        let lua = self.lock();
+        (lua.priv_app_data_ref::<ModuleGraph>())
+            .map(|graph| graph.clone())
+            .unwrap_or_default()
+    }
+}
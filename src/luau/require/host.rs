@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::io::Result as IoResult;
+use std::path::PathBuf;
+use std::result::Result as StdResult;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use super::{cache, NavigateError, Require};
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::state::Lua;
+use crate::traits::IntoLua;
+use crate::types::MaybeSend;
+use crate::value::Value;
+
+#[cfg(feature = "send")]
+type ModuleInit = Box<dyn Fn(&Lua) -> Result<Value> + Send>;
+
+#[cfg(not(feature = "send"))]
+type ModuleInit = Box<dyn Fn(&Lua) -> Result<Value>>;
+
+/// A [`Require`] wrapper that mounts modules provided by Rust code under a reserved alias.
+///
+/// Host modules are required using the same syntax as other modules, e.g. `require("@host/net")`
+/// for a module named `net` under the default `host` alias. All paths under the alias are reserved:
+/// requiring a module that was not added is an error, and other paths are resolved by the wrapped
+/// requirer. Module names and the alias are case-insensitive.
+///
+/// Host modules are constructed on first `require` and cached like other modules, using the
+/// `@<alias>/<name>` path as the cache key (see [`Lua::loaded_modules`]).
+///
+/// # Examples
+///
+/// ```
+/// # use ulua::{HostModules, Lua, MemoryRequirer, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let requirer = MemoryRequirer::new().with_module("main.luau", "return require('@host/version')");
+/// let requirer = HostModules::new(requirer)
+///     .with_module("version", "1.0")
+///     .with_lazy_module("db", |lua| lua.create_table());
+/// lua.globals().set("require", lua.create_require_function(requirer)?)?;
+///
+/// let version: String = lua.load("return require('./main')").eval()?;
+/// assert_eq!(version, "1.0");
+/// # Ok(())
+/// # }
+/// ```
+pub struct HostModules<R> {
+    requirer: R,
+    alias: String,
+    modules: BTreeMap<String, Arc<Mutex<ModuleInit>>>,
+}
+
+impl<R: Require> HostModules<R> {
+    /// Creates a new `HostModules` wrapper around the given requirer, using the `host` alias.
+    pub fn new(requirer: R) -> Self {
+        HostModules {
+            requirer,
+            alias: "host".to_string(),
+            modules: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the alias (without `@`) under which the host modules are mounted.
+    pub fn with_alias(mut self, alias: impl AsRef<str>) -> Self {
+        self.alias = alias.as_ref().trim_start_matches('@').to_ascii_lowercase();
+        self
+    }
+
+    /// Adds a module with the given name and value.
+    pub fn with_module(
+        self,
+        name: impl AsRef<str>,
+        value: impl IntoLua + Clone + MaybeSend + 'static,
+    ) -> Self {
+        self.with_lazy_module(name, move |lua| value.clone().into_lua(lua))
+    }
+
+    /// Adds a module with the given name, that is constructed by the function on first `require`.
+    ///
+    /// Errors returned by the function are raised in the requiring code, and the function is
+    /// called again on the next `require`.
+    pub fn with_lazy_module<F, V>(mut self, name: impl AsRef<str>, init: F) -> Self
+    where
+        F: Fn(&Lua) -> Result<V> + MaybeSend + 'static,
+        V: IntoLua,
+    {
+        let init: ModuleInit = Box::new(move |lua| init(lua)?.into_lua(lua));
+        let name = name.as_ref().to_ascii_lowercase();
+        self.modules.insert(name, Arc::new(Mutex::new(init)));
+        self
+    }
+
+    /// Returns a reference to the wrapped requirer.
+    pub fn requirer(&self) -> &R {
+        &self.requirer
+    }
+}
+
+impl<R: Require> Require for HostModules<R> {
+    fn is_require_allowed(&self, chunk_name: &str) -> bool {
+        self.requirer.is_require_allowed(chunk_name)
+    }
+
+    fn reset(&mut self, chunk_name: &str) -> StdResult<(), NavigateError> {
+        self.requirer.reset(chunk_name)
+    }
+
+    fn jump_to_alias(&mut self, path: &str) -> StdResult<(), NavigateError> {
+        self.requirer.jump_to_alias(path)
+    }
+
+    fn to_parent(&mut self) -> StdResult<(), NavigateError> {
+        self.requirer.to_parent()
+    }
+
+    fn to_child(&mut self, name: &str) -> StdResult<(), NavigateError> {
+        self.requirer.to_child(name)
+    }
+
+    fn has_module(&self) -> bool {
+        self.requirer.has_module()
+    }
+
+    fn cache_key(&self) -> String {
+        self.requirer.cache_key()
+    }
+
+    fn has_config(&self) -> bool {
+        self.requirer.has_config()
+    }
+
+    fn config(&self) -> IoResult<Vec<u8>> {
+        self.requirer.config()
+    }
+
+    fn loader(&self, lua: &Lua) -> Result<Function> {
+        self.requirer.loader(lua)
+    }
+
+    fn module_path(&self) -> Option<PathBuf> {
+        self.requirer.module_path()
+    }
+
+    fn host_module(&self, lua: &Lua, path: &str) -> Result<Option<(String, Function)>> {
+        let rest = path.strip_prefix('@').unwrap_or_default();
+        let (alias, name) = rest.split_once('/').unwrap_or((rest, ""));
+        if !path.starts_with('@') || !alias.eq_ignore_ascii_case(&self.alias) {
+            return self.requirer.host_module(lua, path);
+        }
+
+        let name = name.to_ascii_lowercase();
+        let init = (self.modules.get(&name).cloned())
+            .ok_or_else(|| Error::runtime(format!("module '{path}' is not provided by the host")))?;
+        let cache_key = format!("@{}/{name}", self.alias);
+        let loader = lua.create_function({
+            let cache_key = cache_key.clone();
+            move |lua, ()| {
+                let value = (init.lock())(lua)?;
+                cache::record_module(&lua.lock(), cache_key.clone(), None);
+                Ok(value)
+            }
+        })?;
+        Ok(Some((cache_key, loader)))
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::result::Result as StdResult;
+use std::sync::Arc;
+
+use super::vfs::{config_path, normalize_path, unique_name, Files, VirtualNavigator};
+use super::{NavigateError, Require, TextRequirer};
+use crate::chunk::ChunkMode;
+use crate::error::Result;
+use crate::function::Function;
+use crate::state::Lua;
+
+/// An implementation of Luau `require-by-string` navigation that loads modules from memory.
+///
+/// Modules are stored in a virtual file system, as a map of paths to module sources. Paths are
+/// relative to the virtual root, use `/` as a separator and include file extensions (`.luau` or
+/// `.lua`). Directories with `init.luau` files and `.luaurc` configuration files are supported, and
+/// modules are resolved in the same way as by [`TextRequirer`].
+///
+/// Modules required from Rust code are resolved relative to the virtual root.
+///
+/// Module cache keys are the module paths prefixed with the requirer name
+/// (e.g. `memory-1/utils/init.luau`), see [`MemoryRequirer::with_name`].
+///
+/// # Examples
+///
+/// ```
+/// # use ulua::{Lua, MemoryRequirer, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let requirer = MemoryRequirer::new()
+///     .with_module("utils/init.luau", "return { answer = require('@self/answer') }")
+///     .with_module("utils/answer.luau", "return 42")
+///     .with_config("", r#"{ "aliases": { "utils": "./utils" } }"#);
+/// lua.globals().set("require", lua.create_require_function(requirer)?)?;
+///
+/// let answer: i32 = lua.load("return require('@utils').answer").eval()?;
+/// assert_eq!(answer, 42);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MemoryRequirer {
+    files: Arc<Files>,
+    name: String,
+    navigator: VirtualNavigator,
+}
+
+impl Default for MemoryRequirer {
+    fn default() -> Self {
+        MemoryRequirer {
+            files: Arc::default(),
+            name: unique_name("memory"),
+            navigator: VirtualNavigator::default(),
+        }
+    }
+}
+
+impl MemoryRequirer {
+    /// Creates a new `MemoryRequirer` without modules.
+    ///
+    /// The requirer gets a name unique within the process (`memory-<n>`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name used as a prefix of module cache keys.
+    ///
+    /// Requirers with the same name share cached modules, so the name should identify the set of
+    /// modules.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Returns the name used as a prefix of module cache keys.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds a module source at the given path (with extension).
+    pub fn with_module(mut self, path: impl AsRef<str>, source: impl Into<Vec<u8>>) -> Self {
+        Arc::make_mut(&mut self.files).insert(normalize_path(path.as_ref()), source.into());
+        self
+    }
+
+    /// Adds a `.luaurc` configuration file in the given directory.
+    pub fn with_config(mut self, dir: impl AsRef<str>, config: impl Into<Vec<u8>>) -> Self {
+        let path = config_path(&normalize_path(dir.as_ref()));
+        Arc::make_mut(&mut self.files).insert(path, config.into());
+        self
+    }
+
+    /// Returns an iterator over the paths of all files in the virtual file system.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(|path| path.as_str())
+    }
+}
+
+impl<K: AsRef<str>, V: Into<Vec<u8>>> FromIterator<(K, V)> for MemoryRequirer {
+    /// Creates a `MemoryRequirer` from pairs of file paths and contents.
+    ///
+    /// Configuration files can be added using the `.luaurc` file name (e.g. `src/.luaurc`).
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        (iter.into_iter()).fold(Self::new(), |requirer, (path, content)| {
+            requirer.with_module(path, content)
+        })
+    }
+}
+
+impl Require for MemoryRequirer {
+    fn is_require_allowed(&self, chunk_name: &str) -> bool {
+        chunk_name.starts_with(TextRequirer::CHUNK_PREFIX)
+    }
+
+    fn reset(&mut self, chunk_name: &str) -> StdResult<(), NavigateError> {
+        self.navigator.reset(&self.files, chunk_name)
+    }
+
+    fn jump_to_alias(&mut self, path: &str) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_alias(&self.files, path)
+    }
+
+    fn to_parent(&mut self) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_parent(&self.files)
+    }
+
+    fn to_child(&mut self, name: &str) -> StdResult<(), NavigateError> {
+        self.navigator.jump_to_child(&self.files, name)
+    }
+
+    fn has_module(&self) -> bool {
+        self.navigator.resolved_path().is_some()
+    }
+
+    fn cache_key(&self) -> String {
+        format!("{}/{}", self.name, self.navigator.resolved_path().unwrap())
+    }
+
+    fn has_config(&self) -> bool {
+        self.navigator.config(&self.files).is_some()
+    }
+
+    fn config(&self) -> IoResult<Vec<u8>> {
+        (self.navigator.config(&self.files))
+            .map(|config| config.to_vec())
+            .ok_or_else(|| IoError::from(IoErrorKind::NotFound))
+    }
+
+    fn loader(&self, lua: &Lua) -> Result<Function> {
+        let path = self.navigator.resolved_path().unwrap();
+        lua.load(&self.files[path])
+            .set_name(self.navigator.chunk_name())
+            .set_mode(ChunkMode::Text)
+            .into_function()
+    }
+}
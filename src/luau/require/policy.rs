@@ -0,0 +1,57 @@
+use std::result::Result as StdResult;
+
+use crate::types::MaybeSend;
+
+/// A `require` call that is checked by a [`RequirePolicy`].
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct RequireRequest<'a> {
+    /// Chunk name of the code that calls `require` (e.g. `@plugins/foo/init`).
+    pub chunk_name: &'a str,
+    /// The path passed to `require`.
+    pub path: &'a str,
+    /// The cache key of the resolved module (see [`Require::cache_key`]).
+    ///
+    /// [`Require::cache_key`]: super::Require::cache_key
+    pub cache_key: &'a str,
+}
+
+/// A policy that decides which modules can be required by which code.
+///
+/// Unlike [`Require::is_require_allowed`], which only sees the requiring chunk, a policy is
+/// consulted after the module is resolved (but before it is loaded), and can allow or deny each
+/// requirer-to-module edge.
+/// The policy is checked on every `require` call, including calls that return a cached module,
+/// and is not applied to modules registered using [`Lua::register_module`].
+///
+/// It's implemented for closures that take a [`RequireRequest`] and return `Err(reason)` to deny
+/// the request.
+///
+/// See [`Lua::create_require_function_with_policy`].
+///
+/// [`Require::is_require_allowed`]: super::Require::is_require_allowed
+pub trait RequirePolicy: MaybeSend + 'static {
+    /// Checks whether the request is allowed.
+    ///
+    /// Returning `Err(reason)` denies the request, raising an error in the calling code.
+    fn check(&self, request: &RequireRequest) -> StdResult<(), String>;
+}
+
+impl<F> RequirePolicy for F
+where
+    F: Fn(&RequireRequest) -> StdResult<(), String> + MaybeSend + 'static,
+{
+    fn check(&self, request: &RequireRequest) -> StdResult<(), String> {
+        self(request)
+    }
+}
+
+/// Checks the request against the policy.
+///
+/// Returns the error message if the request is denied.
+pub(crate) fn check_request(policy: &dyn RequirePolicy, request: &RequireRequest) -> Option<String> {
+    (policy.check(request).err()).map(|reason| {
+        let (path, chunk_name) = (request.path, request.chunk_name);
+        format!("require '{path}' is not allowed from '{chunk_name}': {reason}")
+    })
+}
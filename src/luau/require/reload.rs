@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::{fmt, fs};
+
+use parking_lot::Mutex;
+
+use super::{cache, NavigateError, Require};
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::state::Lua;
+use crate::table::Table;
+use crate::types::MaybeSend;
+use crate::value::Value;
+
+// Registry table with results of module loaders (see `create_require_function`)
+const LOADER_CACHE: &str = "__ULUA_LOADER_CACHE";
+
+#[cfg(feature = "send")]
+type ReloadHook = Box<dyn Fn(&Lua, &ModuleReload) -> Result<()> + Send>;
+
+#[cfg(not(feature = "send"))]
+type ReloadHook = Box<dyn Fn(&Lua, &ModuleReload) -> Result<()>>;
+
+/// Information about a reloaded module, passed to the [`HotReloader::on_reload`] hook.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ModuleReload {
+    /// The cache key of the module.
+    pub cache_key: String,
+    /// The chunk name of the module.
+    pub chunk_name: String,
+    /// The value previously returned by the module.
+    pub old: Value,
+    /// The value returned by the reloaded module.
+    pub new: Value,
+    /// `true` if the module was not changed itself, but reloaded because one of its dependencies
+    /// was reloaded.
+    pub is_dependent: bool,
+}
+
+/// A [`Require`] wrapper that allows to reload modules when they are changed.
+///
+/// The reloader keeps track of the modules loaded through it, their files (see
+/// [`Require::module_path`]) and dependencies between them.
+/// When a module is reloaded, its loader is called again and the result replaces the cached value
+/// returned by `require`. Modules that (transitively) depend on it are reloaded after it, so they
+/// pick up the new value.
+///
+/// Values already captured by other code (for example, by the main script) are not updated.
+/// The [`HotReloader::on_reload`] hook can be used to migrate state from the old value to the new
+/// one, or to update the old value in place.
+///
+/// The reloader is cheap to clone, all clones share the same state.
+pub struct HotReloader<R> {
+    inner: Arc<Mutex<Inner<R>>>,
+}
+
+struct Inner<R> {
+    requirer: R,
+    /// Chunk name of the module that calls `require`
+    current: Option<String>,
+    /// Loaded modules, keyed by cache key
+    modules: BTreeMap<String, Module>,
+    hook: Option<ReloadHook>,
+}
+
+struct Module {
+    chunk_name: String,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    /// Cache keys of modules required by this module
+    dependencies: BTreeSet<String>,
+}
+
+impl<R> Clone for HotReloader<R> {
+    fn clone(&self) -> Self {
+        HotReloader {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<R> fmt::Debug for HotReloader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("HotReloader")
+            .field("modules", &inner.modules.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<R: Require> HotReloader<R> {
+    /// Creates a new `HotReloader` that uses `requirer` to find and load modules.
+    pub fn new(requirer: R) -> Self {
+        let inner = Inner {
+            requirer,
+            current: None,
+            modules: BTreeMap::new(),
+            hook: None,
+        };
+        HotReloader {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Sets a hook that is called after each module is reloaded.
+    ///
+    /// Dependencies are always reloaded (and the hook is called) before their dependents.
+    /// Returning an error from the hook stops reloading.
+    pub fn on_reload<F>(&self, hook: F)
+    where
+        F: Fn(&Lua, &ModuleReload) -> Result<()> + MaybeSend + 'static,
+    {
+        self.inner.lock().hook = Some(Box::new(hook));
+    }
+
+    /// Checks files of the loaded modules for changes and reloads the changed modules together with
+    /// their dependents.
+    ///
+    /// Returns cache keys of the reloaded modules, in the order they were reloaded.
+    ///
+    /// This function is expected to be called periodically by the host, for example once per frame
+    /// or when a file system watcher reports changes.
+    ///
+    /// A module that fails to reload keeps its previous version and is reloaded again on the next
+    /// call.
+    pub fn poll(&self, lua: &Lua) -> Result<Vec<String>> {
+        let mut modified_times = BTreeMap::new();
+        for (cache_key, module) in &self.inner.lock().modules {
+            let Some(path) = &module.path else { continue };
+            // Removed files are ignored, the module keeps its last version
+            let modified = modified_time(path);
+            if modified.is_some() && modified != module.modified {
+                modified_times.insert(cache_key.clone(), modified);
+            }
+        }
+        let changed = modified_times.keys().cloned().collect();
+        self.reload_modules(lua, changed, modified_times)
+    }
+
+    /// Reloads the module with the given cache key together with its dependents.
+    ///
+    /// Returns cache keys of the reloaded modules, in the order they were reloaded.
+    pub fn reload(&self, lua: &Lua, cache_key: &str) -> Result<Vec<String>> {
+        if !self.inner.lock().modules.contains_key(cache_key) {
+            return Err(Error::runtime(format!("module '{cache_key}' is not loaded")));
+        }
+        self.reload_modules(lua, vec![cache_key.to_string()], BTreeMap::new())
+    }
+
+    // Modification times of the changed files are recorded only after their modules are reloaded
+    fn reload_modules(
+        &self,
+        lua: &Lua,
+        changed: Vec<String>,
+        mut modified_times: BTreeMap<String, Option<SystemTime>>,
+    ) -> Result<Vec<String>> {
+        if changed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let order = self.inner.lock().reload_order(&changed);
+        let cache: Table = lua.named_registry_value(LOADER_CACHE)?;
+        for cache_key in &order {
+            // The lock must be released before calling the loader, as it can require other modules
+            let (chunk_name, loader) = self.inner.lock().prepare_reload(lua, cache_key)?;
+            let new = match loader.call(())? {
+                Value::Nil => Value::Boolean(true),
+                value => value,
+            };
+            let old = cache.raw_get(cache_key.as_str())?;
+            cache.raw_set(cache_key.as_str(), &new)?;
+            if let Some(modified) = modified_times.remove(cache_key) {
+                let mut inner = self.inner.lock();
+                if let Some(module) = inner.modules.get_mut(cache_key) {
+                    module.modified = modified;
+                }
+            }
+
+            // The hook is taken out while running, so it can require modules
+            let hook = self.inner.lock().hook.take();
+            if let Some(hook) = hook {
+                let is_dependent = !changed.contains(cache_key);
+                let event = ModuleReload {
+                    cache_key: cache_key.clone(),
+                    chunk_name,
+                    old,
+                    new,
+                    is_dependent,
+                };
+                let result = hook(lua, &event);
+                self.inner.lock().hook.get_or_insert(hook);
+                result?;
+            }
+        }
+        Ok(order)
+    }
+}
+
+impl<R: Require> Inner<R> {
+    // Returns the changed modules and their dependents, sorted so that dependencies come first
+    fn reload_order(&self, changed: &[String]) -> Vec<String> {
+        let mut affected = BTreeSet::new();
+        let mut stack = changed.to_vec();
+        while let Some(cache_key) = stack.pop() {
+            if affected.insert(cache_key.clone()) {
+                let dependents = (self.modules.iter())
+                    .filter(|(_, module)| module.dependencies.contains(&cache_key))
+                    .map(|(key, _)| key.clone());
+                stack.extend(dependents);
+            }
+        }
+
+        fn visit(
+            modules: &BTreeMap<String, Module>,
+            affected: &BTreeSet<String>,
+            visited: &mut BTreeSet<String>,
+            order: &mut Vec<String>,
+            cache_key: &String,
+        ) {
+            if !visited.insert(cache_key.clone()) {
+                return;
+            }
+            for dependency in &modules[cache_key].dependencies {
+                if affected.contains(dependency) {
+                    visit(modules, affected, visited, order, dependency);
+                }
+            }
+            order.push(cache_key.clone());
+        }
+
+        let (mut visited, mut order) = (BTreeSet::new(), Vec::new());
+        for cache_key in &affected {
+            visit(&self.modules, &affected, &mut visited, &mut order, cache_key);
+        }
+        order
+    }
+
+    // Navigates to the module and returns its chunk name and a new loader
+    fn prepare_reload(&mut self, lua: &Lua, cache_key: &str) -> Result<(String, Function)> {
+        let module = self.modules.get_mut(cache_key).expect("module is not loaded");
+        // Dependencies are collected again when the module is loaded
+        module.dependencies.clear();
+
+        let not_found = || Error::runtime(format!("cannot reload module '{cache_key}': module not found"));
+        match self.requirer.reset(&module.chunk_name) {
+            Ok(()) => {}
+            Err(NavigateError::Other(err)) => return Err(err),
+            Err(_) => return Err(not_found()),
+        }
+        if !self.requirer.has_module() || self.requirer.cache_key() != cache_key {
+            return Err(not_found());
+        }
+        let loader = self.requirer.loader(lua)?;
+        cache::record_module(&lua.lock(), cache_key.to_string(), self.requirer.module_path());
+        Ok((module.chunk_name.clone(), loader))
+    }
+}
+
+impl<R: Require> Require for HotReloader<R> {
+    fn is_require_allowed(&self, chunk_name: &str) -> bool {
+        self.inner.lock().requirer.is_require_allowed(chunk_name)
+    }
+
+    fn reset(&mut self, chunk_name: &str) -> StdResult<(), NavigateError> {
+        let mut inner = self.inner.lock();
+        inner.current = Some(chunk_name.to_string());
+        inner.requirer.reset(chunk_name)
+    }
+
+    fn jump_to_alias(&mut self, path: &str) -> StdResult<(), NavigateError> {
+        self.inner.lock().requirer.jump_to_alias(path)
+    }
+
+    fn to_parent(&mut self) -> StdResult<(), NavigateError> {
+        self.inner.lock().requirer.to_parent()
+    }
+
+    fn to_child(&mut self, name: &str) -> StdResult<(), NavigateError> {
+        self.inner.lock().requirer.to_child(name)
+    }
+
+    fn has_module(&self) -> bool {
+        self.inner.lock().requirer.has_module()
+    }
+
+    fn cache_key(&self) -> String {
+        self.inner.lock().requirer.cache_key()
+    }
+
+    fn has_config(&self) -> bool {
+        self.inner.lock().requirer.has_config()
+    }
+
+    fn config(&self) -> IoResult<Vec<u8>> {
+        self.inner.lock().requirer.config()
+    }
+
+    fn loader(&self, lua: &Lua) -> Result<Function> {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+        let loader = inner.requirer.loader(lua)?;
+        let cache_key = inner.requirer.cache_key();
+
+        // Modules are reloaded by navigating to their chunk name, which is only known for Luau loaders
+        let info = loader.info();
+        if let Some(chunk_name) = info.source.filter(|_| info.what != "C") {
+            inner.modules.entry(cache_key.clone()).or_insert_with(|| {
+                let path = inner.requirer.module_path();
+                let modified = path.as_deref().and_then(modified_time);
+                Module {
+                    chunk_name,
+                    path,
+                    modified,
+                    dependencies: BTreeSet::new(),
+                }
+            });
+        }
+
+        // Record the dependency of the requiring module
+        let current = inner.current.as_deref();
+        let requirer = (inner.modules.values_mut()).find(|module| Some(&*module.chunk_name) == current);
+        if let Some(requirer) = requirer {
+            requirer.dependencies.insert(cache_key);
+        }
+
+        Ok(loader)
+    }
+
+    fn module_path(&self) -> Option<PathBuf> {
+        self.inner.lock().requirer.module_path()
+    }
+
+    fn host_module(&self, lua: &Lua, path: &str) -> Result<Option<(String, Function)>> {
+        self.inner.lock().requirer.host_module(lua, path)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{NavigateError, TextRequirer};
+
+pub(super) const CONFIG_FILE: &str = ".luaurc";
+
+/// Files of a virtual file system, keyed by path relative to the root (using `/` as a separator).
+pub(super) type Files = BTreeMap<String, Vec<u8>>;
+
+/// Returns a default name for a virtual file system, unique within the process.
+///
+/// The name prefixes module cache keys, so modules from different file systems never share
+/// a cache entry.
+pub(super) fn unique_name(kind: &str) -> String {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+    format!("{kind}-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Implements `require-by-string` navigation over a virtual file system.
+///
+/// Modules are resolved in the same way as by [`TextRequirer`], using the virtual root instead of
+/// the current directory.
+#[derive(Clone, Debug, Default)]
+pub(super) struct VirtualNavigator {
+    /// Path components of the current module (not mapped to a file)
+    path: Vec<String>,
+    /// Path of the file for the current module
+    resolved_path: Option<String>,
+}
+
+impl VirtualNavigator {
+    pub(super) fn reset(&mut self, files: &Files, chunk_name: &str) -> StdResult<(), NavigateError> {
+        let chunk_name =
+            (chunk_name.strip_prefix(TextRequirer::CHUNK_PREFIX)).ok_or(NavigateError::NotFound)?;
+        let chunk_name = TextRequirer::normalize_chunk_name(chunk_name);
+
+        if chunk_name.ends_with(".rs") {
+            // Special case for Rust source files, reset to the root
+            let file_name = chunk_name.rsplit(['/', '\\']).next().unwrap_or(chunk_name);
+            self.path = vec![file_name.to_string()];
+            self.resolved_path = None;
+            return Ok(());
+        }
+
+        let mut path = Vec::new();
+        for component in chunk_name.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    path.pop().ok_or(NavigateError::NotFound)?;
+                }
+                _ => path.push(component.to_string()),
+            }
+        }
+        self.navigate(files, path)
+    }
+
+    pub(super) fn jump_to_alias(&mut self, files: &Files, path: &str) -> StdResult<(), NavigateError> {
+        // Absolute alias paths are relative to the root
+        self.reset(files, &format!("{}{path}", TextRequirer::CHUNK_PREFIX))
+    }
+
+    pub(super) fn jump_to_parent(&mut self, files: &Files) -> StdResult<(), NavigateError> {
+        let mut path = self.path.clone();
+        if path.pop().is_none() {
+            // Reached the root
+            return Err(NavigateError::NotFound);
+        }
+        self.navigate(files, path)
+    }
+
+    pub(super) fn jump_to_child(&mut self, files: &Files, name: &str) -> StdResult<(), NavigateError> {
+        let mut path = self.path.clone();
+        path.push(name.to_string());
+        self.navigate(files, path)
+    }
+
+    /// Returns path of the file for the current module.
+    pub(super) fn resolved_path(&self) -> Option<&str> {
+        self.resolved_path.as_deref()
+    }
+
+    /// Returns chunk name of the current module.
+    pub(super) fn chunk_name(&self) -> String {
+        format!("{}{}", TextRequirer::CHUNK_PREFIX, self.path.join("/"))
+    }
+
+    /// Returns content of the configuration file in the current directory.
+    pub(super) fn config<'a>(&self, files: &'a Files) -> Option<&'a [u8]> {
+        let config = config_path(&self.path.join("/"));
+        files.get(&config).map(|config| config.as_slice())
+    }
+
+    fn navigate(&mut self, files: &Files, path: Vec<String>) -> StdResult<(), NavigateError> {
+        self.resolved_path = resolve(files, &path.join("/"))?;
+        self.path = path;
+        Ok(())
+    }
+}
+
+pub(super) fn config_path(dir: &str) -> String {
+    match dir {
+        "" => CONFIG_FILE.to_string(),
+        dir => format!("{dir}/{CONFIG_FILE}"),
+    }
+}
+
+/// Normalizes a virtual file path, removing `.` components and leading or repeated separators.
+pub(super) fn normalize_path(path: &str) -> String {
+    let components = path.split(['/', '\\']).filter(|c| !c.is_empty() && *c != ".");
+    components.collect::<Vec<_>>().join("/")
+}
+
+fn is_dir(files: &Files, dir: &str) -> bool {
+    if dir.is_empty() {
+        return !files.is_empty();
+    }
+    let prefix = format!("{dir}/");
+    (files.range(prefix.clone()..).next()).is_some_and(|(path, _)| path.starts_with(&prefix))
+}
+
+// Resolves a module path (without extension) to a file
+fn resolve(files: &Files, path: &str) -> StdResult<Option<String>, NavigateError> {
+    let mut found = None;
+    let mut candidates = Vec::new();
+    if !path.is_empty() && path.rsplit('/').next() != Some("init") {
+        candidates.extend(
+            TextRequirer::FILE_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{path}.{ext}")),
+        );
+    }
+    let is_dir = is_dir(files, path);
+    if is_dir {
+        let dir = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{path}/")
+        };
+        candidates.extend(
+            TextRequirer::FILE_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{dir}init.{ext}")),
+        );
+    }
+    for candidate in candidates {
+        if files.contains_key(&candidate) && found.replace(candidate).is_some() {
+            return Err(NavigateError::Ambiguous);
+        }
+    }
+    match found {
+        Some(found) => Ok(Some(found)),
+        // Directories without init files are considered valid "intermediate" path
+        None if is_dir => Ok(None),
+        None => Err(NavigateError::NotFound),
+    }
+}
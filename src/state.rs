@@ -27,7 +27,10 @@ use crate::userdata::{AnyUserData, UserData, UserDataProxy, UserDataRegistry, Us
 use crate::util::{assert_stack, check_stack, protect_lua_closure, push_string, rawset_field, StackGuard};
 use crate::value::{Nil, Value};
 
-use crate::{buffer::Buffer, chunk::Compiler};
+use crate::{
+    buffer::Buffer,
+    chunk::{ChunkCache, Compiler},
+};
 
 #[cfg(feature = "async")]
 use {
@@ -41,7 +44,11 @@ use serde::Serialize;
 
 pub(crate) use extra::ExtraData;
 pub use raw::RawLua;
-pub(crate) use util::callback_error_ext;
+pub(crate) use util::{callback_error_ext, interrupt_proc, StateGuard};
+
+// Registry tables with registered modules and initializers of lazy modules (used by `require`)
+const LOADED_MODULES_KEY: *const c_char = ffi::LUA_REGISTERED_MODULES_TABLE;
+const LAZY_MODULES_KEY: *const c_char = cstr!("__ULUA_LAZY_MODULES");
 
 /// Top level Lua struct which represents an instance of Lua VM.
 pub struct Lua {
@@ -313,8 +320,6 @@ impl Lua {
     ///
     /// [required]: https://www.lua.org/manual/5.4/manual.html#pdf-require
     pub fn register_module(&self, modname: &str, value: impl IntoLua) -> Result<()> {
-        const LOADED_MODULES_KEY: *const c_char = ffi::LUA_REGISTERED_MODULES_TABLE;
-
         if !modname.starts_with('@') {
             return Err(Error::runtime("module name must begin with '@'"));
         }
@@ -326,6 +331,46 @@ impl Lua {
                 ffi::lua_pushlstring(state, modname.as_ptr() as *const c_char, modname.len() as _);
                 ffi::lua_pushvalue(state, -3);
                 ffi::lua_rawset(state, -3);
+                // Replace lazy module with the same name, if any
+                ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, LAZY_MODULES_KEY);
+                ffi::lua_pushlstring(state, modname.as_ptr() as *const c_char, modname.len() as _);
+                ffi::lua_pushnil(state);
+                ffi::lua_rawset(state, -3);
+            })
+        }
+    }
+
+    /// Registers module into an existing Lua state, that is constructed by the given function on
+    /// first [require].
+    ///
+    /// Errors returned by the function are raised in the requiring code, and the function is
+    /// called again on the next `require`. Once the module is constructed, the function is
+    /// dropped and the value is returned by subsequent `require` calls, as if it was registered
+    /// using [`Lua::register_module`].
+    ///
+    /// [require]: https://www.lua.org/manual/5.4/manual.html#pdf-require
+    pub fn register_lazy_module<F, R>(&self, modname: &str, init: F) -> Result<()>
+    where
+        F: Fn(&Lua) -> Result<R> + MaybeSend + 'static,
+        R: IntoLua,
+    {
+        if !modname.starts_with('@') {
+            return Err(Error::runtime("module name must begin with '@'"));
+        }
+
+        let init = self.create_function(move |lua, ()| init(lua))?;
+        let modname = modname.to_ascii_lowercase();
+        unsafe {
+            self.exec_raw::<()>(init, |state| {
+                ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, LAZY_MODULES_KEY);
+                ffi::lua_pushlstring(state, modname.as_ptr() as *const c_char, modname.len() as _);
+                ffi::lua_pushvalue(state, -3);
+                ffi::lua_rawset(state, -3);
+                // Remove the previously registered value
+                ffi::luaL_getsubtable(state, ffi::LUA_REGISTRYINDEX, LOADED_MODULES_KEY);
+                ffi::lua_pushlstring(state, modname.as_ptr() as *const c_char, modname.len() as _);
+                ffi::lua_pushnil(state);
+                ffi::lua_rawset(state, -3);
             })
         }
     }
@@ -469,30 +514,6 @@ impl Lua {
     where
         F: Fn(&Lua) -> Result<VmState> + MaybeSend + 'static,
     {
-        unsafe extern "C-unwind" fn interrupt_proc(state: *mut ffi::lua_State, gc: c_int) {
-            if gc >= 0 {
-                // We don't support GC interrupts since they cannot survive Lua exceptions
-                return;
-            }
-            let result = callback_error_ext(state, ptr::null_mut(), false, move |extra, _| {
-                let interrupt_cb = (*extra).interrupt_callback.clone();
-                let interrupt_cb = ulua_expect!(interrupt_cb, "no interrupt callback set in interrupt_proc");
-                if XRc::strong_count(&interrupt_cb) > 2 {
-                    return Ok(VmState::Continue); // Don't allow recursion
-                }
-                interrupt_cb((*extra).lua())
-            });
-            match result {
-                VmState::Continue => {}
-                VmState::Yield => {
-                    // We can yield only at yieldable points, otherwise ignore and continue
-                    if ffi::lua_isyieldable(state) != 0 {
-                        ffi::lua_yield(state, 0);
-                    }
-                }
-            }
-        }
-
         // Set interrupt callback
         let lua = self.lock();
         unsafe {
@@ -508,7 +529,10 @@ impl Lua {
         let lua = self.lock();
         unsafe {
             (*lua.extra.get()).interrupt_callback = None;
-            (*ffi::lua_callbacks(lua.main_state())).interrupt = None;
+            // The debugger uses interrupts to pause execution
+            if (*lua.extra.get()).debugger.is_none() {
+                (*ffi::lua_callbacks(lua.main_state())).interrupt = None;
+            }
         }
     }
 
@@ -764,10 +788,49 @@ impl Lua {
         unsafe { (*lua.extra.get()).compiler = Some(compiler) };
     }
 
+    /// Sets a persistent cache for compiled chunks.
+    ///
+    /// When set, text chunks are compiled in advance and the resulting bytecode is looked up in
+    /// (and stored to) the cache, including modules loaded via `require` function.
+    ///
+    /// See [`ChunkCache`] and [`FileChunkCache`] for details.
+    ///
+    /// [`FileChunkCache`]: crate::FileChunkCache
+    pub fn set_chunk_cache(&self, cache: impl ChunkCache) {
+        let lua = self.lock();
+        unsafe { (*lua.extra.get()).chunk_cache = Some(XRc::new(cache)) };
+    }
+
+    /// Removes the chunk cache previously set by [`Lua::set_chunk_cache`].
+    pub fn remove_chunk_cache(&self) {
+        let lua = self.lock();
+        unsafe { (*lua.extra.get()).chunk_cache = None };
+    }
+
+    /// Sets whether bytecode may be loaded without verification.
+    ///
+    /// Chunks loaded with [`ChunkMode::Binary`] are always checked by the [bytecode verifier]
+    /// before loading. Other bytecode, such as binary chunks loaded with auto-detected mode or
+    /// fetched from the [chunk cache], is trusted by default.
+    ///
+    /// When set to `false`, every binary chunk is verified and refused if it fails verification.
+    ///
+    /// [`ChunkMode::Binary`]: crate::ChunkMode::Binary
+    /// [bytecode verifier]: crate::bytecode::Bytecode::verify
+    /// [chunk cache]: Lua::set_chunk_cache
+    pub fn set_allow_unverified_bytecode(&self, allow: bool) {
+        let lua = self.lock();
+        unsafe { (*lua.extra.get()).allow_unverified_bytecode = allow };
+    }
+
     /// Toggles JIT compilation mode for new chunks of code.
     ///
     /// By default JIT is enabled. Changing this option does not have any effect on
     /// already loaded functions.
+    ///
+    /// When JIT is disabled, chunks annotated with `--!native` or containing `@native` functions
+    /// are still compiled to native code. Use [`Chunk::set_native`] to control native code
+    /// generation for a single chunk, or [`Function::compile_native`] for a loaded function.
     pub fn enable_jit(&self, enable: bool) {
         let lua = self.lock();
         unsafe { (*lua.extra.get()).enable_jit = enable };
@@ -813,6 +876,10 @@ impl Lua {
             mode: chunk.mode(),
             source: chunk.source(),
             compiler: unsafe { (*self.lock().extra.get()).compiler.clone() },
+            cache: unsafe { (*self.lock().extra.get()).chunk_cache.clone() },
+            verify: unsafe { !(*self.lock().extra.get()).allow_unverified_bytecode },
+            trusted: chunk.is_trusted(),
+            native: None,
         }
     }
 
@@ -1105,7 +1172,9 @@ impl Lua {
             }
 
             // Add to "pending" registration map
-            ((*lua.extra.get()).pending_userdata_reg).insert(type_id, registry.into_raw());
+            let registry = registry.into_raw();
+            ((*lua.extra.get()).userdata_definitions).insert(type_id, registry.definition.clone());
+            ((*lua.extra.get()).pending_userdata_reg).insert(type_id, registry);
         }
         Ok(())
     }
@@ -9,13 +9,14 @@ use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 
 use crate::error::Result;
+use crate::luau::{NativeCodeStats, UserDataDefinition};
 use crate::state::RawLua;
 use crate::stdlib::StdLib;
 use crate::types::{AppData, ReentrantMutex, XRc};
 use crate::userdata::RawUserDataRegistry;
 use crate::util::{get_internal_metatable, TypeKey, WrappedFailure};
 
-use crate::chunk::Compiler;
+use crate::chunk::{ChunkCache, Compiler};
 
 #[cfg(feature = "async")]
 use {futures_util::task::noop_waker_ref, std::ptr::NonNull, std::task::Waker};
@@ -35,6 +36,7 @@ pub(crate) struct ExtraData {
     pub(super) owned: bool,
 
     pub(super) pending_userdata_reg: FxHashMap<TypeId, RawUserDataRegistry>,
+    pub(crate) userdata_definitions: FxHashMap<TypeId, UserDataDefinition>,
     pub(super) registered_userdata_t: FxHashMap<TypeId, c_int>,
     pub(super) registered_userdata_mt: FxHashMap<*const c_void, Option<TypeId>>,
     pub(super) registered_userdata_dtors: FxHashMap<TypeId, ffi::lua_CFunction>,
@@ -72,14 +74,19 @@ pub(crate) struct ExtraData {
     #[cfg(feature = "async")]
     pub(super) waker: NonNull<Waker>,
 
-    pub(super) interrupt_callback: Option<crate::types::InterruptCallback>,
+    pub(crate) interrupt_callback: Option<crate::types::InterruptCallback>,
     pub(super) thread_creation_callback: Option<crate::types::ThreadCreationCallback>,
     pub(super) thread_collection_callback: Option<crate::types::ThreadCollectionCallback>,
+    pub(crate) debugger: Option<crate::luau::DebuggerState>,
 
     pub(crate) running_gc: bool,
     pub(crate) sandboxed: bool,
     pub(super) compiler: Option<Compiler>,
-    pub(super) enable_jit: bool,
+    pub(super) chunk_cache: Option<XRc<dyn ChunkCache>>,
+    pub(super) allow_unverified_bytecode: bool,
+    pub(crate) enable_jit: bool,
+    // Statistics of native code generation (if collected)
+    pub(crate) native_stats: Option<NativeCodeStats>,
 }
 
 impl Drop for ExtraData {
@@ -137,6 +144,7 @@ impl ExtraData {
             weak: MaybeUninit::uninit(),
             owned,
             pending_userdata_reg: FxHashMap::default(),
+            userdata_definitions: FxHashMap::default(),
             registered_userdata_t: FxHashMap::default(),
             registered_userdata_mt: FxHashMap::default(),
             registered_userdata_dtors: FxHashMap::default(),
@@ -162,9 +170,13 @@ impl ExtraData {
             interrupt_callback: None,
             thread_creation_callback: None,
             thread_collection_callback: None,
+            debugger: None,
             sandboxed: false,
             compiler: None,
+            chunk_cache: None,
+            allow_unverified_bytecode: true,
             enable_jit: true,
+            native_stats: None,
             running_gc: false,
         }));
 
@@ -304,34 +304,45 @@ impl RawLua {
         Arc::ptr_eq(&key.unref_list, registry_unref_list)
     }
 
+    /// Loads a chunk, returning the function and whether it was compiled to native code.
     pub(crate) fn load_chunk(
         &self,
         name: Option<&CStr>,
         env: Option<&Table>,
         mode: Option<ChunkMode>,
         source: &[u8],
-    ) -> Result<Function> {
+        native: bool,
+    ) -> Result<(Function, bool)> {
         let state = self.state();
         unsafe {
             let _sg = StackGuard::new(state);
             check_stack(state, 3)?;
 
-            let name = name.map(CStr::as_ptr).unwrap_or(ptr::null());
+            let name_ptr = name.map(CStr::as_ptr).unwrap_or(ptr::null());
+            // Native code cannot be stopped by the debugger
+            let native =
+                native && (*self.extra.get()).debugger.is_none() && ffi::luau_codegen_supported() != 0;
             let mode = match mode {
                 Some(ChunkMode::Binary) => cstr!("b"),
                 Some(ChunkMode::Text) => cstr!("t"),
                 None => cstr!("bt"),
             };
             let status = if self.unlikely_memory_error() {
-                self.load_chunk_inner(state, name, env, mode, source)
+                self.load_chunk_inner(state, name_ptr, env, mode, source, native)
             } else {
                 // Luau and Lua 5.2 can trigger an exception during chunk loading
                 protect_lua!(state, 0, 1, |state| {
-                    self.load_chunk_inner(state, name, env, mode, source)
+                    self.load_chunk_inner(state, name_ptr, env, mode, source, native)
                 })?
             };
             match status {
-                ffi::LUA_OK => Ok(Function(self.pop_ref())),
+                ffi::LUA_OK => {
+                    let function = Function(self.pop_ref());
+                    if let Some(name) = name.filter(|_| (*self.extra.get()).debugger.is_some()) {
+                        crate::luau::track_chunk(self, &function, name)?;
+                    }
+                    Ok((function, native))
+                }
                 err => Err(pop_error(state, err)),
             }
         }
@@ -344,6 +355,7 @@ impl RawLua {
         env: Option<&Table>,
         mode: *const c_char,
         source: &[u8],
+        native: bool,
     ) -> c_int {
         let status = ffi::luaL_loadbufferenv(
             state,
@@ -359,10 +371,8 @@ impl RawLua {
                 _ => 0,
             },
         );
-        if status == ffi::LUA_OK {
-            if (*self.extra.get()).enable_jit && ffi::luau_codegen_supported() != 0 {
-                ffi::luau_codegen_compile(state, -1);
-            }
+        if status == ffi::LUA_OK && native {
+            ffi::luau_codegen_compile(state, -1);
         }
         status
     }
@@ -772,6 +782,9 @@ impl RawLua {
             (*self.extra.get())
                 .registered_userdata_dtors
                 .insert(type_id, registry.destructor);
+            (*self.extra.get())
+                .userdata_definitions
+                .insert(type_id, registry.definition.clone());
         }
 
         self.push_userdata_metatable(registry, state)?;
@@ -5,12 +5,13 @@ use std::sync::Arc;
 
 use crate::error::{Error, Result};
 use crate::state::{ExtraData, RawLua};
+use crate::types::{VmState, XRc};
 use crate::util::{self, get_internal_metatable, WrappedFailure};
 
-struct StateGuard<'a>(&'a RawLua, *mut ffi::lua_State);
+pub(crate) struct StateGuard<'a>(&'a RawLua, *mut ffi::lua_State);
 
 impl<'a> StateGuard<'a> {
-    fn new(inner: &'a RawLua, mut state: *mut ffi::lua_State) -> Self {
+    pub(crate) fn new(inner: &'a RawLua, mut state: *mut ffi::lua_State) -> Self {
         state = inner.state.replace(state);
         Self(inner, state)
     }
@@ -148,3 +149,34 @@ where
         }
     }
 }
+
+pub(crate) unsafe extern "C-unwind" fn interrupt_proc(state: *mut ffi::lua_State, gc: c_int) {
+    if gc >= 0 {
+        // We don't support GC interrupts since they cannot survive Lua exceptions
+        return;
+    }
+    let extra = ExtraData::get(state);
+    if (*extra).debugger.is_some() {
+        crate::luau::debugger_interrupt(state);
+    }
+    if (*extra).interrupt_callback.is_none() {
+        return;
+    }
+    let result = callback_error_ext(state, extra, false, move |extra, _| {
+        let interrupt_cb = (*extra).interrupt_callback.clone();
+        let interrupt_cb = ulua_expect!(interrupt_cb, "no interrupt callback set in interrupt_proc");
+        if XRc::strong_count(&interrupt_cb) > 2 {
+            return Ok(VmState::Continue); // Don't allow recursion
+        }
+        interrupt_cb((*extra).lua())
+    });
+    match result {
+        VmState::Continue => {}
+        VmState::Yield => {
+            // We can yield only at yieldable points, otherwise ignore and continue
+            if ffi::lua_isyieldable(state) != 0 {
+                ffi::lua_yield(state, 0);
+            }
+        }
+    }
+}
@@ -311,6 +311,18 @@ pub trait UserDataMethods<T> {
         F: FnMut(&Lua, A) -> Result<R> + MaybeSend + 'static,
         A: FromLuaMulti,
         R: IntoLuaMulti;
+
+    /// Sets a Luau type signature of a method or function, used by [`Lua::type_definitions`].
+    ///
+    /// For methods and metamethods the signature is the part of the declaration following the
+    /// method name, e.g. `"(self, by: number): number"`. For functions it is a function type, e.g.
+    /// `"(value: number) -> Counter"`.
+    ///
+    /// The method must be registered as well, otherwise [`Lua::type_definitions`] returns an error.
+    ///
+    /// The default implementation does nothing.
+    #[allow(unused_variables)]
+    fn set_method_type(&mut self, name: impl Into<StdString>, signature: impl Into<StdString>) {}
 }
 
 /// Field registry for [`UserData`] implementors.
@@ -391,6 +403,14 @@ pub trait UserDataFields<T> {
     where
         F: FnOnce(&Lua) -> Result<R> + 'static,
         R: IntoLua;
+
+    /// Sets a Luau type of a field, used by [`Lua::type_definitions`].
+    ///
+    /// The field must be registered as well, otherwise [`Lua::type_definitions`] returns an error.
+    ///
+    /// The default implementation does nothing.
+    #[allow(unused_variables)]
+    fn set_field_type(&mut self, name: impl Into<StdString>, ty: impl Into<StdString>) {}
 }
 
 /// Trait for custom userdata types.
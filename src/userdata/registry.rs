@@ -7,6 +7,7 @@ use std::os::raw::c_void;
 use std::string::String as StdString;
 
 use crate::error::{Error, Result};
+use crate::luau::UserDataDefinition;
 use crate::state::{Lua, LuaGuard};
 use crate::traits::{FromLua, FromLuaMulti, IntoLua, IntoLuaMulti};
 use crate::types::{Callback, MaybeSend};
@@ -58,6 +59,9 @@ pub(crate) struct RawUserDataRegistry {
     pub(crate) type_name: StdString,
 
     pub(crate) enable_namecall: bool,
+
+    // Type information for generating definitions
+    pub(crate) definition: UserDataDefinition,
 }
 
 impl UserDataType {
@@ -88,6 +92,7 @@ impl<T> UserDataRegistry<T> {
 
     #[inline(always)]
     fn with_type(lua: &Lua, r#type: UserDataType) -> Self {
+        let type_name = short_type_name::<T>();
         let raw = RawUserDataRegistry {
             fields: Vec::new(),
             field_getters: Vec::new(),
@@ -101,7 +106,8 @@ impl<T> UserDataRegistry<T> {
             async_meta_methods: Vec::new(),
             destructor: super::util::destroy_userdata_storage::<T>,
             type_id: r#type.type_id(),
-            type_name: short_type_name::<T>(),
+            definition: UserDataDefinition::new(std::any::type_name::<T>(), &type_name),
+            type_name,
             enable_namecall: false,
         };
 
@@ -398,6 +404,7 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
         V: IntoLua + 'static,
     {
         let name = name.into();
+        self.raw.definition.fields.push(name.clone());
         self.raw.fields.push((name, value.into_lua(self.lua.lua())));
     }
 
@@ -408,6 +415,7 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method(&name, move |lua, data, ()| method(lua, data));
+        self.raw.definition.fields.push(name.clone());
         self.raw.field_getters.push((name, callback));
     }
 
@@ -418,6 +426,7 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method_mut(&name, method);
+        self.raw.definition.fields.push(name.clone());
         self.raw.field_setters.push((name, callback));
     }
 
@@ -428,6 +437,7 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function(&name, function);
+        self.raw.definition.fields.push(name.clone());
         self.raw.field_getters.push((name, callback));
     }
 
@@ -438,6 +448,7 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function_mut(&name, move |lua, (data, val)| function(lua, data, val));
+        self.raw.definition.fields.push(name.clone());
         self.raw.field_setters.push((name, callback));
     }
 
@@ -461,6 +472,10 @@ impl<T> UserDataFields<T> for UserDataRegistry<T> {
         let field = f(lua).and_then(|v| Self::check_meta_field(lua, &name, v).and_then(|v| v.into_lua(lua)));
         self.raw.meta_fields.push((name, field));
     }
+
+    fn set_field_type(&mut self, name: impl Into<StdString>, ty: impl Into<StdString>) {
+        (self.raw.definition.field_types).insert(name.into(), ty.into());
+    }
 }
 
 impl<T> UserDataMethods<T> for UserDataRegistry<T> {
@@ -472,6 +487,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method(&name, method);
+        self.raw.definition.methods.push(name.clone());
         self.raw.methods.push((name, callback));
     }
 
@@ -483,6 +499,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method_mut(&name, method);
+        self.raw.definition.methods.push(name.clone());
         self.raw.methods.push((name, callback));
     }
 
@@ -497,6 +514,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_async_method(&name, method);
+        self.raw.definition.methods.push(name.clone());
         self.raw.async_methods.push((name, callback));
     }
 
@@ -511,6 +529,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_async_method_mut(&name, method);
+        self.raw.definition.methods.push(name.clone());
         self.raw.async_methods.push((name, callback));
     }
 
@@ -522,6 +541,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function(&name, function);
+        self.raw.definition.functions.push(name.clone());
         self.raw.methods.push((name, callback));
     }
 
@@ -533,6 +553,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function_mut(&name, function);
+        self.raw.definition.functions.push(name.clone());
         self.raw.methods.push((name, callback));
     }
 
@@ -546,6 +567,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_async_function(&name, function);
+        self.raw.definition.functions.push(name.clone());
         self.raw.async_methods.push((name, callback));
     }
 
@@ -557,6 +579,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method(&name, method);
+        self.raw.definition.meta_methods.push(name.clone());
         self.raw.meta_methods.push((name, callback));
     }
 
@@ -568,6 +591,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_method_mut(&name, method);
+        self.raw.definition.meta_methods.push(name.clone());
         self.raw.meta_methods.push((name, callback));
     }
 
@@ -579,6 +603,7 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function(&name, function);
+        self.raw.definition.meta_methods.push(name.clone());
         self.raw.meta_methods.push((name, callback));
     }
 
@@ -590,8 +615,13 @@ impl<T> UserDataMethods<T> for UserDataRegistry<T> {
     {
         let name = name.into();
         let callback = self.box_function_mut(&name, function);
+        self.raw.definition.meta_methods.push(name.clone());
         self.raw.meta_methods.push((name, callback));
     }
+
+    fn set_method_type(&mut self, name: impl Into<StdString>, signature: impl Into<StdString>) {
+        (self.raw.definition.method_types).insert(name.into(), signature.into());
+    }
 }
 
 macro_rules! lua_userdata_impl {
@@ -612,6 +642,8 @@ macro_rules! lua_userdata_impl {
                 (registry.raw.meta_methods).extend(orig_registry.raw.meta_methods);
                 #[cfg(feature = "async")]
                 (registry.raw.async_meta_methods).extend(orig_registry.raw.async_meta_methods);
+                // Wrapped types are described by the definition of the inner type
+                registry.raw.definition = orig_registry.raw.definition;
             }
         }
     };
@@ -6,7 +6,7 @@ use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::ptr;
 use std::sync::Arc;
 
-use crate::error::{Error, Result};
+use crate::error::{Error, Result, SyntaxDiagnostic};
 use crate::memory::MemoryState;
 use crate::util::{
     check_stack, get_internal_userdata, init_internal_metatable, push_internal_userdata, push_string,
@@ -130,6 +130,7 @@ pub(crate) unsafe fn pop_error(state: *mut ffi::lua_State, err_code: c_int) -> E
                         // This seems terrible, but as far as I can tell, this is exactly what the
                         // stock Lua REPL does.
                         incomplete_input: err_string.ends_with("<eof>") || err_string.ends_with("'<eof>'"),
+                        diagnostics: SyntaxDiagnostic::from_message(&err_string).into_iter().collect(),
                         message: err_string,
                     }
                 }
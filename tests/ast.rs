@@ -0,0 +1,127 @@
+use ulua::ast::{Ast, CommentKind, NodeKind, Position, Span};
+use ulua::{Error, Result};
+
+#[test]
+fn test_ast_parse() -> Result<()> {
+    let source = r#"-- Greets the user
+local name: string, count = "world", 2
+local function greet(who: string?, ...)
+    print(`hello {who}`) --[[ block ]]
+end
+for i = 1, count do greet(name .. i) end
+"#;
+    let ast = Ast::parse(source)?;
+
+    let root = ast.root();
+    assert_eq!(root.kind(), NodeKind::StatBlock);
+    assert_eq!(root.parent(), None);
+    let statements = root.children().map(|node| node.kind()).collect::<Vec<_>>();
+    assert_eq!(
+        statements,
+        [
+            NodeKind::StatLocal,
+            NodeKind::StatLocalFunction,
+            NodeKind::StatFor
+        ]
+    );
+
+    // Declared locals come first, with their type annotations
+    let local = root.children().next().unwrap();
+    let children = local.children().collect::<Vec<_>>();
+    assert_eq!(children.len(), 4);
+    assert_eq!(children[0].kind(), NodeKind::Local);
+    assert_eq!(children[0].text().unwrap(), "name");
+    let annotation = children[0].children().next().unwrap();
+    assert_eq!(annotation.kind(), NodeKind::TypeReference);
+    assert!(annotation.kind().is_type());
+    assert_eq!(annotation.text().unwrap(), "string");
+    assert_eq!(annotation.parent(), Some(children[0]));
+    assert_eq!(children[1].text().unwrap(), "count");
+    assert_eq!(children[2].kind(), NodeKind::ExprConstantString);
+    assert_eq!(children[2].text().unwrap(), "world");
+    assert_eq!(children[3].text().unwrap(), "2");
+    assert!(children[3].kind().is_expression());
+
+    // Spans are 1-based, with exclusive end
+    let span = Span {
+        start: Position { line: 2, column: 1 },
+        end: Position { line: 2, column: 39 },
+    };
+    assert_eq!(local.span(), span);
+    assert_eq!(local.source_text(), r#"local name: string, count = "world", 2"#);
+    assert_eq!(children[2].source_text(), r#""world""#);
+
+    // Function parameters
+    let function = root.children().nth(1).unwrap().children().nth(1).unwrap();
+    assert_eq!(function.kind(), NodeKind::ExprFunction);
+    assert_eq!(function.text().unwrap(), "greet");
+    let params = (function.children())
+        .filter(|node| node.kind() == NodeKind::Local)
+        .map(|node| node.text().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(params, ["who"]);
+
+    // All nodes in source order
+    let calls = (ast.nodes())
+        .filter(|node| node.kind() == NodeKind::ExprCall)
+        .map(|call| call.children().next().unwrap().text().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(calls, ["print", "greet"]);
+    let binary = ast
+        .nodes()
+        .find(|node| node.kind() == NodeKind::ExprBinary)
+        .unwrap();
+    assert_eq!(binary.text().unwrap(), "..");
+    let for_loop = root.children().nth(2).unwrap();
+    assert_eq!(for_loop.descendants().count(), 10);
+    assert!(for_loop
+        .descendants()
+        .all(|node| node.span().start >= for_loop.span().start));
+
+    // Comments
+    let comments = ast.comments();
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].kind, CommentKind::Line);
+    assert_eq!(ast.source_text(comments[0].span), "-- Greets the user");
+    assert_eq!(comments[1].kind, CommentKind::Block);
+    assert_eq!(ast.source_text(comments[1].span), "--[[ block ]]");
+
+    Ok(())
+}
+
+#[test]
+fn test_ast_syntax_errors() -> Result<()> {
+    match Ast::parse("local x = 1\nlocal y = x + %\nlocal = 2\n") {
+        Err(Error::SyntaxError {
+            message,
+            incomplete_input,
+            diagnostics,
+        }) => {
+            assert!(!incomplete_input);
+            assert_eq!(message, "2: Expected identifier when parsing expression, got '%'");
+            assert_eq!(diagnostics.len(), 3);
+            assert_eq!(diagnostics[0].chunk_name, None);
+            assert_eq!(diagnostics[0].line, 2);
+            assert_eq!(diagnostics[0].columns, Some(15..16));
+            assert_eq!(diagnostics[2].line, 3);
+            assert_eq!(diagnostics[2].columns, Some(7..8));
+            assert!(diagnostics[2].message.contains("parsing variable name"));
+        }
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    // Columns are counted in characters
+    match Ast::parse("local s = 'héllo' +") {
+        Err(Error::SyntaxError {
+            incomplete_input,
+            diagnostics,
+            ..
+        }) => {
+            assert!(incomplete_input);
+            assert_eq!(diagnostics[0].columns, Some(20..21));
+        }
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "bundle")]
+
+use ulua::{Bundle, Compiler, Error, Lua, Result};
+
+const KEY: &[u8] = b"secret build key";
+
+#[test]
+fn test_bundle_roundtrip() -> Result<()> {
+    let compiler = Compiler::new().set_optimization_level(2).set_debug_level(2);
+    let bundle = Bundle::compile(&compiler, "@plugin.luau", "return ...")?;
+    assert_eq!(bundle.name, "@plugin.luau");
+    assert!(!bundle.luau_version.is_empty());
+    assert_eq!((bundle.optimization_level, bundle.debug_level), (2, 2));
+    assert_eq!(bundle.bytecode, compiler.compile("return ...")?);
+
+    let data = bundle.to_bytes();
+    assert_eq!(Bundle::from_bytes(&data)?, bundle);
+    assert_eq!(Bundle::verify(&data, &bundle.sign(KEY), KEY)?, bundle);
+
+    match Bundle::from_bytes(&data[..data.len() - 1]) {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("unexpected end of data"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_load_bundle() -> Result<()> {
+    let lua = Lua::new();
+
+    let bundle = Bundle::compile(
+        &Compiler::new(),
+        "@plugin.luau",
+        "return 1 + 2, debug.info(1, 's')",
+    )?;
+    let (data, signature) = (bundle.to_bytes(), bundle.sign(KEY));
+    let (n, source) = lua.load_bundle(&data, &signature, KEY)?.eval::<(i32, String)>()?;
+    assert_eq!(n, 3);
+    assert_eq!(source, "plugin.luau");
+
+    // Signed bytecode is trusted, so it is compiled to native code when JIT is enabled
+    let codegen = lua.load("return 1").into_function()?.compile_native()?;
+    lua.enable_native_code_stats(true);
+    let source = "local function add(a, b) return a + b end return add(1, 2)";
+    let bundle = Bundle::compile(&Compiler::new(), "@add.luau", source)?;
+    let (add_data, add_signature) = (bundle.to_bytes(), bundle.sign(KEY));
+    assert_eq!(lua.load_bundle(&add_data, &add_signature, KEY)?.eval::<i32>()?, 3);
+    assert_eq!(lua.native_code_stats().chunks, codegen as usize);
+
+    // Wrong key
+    match lua.load_bundle(&data, &signature, b"other key").err() {
+        Some(Error::BytecodeError(msg)) => assert!(msg.contains("signature mismatch"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Tampered data
+    let mut tampered = data.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    match lua.load_bundle(&tampered, &signature, KEY).err() {
+        Some(Error::BytecodeError(msg)) => assert!(msg.contains("signature mismatch"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Tampered signature
+    let mut bad_signature = signature;
+    bad_signature[0] ^= 1;
+    assert!(lua.load_bundle(&data, &bad_signature, KEY).is_err());
+    assert!(lua.load_bundle(&data, &signature[..16], KEY).is_err());
+
+    Ok(())
+}
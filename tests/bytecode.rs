@@ -0,0 +1,241 @@
+use ulua::bytecode::{Bytecode, Constant, OpCode};
+use ulua::{ChunkMode, Compiler, Error, Lua, Result};
+
+#[test]
+fn test_bytecode_parse() -> Result<()> {
+    let compiler = Compiler::new().set_debug_level(2);
+    let bytecode = Bytecode::parse(&compiler.compile(
+        r#"
+        local function add(a, b)
+            return a + b
+        end
+        local t = {}
+        t:insert(add(1, 2))
+        print(string.format("%d", 1))
+    "#,
+    )?)?;
+
+    assert!(bytecode.version >= ulua::bytecode::VERSION_MIN);
+    assert_eq!(bytecode.protos.len(), 2);
+
+    let main = bytecode.main_proto();
+    assert!(main.is_vararg);
+    assert_eq!(main.children.len(), 1);
+
+    let add = &bytecode.protos[main.children[0]];
+    assert_eq!(add.debug_name.as_deref(), Some("add"));
+    assert_eq!(add.num_params, 2);
+    assert_eq!(add.line_defined, 2);
+    assert_eq!(add.count(OpCode::ADD), 1);
+    assert_eq!(
+        add.locals.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(),
+        ["a", "b"]
+    );
+    let add_insn = add.instructions.iter().find(|i| i.op == OpCode::ADD).unwrap();
+    assert_eq!(add_insn.line, Some(3));
+
+    let imports = bytecode.import_sites();
+    assert!(imports.iter().any(|s| s.name == "print" && s.line == Some(7)));
+    assert!(imports.iter().any(|s| s.name == "string.format"));
+    let namecalls = bytecode.namecall_sites();
+    assert_eq!(namecalls.len(), 1);
+    assert_eq!(namecalls[0].name, "insert");
+    assert_eq!(namecalls[0].line, Some(6));
+
+    Ok(())
+}
+
+#[test]
+fn test_bytecode_constant_folding() -> Result<()> {
+    let source = "return mylib.value * 2";
+
+    let compiler = Compiler::new().set_optimization_level(1);
+    let bytecode = Bytecode::parse(&compiler.compile(source)?)?;
+    assert_eq!(bytecode.main_proto().count(OpCode::MULK), 1);
+
+    let compiler = compiler
+        .set_optimization_level(2)
+        .add_library_constant("mylib.value", 21.0);
+    let bytecode = Bytecode::parse(&compiler.compile(source)?)?;
+    let main = bytecode.main_proto();
+    assert_eq!(main.count(OpCode::MULK), 0);
+    assert!(main
+        .instructions
+        .iter()
+        .any(|i| i.op == OpCode::LOADN && i.d() == 42));
+
+    Ok(())
+}
+
+#[test]
+fn test_bytecode_disassemble() -> Result<()> {
+    let compiler = Compiler::new();
+    let bytecode = Bytecode::parse(&compiler.compile("local x = math.floor(1.5) print(x)")?)?;
+
+    let text = bytecode.disassemble();
+    assert!(text.contains("GETIMPORT"), "{text}");
+    assert!(text.contains("[math.floor]"), "{text}");
+    assert!(text.contains("RETURN R0 0"), "{text}");
+    assert_eq!(text, bytecode.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_bytecode_errors() -> Result<()> {
+    match Bytecode::parse(&[]) {
+        Err(Error::BytecodeError(_)) => {}
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    match Bytecode::parse(&[255, 1, 0]) {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("unsupported bytecode version")),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Truncated blob
+    let data = Compiler::new().compile("return 1 + 2")?;
+    match Bytecode::parse(&data[..data.len() - 3]) {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("unexpected end of data")),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+// Replaces the first instruction matching `op` in the main proto using `f`
+fn patch_instruction(data: &mut [u8], op: OpCode, f: impl FnOnce(u32) -> u32) {
+    let bytecode = Bytecode::parse(data).unwrap();
+    let insn = bytecode
+        .main_proto()
+        .instructions
+        .iter()
+        .find(|i| i.op == op)
+        .unwrap();
+    let word = insn.word.to_le_bytes();
+    let pos = data.windows(4).position(|w| w == word).unwrap();
+    data[pos..pos + 4].copy_from_slice(&f(insn.word).to_le_bytes());
+}
+
+#[test]
+fn test_bytecode_verify() -> Result<()> {
+    let source = r##"
+        local function sum(...)
+            local s = 0
+            for _, v in ipairs({...}) do s += v end
+            return s
+        end
+        local t = {}
+        t:insert(sum(1, 2, 3))
+        print(select("#", ...), math.max(1, ...))
+        return sum(...)
+    "##;
+    for level in 0..=2 {
+        let data = Compiler::new().set_optimization_level(level).compile(source)?;
+        Bytecode::parse(&data)?.verify()?;
+    }
+
+    let data = Compiler::new().compile("local a, b = ... return a + b")?;
+
+    // Register out of range
+    let mut bad = data.clone();
+    patch_instruction(&mut bad, OpCode::ADD, |w| (w & 0xffff) | (200 << 16));
+    match Bytecode::parse(&bad)?.verify() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("register R200 is out of range"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Variable results that are never consumed
+    let mut bad = data.clone();
+    patch_instruction(&mut bad, OpCode::GETVARARGS, |w| w & 0xffff);
+    match Bytecode::parse(&bad)?.verify() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("not consumed"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Jump outside of the function
+    let data = Compiler::new().compile("local x = ... if x then x = 1 end return x")?;
+    let mut bad = data.clone();
+    patch_instruction(&mut bad, OpCode::JUMPIFNOT, |w| (w & 0xffff) | (1000 << 16));
+    match Bytecode::parse(&bad)?.verify() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("invalid jump target"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Breakpoint instruction (the VM reads the original instruction from debug data)
+    let mut bad = Compiler::new().compile("local a = 1 return a")?;
+    patch_instruction(&mut bad, OpCode::LOADN, |w| (w & !0xff) | OpCode::BREAK as u32);
+    match Bytecode::parse(&bad)?.verify() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("reserved for the VM"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+    match Lua::new().load(&bad).set_mode(ChunkMode::Binary).into_function() {
+        Err(Error::BytecodeError(_)) => {}
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Main proto with upvalues (the VM creates the main closure without them)
+    let mut bad = Compiler::new().compile("local x = {} local function f() return x end return f")?;
+    assert_eq!(bad.pop(), Some(1));
+    bad.push(0);
+    assert_eq!(Bytecode::parse(&bad)?.main_proto().num_upvalues, 1);
+    match Bytecode::parse(&bad)?.verify() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("main proto cannot have upvalues"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+    match Lua::new().load(&bad).set_mode(ChunkMode::Binary).into_function() {
+        Err(Error::BytecodeError(_)) => {}
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Table template with constant values (not supported by the VM)
+    let data = Compiler::new().compile("local t = {a = ..., b = ...} return t")?;
+    let bytecode = Bytecode::parse(&data)?;
+    let keys = bytecode.main_proto().constants.iter().find_map(|k| match k {
+        Constant::Table(keys) => Some(keys.iter().map(|&(key, _)| key as u8).collect::<Vec<_>>()),
+        _ => None,
+    });
+    let keys = keys.unwrap();
+    let mut template = vec![5, keys.len() as u8];
+    template.extend(&keys);
+    let pos = data.windows(template.len()).position(|w| w == template).unwrap();
+    let mut with_values = vec![8, keys.len() as u8];
+    for key in keys {
+        with_values.push(key);
+        with_values.extend((-1i32).to_le_bytes());
+    }
+    let bad = [&data[..pos], &with_values, &data[pos + template.len()..]].concat();
+    match Bytecode::parse(&bad) {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("unknown constant type 8"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_verified_binary_chunks() -> Result<()> {
+    let lua = Lua::new();
+
+    let data = Compiler::new().compile("local a, b = ... return a + b")?;
+    let f = lua.load(&data).set_mode(ChunkMode::Binary).into_function()?;
+    assert_eq!(f.call::<i32>((1, 2))?, 3);
+
+    let mut bad = data.clone();
+    patch_instruction(&mut bad, OpCode::ADD, |w| (w & 0xffff) | (200 << 16));
+    match lua.load(&bad).set_mode(ChunkMode::Binary).into_function() {
+        Err(Error::BytecodeError(msg)) => assert!(msg.contains("out of range"), "{msg}"),
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+
+    // Refuse unverified bytecode with auto-detected mode
+    lua.set_allow_unverified_bytecode(false);
+    match lua.load(&bad).into_function() {
+        Err(Error::BytecodeError(_)) => {}
+        r => panic!("expected BytecodeError, got {r:?}"),
+    }
+    assert_eq!(lua.load(&data).call::<i32>((2, 3))?, 5);
+    assert_eq!(lua.load("return 1 + 1").eval::<i32>()?, 2);
+
+    Ok(())
+}
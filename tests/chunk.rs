@@ -1,6 +1,6 @@
 use std::{fs, io};
 
-use ulua::{Chunk, ChunkMode, Lua, Result};
+use ulua::{Chunk, ChunkMode, Error, Lua, Result};
 
 #[test]
 fn test_chunk_methods() -> Result<()> {
@@ -111,6 +111,89 @@ fn test_chunk_macro() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(feature = "macros")]
+fn test_chunk_macro_source() -> Result<()> {
+    let lua = Lua::new();
+
+    let x = 7;
+    let (n, s, line, text) = lua
+        .load(ulua_macros::chunk! {
+            -- Lua comments are kept
+            local n = $x // 2 -- floor division
+
+            --[[ multiline
+                 comment ]]
+            local s = "\072\u{69}" .. [[ $x ]]
+            return n, s, debug.info(1, "l"), "$x"
+        })
+        .eval::<(i32, String, i32, String)>()?;
+    assert_eq!(n, 3);
+    assert_eq!(s, "Hi $x ");
+    assert_eq!(line, 8);
+    assert_eq!(text, "$x");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn test_chunk_macro_nested() -> Result<()> {
+    macro_rules! wrap {
+        ($($t:tt)*) => {
+            ulua_macros::chunk! { $($t)* }
+        };
+    }
+
+    let lua = Lua::new();
+
+    // The tokens are not in the call site text (`macro_rules!` transcriber)
+    assert_eq!(lua.load(wrap! { return 1 + 2 }).eval::<i32>()?, 3);
+    let x = 5;
+    assert_eq!(lua.load(wrap! { return $x * 2 }).eval::<i32>()?, 10);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn test_chunk_macro_bytecode() -> Result<()> {
+    use ulua::AsChunk;
+
+    let lua = Lua::new();
+
+    let name = "Rustacean";
+    let chunk = ulua_macros::chunk! {
+        #![bytecode]
+        local greeting = "hello, " .. $name
+        return greeting
+    };
+    assert_eq!(AsChunk::mode(&chunk), Some(ChunkMode::Binary));
+    assert!(chunk.is_trusted());
+    assert!(ulua::bytecode::Bytecode::parse(&chunk.source()?).is_ok());
+    assert_eq!(lua.load(chunk).eval::<String>()?, "hello, Rustacean");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn test_include_luau() -> Result<()> {
+    use ulua::AsChunk;
+
+    let lua = Lua::new();
+
+    let chunk = ulua::include_luau!("tests/luau/include.luau", optimize = 2, debug = 2);
+    assert_eq!(chunk.name().as_deref(), Some("@tests/luau/include.luau"));
+    assert_eq!(AsChunk::mode(&chunk), Some(ChunkMode::Binary));
+    assert!(chunk.is_trusted());
+    let (greeting, source) = lua.load(chunk).call::<(String, String)>("Rustacean")?;
+    assert_eq!(greeting, "hello, Rustacean");
+    assert_eq!(source, "tests/luau/include.luau");
+
+    Ok(())
+}
+
 #[test]
 fn test_compiler() -> Result<()> {
     let compiler = ulua::Compiler::new()
@@ -176,3 +259,111 @@ fn test_chunk_wrap() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_chunk_cache() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use ulua::{ChunkCache, ChunkCacheKey, Compiler, FileChunkCache};
+
+    struct CountingCache {
+        inner: FileChunkCache,
+        hits: Arc<AtomicUsize>,
+    }
+
+    impl ChunkCache for CountingCache {
+        fn get(&self, key: &ChunkCacheKey) -> Option<Vec<u8>> {
+            let data = self.inner.get(key);
+            if data.is_some() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            data
+        }
+
+        fn put(&self, key: &ChunkCacheKey, bytecode: &[u8]) {
+            self.inner.put(key, bytecode)
+        }
+    }
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    for i in 0..2 {
+        let lua = Lua::new();
+        let inner = FileChunkCache::new(temp_dir.path())?;
+        lua.set_chunk_cache(CountingCache {
+            inner,
+            hits: hits.clone(),
+        });
+        assert_eq!(lua.load("return 1 + 2").eval::<i32>()?, 3);
+        lua.load("x = 5").exec()?;
+        assert_eq!(hits.load(Ordering::Relaxed), i * 2);
+    }
+
+    // Syntax errors are reported as usual and not cached
+    let lua = Lua::new();
+    lua.set_chunk_cache(FileChunkCache::new(temp_dir.path())?);
+    match lua.load("local x =").exec() {
+        Err(Error::SyntaxError { .. }) => {}
+        res => panic!("expected SyntaxError, got {res:?}"),
+    }
+
+    // Different compiler options produce different keys
+    let key1 = ChunkCacheKey::new("return 1", &Compiler::new());
+    let key2 = ChunkCacheKey::new("return 1", &Compiler::new().set_optimization_level(2));
+    assert_ne!(key1, key2);
+    assert_eq!(key1, ChunkCacheKey::new("return 1", &Compiler::new()));
+    assert_ne!(key1, ChunkCacheKey::new("return 1 ", &Compiler::new()));
+
+    // Concurrent writers never leave partial entries or temporary files behind
+    let cache = FileChunkCache::new(temp_dir.path().join("concurrent"))?;
+    std::thread::scope(|s| {
+        for i in 0..4u8 {
+            // Each writer has its own cache instance, so writes are not serialized
+            let cache = FileChunkCache::new(cache.dir()).unwrap();
+            s.spawn(move || (0..20).for_each(|_| cache.put(&key1, &[i; 64])));
+        }
+    });
+    assert!(matches!(cache.get(&key1).as_deref(), Some([i, ..]) if *i < 4));
+    assert_eq!(fs::read_dir(cache.dir())?.count(), 1);
+
+    // Corrupted entries are ignored
+    let cache = FileChunkCache::new(temp_dir.path().join("corrupted"))?;
+    cache.put(&key1, b"bytecode");
+    assert_eq!(cache.get(&key1).as_deref(), Some(&b"bytecode"[..]));
+    let path = cache.dir().join(format!("{key1}.luauc"));
+    let mut data = fs::read(&path)?;
+    *data.last_mut().unwrap() ^= 0xff;
+    fs::write(&path, data)?;
+    assert_eq!(cache.get(&key1), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_chunk_cache_eviction() -> Result<()> {
+    use ulua::{ChunkCache, ChunkCacheKey, Compiler, FileChunkCache};
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let cache = FileChunkCache::new(temp_dir.path())?.set_max_size(300);
+
+    let compiler = Compiler::new();
+    let keys = (0..5)
+        .map(|i| ChunkCacheKey::new(format!("return {i}"), &compiler))
+        .collect::<Vec<_>>();
+    for key in &keys {
+        cache.put(key, &[0; 100]);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // Only the most recent entries fit into the limit
+    assert_eq!(cache.get(&keys[0]), None);
+    assert!(cache.get(&keys[4]).is_some());
+    let total = fs::read_dir(temp_dir.path())?.count();
+    assert!(total <= 2, "{total} entries left");
+
+    cache.clear()?;
+    assert_eq!(cache.get(&keys[4]), None);
+
+    Ok(())
+}
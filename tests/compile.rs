@@ -17,6 +17,9 @@ fn test_compilation() {
         t.compile_fail("tests/compile/async_nonstatic_userdata.rs");
     }
 
+    #[cfg(feature = "macros")]
+    t.compile_fail("tests/compile/chunk_syntax_error.rs");
+
     #[cfg(feature = "send")]
     t.compile_fail("tests/compile/non_send.rs");
     #[cfg(not(feature = "send"))]
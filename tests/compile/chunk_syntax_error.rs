@@ -0,0 +1,14 @@
+use ulua::{chunk, Lua};
+
+fn main() {
+    let lua = Lua::new();
+    let n = 1;
+    lua.load(chunk! {
+        local x = $n + 1
+        if x > 1
+            print(x)
+        end
+    })
+    .exec()
+    .unwrap();
+}
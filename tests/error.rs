@@ -99,3 +99,53 @@ fn test_error_anyhow() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_syntax_error_diagnostics() -> Result<()> {
+    let lua = Lua::new();
+
+    let source = "local x = 1\nlocal y = x + %\n";
+    match lua.load(source).set_name("@scripts/test.luau").exec() {
+        Err(Error::SyntaxError { diagnostics, .. }) => {
+            assert_eq!(diagnostics.len(), 1);
+            let diag = &diagnostics[0];
+            assert_eq!(diag.chunk_name.as_deref(), Some("@scripts/test.luau"));
+            assert_eq!(diag.line, 2);
+            assert_eq!(diag.columns, None);
+            assert_eq!(
+                diag.message,
+                "Expected identifier when parsing expression, got '%'"
+            );
+        }
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    // Errors at the end of input
+    match lua.load("local t = {\n\n").exec() {
+        Err(Error::SyntaxError { diagnostics, .. }) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].line, 3);
+            assert!(diagnostics[0].message.ends_with("<eof>"));
+        }
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    // Compiler errors have no chunk name
+    match ulua::Compiler::new().compile("\n\nreturn 1 +* 2") {
+        Err(Error::SyntaxError { diagnostics, .. }) => {
+            assert_eq!(diagnostics[0].chunk_name, None);
+            assert_eq!(diagnostics[0].line, 3);
+            // Luau does not report columns
+            assert_eq!(diagnostics[0].columns, None);
+        }
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    // Mode mismatch is not related to any source location
+    match lua.load("return 1").set_mode(ulua::ChunkMode::Binary).exec() {
+        Err(Error::SyntaxError { diagnostics, .. }) => assert!(diagnostics.is_empty()),
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    Ok(())
+}
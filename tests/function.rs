@@ -1,4 +1,4 @@
-use ulua::{Error, Function, Lua, Result, String, Table, Variadic};
+use ulua::{Compiler, Error, Function, Lua, Result, String, Table, Value, Variadic};
 
 #[test]
 fn test_function_call() -> Result<()> {
@@ -140,6 +140,41 @@ fn test_function_environment() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_function_upvalues() -> Result<()> {
+    let lua = Lua::new();
+    lua.set_compiler(Compiler::new().set_debug_level(2));
+
+    let counter = lua
+        .load(
+            r#"
+            local count, step = 0, 1
+            return function()
+                count += step
+                return count
+            end
+        "#,
+        )
+        .eval::<Function>()?;
+    assert_eq!(counter.call::<i64>(())?, 1);
+
+    let upvalues = counter.upvalues();
+    let names = upvalues.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["count", "step"]);
+    assert_eq!(upvalues[0].1, Value::Number(1.0));
+
+    assert!(counter.set_upvalue("count", 10)?);
+    assert!(!counter.set_upvalue("missing", 0)?);
+    assert_eq!(counter.call::<i64>(())?, 11);
+
+    // Rust functions do not expose upvalues
+    let func = lua.create_function(|_, ()| Ok(()))?;
+    assert!(func.upvalues().is_empty());
+    assert!(!func.set_upvalue("count", 0)?);
+
+    Ok(())
+}
+
 #[test]
 fn test_function_info() -> Result<()> {
     let lua = Lua::new();
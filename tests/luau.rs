@@ -1,11 +1,14 @@
 use std::cell::Cell;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::os::raw::c_void;
 use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use ulua::{
-    Compiler, Error, Function, Lua, LuaOptions, Result, StdLib, Table, ThreadStatus, Value, Vector, VmState,
+    ArchiveRequirer, BreakReason, ChunkMode, Compiler, DebugAction, Error, Function, LanguageMode, Lua,
+    LuaOptions, LuauRc, MemoryRequirer, MetaMethod, ModuleArchive, NativeCodeStats, Result, StdLib, Table,
+    ThreadStatus, UserData, UserDataFields, UserDataMethods, Value, Vector, VmState,
 };
 
 #[test]
@@ -458,5 +461,606 @@ fn test_typeof_error() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_type_definitions() -> Result<()> {
+    struct Counter(i64);
+
+    impl UserData for Counter {
+        fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+            fields.add_field_method_get("value", |_, this| Ok(this.0));
+            fields.add_field_method_set("value", |_, this, value| {
+                this.0 = value;
+                Ok(())
+            });
+            fields.set_field_type("value", "number");
+            fields.add_field("kind", "counter");
+        }
+
+        fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+            methods.add_method_mut("increment", |_, this, by: i64| {
+                this.0 += by;
+                Ok(this.0)
+            });
+            methods.set_method_type("increment", "(self, by: number): number");
+            methods.add_method("reset", |_, _, ()| Ok(()));
+            methods.add_function("new", |_, value: i64| Ok(Counter(value)));
+            methods.set_method_type("new", "(value: number) -> Counter");
+            methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.0.to_string()));
+            methods.add_meta_method(MetaMethod::Index, |_, _, ()| Ok(()));
+        }
+    }
+
+    let lua = Lua::new();
+    lua.register_userdata_type::<String>(|reg| {
+        reg.add_method("len", |_, this, ()| Ok(this.len()));
+    })?;
+    lua.register_module(
+        "@host/counter",
+        lua.create_table_from([("default", lua.create_userdata(Counter(0))?)])?,
+    )?;
+    let net = lua.create_table()?;
+    net.set("timeout", 30)?;
+    net.set("get", lua.create_function(|_, ()| Ok(()))?)?;
+    lua.register_module("@host/net", net)?;
+
+    let defs = lua.type_definitions()?;
+    let expected = r#"-- Type definitions generated by ulua
+
+declare class Counter
+	value: number
+	kind: any
+	new: (value: number) -> Counter
+	function increment(self, by: number): number
+	function reset(self, ...any): ...any
+	function __tostring(self, ...any): ...any
+end
+
+declare class String
+	function len(self, ...any): ...any
+end
+
+-- require("@host/counter")
+type HostCounterModule = {
+	default: Counter,
+}
+
+-- require("@host/net")
+type HostNetModule = {
+	get: (...any) -> ...any,
+	timeout: number,
+}
+"#;
+    assert_eq!(defs, expected);
+
+    // Types with the same name are named after their paths
+    mod a {
+        pub struct Config;
+        impl ulua::UserData for Config {}
+    }
+    mod b {
+        pub struct Config;
+        impl ulua::UserData for Config {}
+    }
+    let lua = Lua::new();
+    lua.create_userdata(a::Config)?;
+    lua.create_userdata(b::Config)?;
+    lua.register_module("@host/config", lua.create_userdata(b::Config)?)?;
+    // Module names are escaped
+    lua.register_module("@host/evil\")\nerror()--", 1)?;
+    let defs = lua.type_definitions()?;
+    assert!(
+        defs.contains("declare class luau_test_type_definitions_a_Config\n"),
+        "{defs}"
+    );
+    assert!(
+        defs.contains("declare class luau_test_type_definitions_b_Config\n"),
+        "{defs}"
+    );
+    assert!(defs.contains("type HostConfigModule = luau_test_type_definitions_b_Config\n"));
+    assert!(
+        defs.contains("-- require(\"@host/evil\\\")\\nerror()--\")\n"),
+        "{defs}"
+    );
+
+    // Type hints must refer to registered methods
+    struct Typo;
+    impl UserData for Typo {
+        fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+            methods.add_method("run", |_, _, ()| Ok(()));
+            methods.set_method_type("rnu", "(self): ()");
+        }
+    }
+    let lua = Lua::new();
+    lua.create_userdata(Typo)?;
+    let err = lua.type_definitions().unwrap_err().to_string();
+    assert!(
+        err.contains("type hint for unknown method 'rnu' of 'Typo'"),
+        "{err}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_native_codegen() -> Result<()> {
+    let lua = Lua::new();
+    let rust_func = lua.create_function(|_, ()| Ok(()))?;
+    assert!(!rust_func.compile_native()?);
+    if !lua.load("return 1").into_function()?.compile_native()? {
+        // Native code generation is not supported on this platform
+        return Ok(());
+    }
+
+    let lua = Lua::new();
+    lua.enable_jit(false);
+    lua.enable_native_code_stats(true);
+    let source = r#"
+        local function add(a, b) return a + b end
+        local function mul(a, b) return a * b end
+        return add(1, mul(2, 3))
+    "#;
+    assert_eq!(lua.load(source).eval::<i32>()?, 7);
+    assert_eq!(lua.native_code_stats(), NativeCodeStats::default());
+
+    // `--!native` chunks are compiled when JIT is disabled (the main function runs once and is skipped)
+    assert_eq!(lua.load(format!("--!native\n{source}")).eval::<i32>()?, 7);
+    let stats = lua.native_code_stats();
+    assert_eq!((stats.chunks, stats.functions), (1, 2));
+    assert!(stats.instructions > 0 && stats.bytecode_size >= stats.instructions * 4);
+
+    // Only `@native` functions are compiled
+    let source2 = "@native local function f(x) return x * 2 end return f(21)";
+    assert_eq!(lua.load(source2).eval::<i32>()?, 42);
+    let stats = lua.native_code_stats();
+    assert_eq!((stats.chunks, stats.functions), (2, 3));
+
+    // Override per chunk
+    lua.load(source).set_native(true).exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 3);
+    lua.load(format!("--!native\n{source}"))
+        .set_native(false)
+        .exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 3);
+
+    lua.enable_jit(true);
+    lua.load(source).exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 4);
+    lua.load(source).set_native(false).exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 4);
+
+    // Untrusted bytecode is compiled only on explicit request
+    let data = Compiler::new().compile(source)?;
+    lua.load(&data).set_mode(ChunkMode::Binary).exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 4);
+    lua.load(&data)
+        .set_mode(ChunkMode::Binary)
+        .set_native(true)
+        .exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 5);
+
+    // Modules from archives are verified once and then trusted
+    let mut archive = ModuleArchive::new();
+    archive.add_module("main.luau", data.clone())?;
+    assert!(archive.add_module("bad.luau", &data[..data.len() / 2]).is_err());
+    assert!(archive.get("bad.luau").is_none());
+    let require = lua.create_require_function(ArchiveRequirer::new(archive))?;
+    let result = lua
+        .load("local require = ... return require('./main')")
+        .call::<i32>(require)?;
+    assert_eq!(result, 7);
+    assert_eq!(lua.native_code_stats().chunks, 6);
+
+    // Native code is not generated while a debugger is attached
+    let debugger = lua.debugger();
+    lua.load(source).set_native(true).exec()?;
+    assert_eq!(lua.native_code_stats().chunks, 6);
+    debugger.detach()?;
+
+    // Statistics are not collected unless enabled
+    lua.enable_native_code_stats(false);
+    lua.load(source).set_native(true).exec()?;
+    assert_eq!(lua.native_code_stats(), NativeCodeStats::default());
+
+    // Opt-in a loaded function
+    lua.enable_jit(false);
+    let func = lua.load("local n = ... return n * n").into_function()?;
+    assert!(func.compile_native()?);
+    assert_eq!(func.call::<i32>(5)?, 25);
+
+    Ok(())
+}
+
+#[test]
+fn test_luaurc() -> Result<()> {
+    let config = LuauRc::parse(
+        r#"
+        // Comments and trailing commas are allowed
+        {
+            "languageMode": "strict",
+            "lint": { "*": true, "LocalUnused": false },
+            "lintErrors": true,
+            "globals": ["game", 'workspace'],
+            "aliases": {
+                "Utils": "./utils",
+                "utils": "./ignored", -- the first definition wins
+            },
+        }
+        "#,
+    )?;
+    assert_eq!(config.language_mode(), Some(LanguageMode::Strict));
+    assert_eq!(config.lint("LocalUnused"), Some(false));
+    assert_eq!(config.lint("LocalShadow"), Some(true));
+    assert_eq!(config.lint_errors(), Some(true));
+    assert_eq!(config.type_errors(), None);
+    assert_eq!(config.globals(), ["game", "workspace"]);
+    assert_eq!(config.alias("UTILS"), Some("./utils"));
+    assert_eq!(config.aliases().collect::<Vec<_>>(), [("Utils", "./utils")]);
+
+    // Round trip
+    assert_eq!(LuauRc::parse(config.to_string())?, config);
+    assert_eq!(LuauRc::parse(LuauRc::new().to_string())?, LuauRc::new());
+
+    // Errors are reported with locations
+    for (source, error) in [
+        ("", ".luaurc:1:1: expected '{', got end of file"),
+        (
+            "{\n  \"languageMode\" \"strict\"\n}",
+            ".luaurc:2:18: expected ':', got '\"strict\"'",
+        ),
+        (
+            "{ \"languageMode\": \"loose\" }",
+            ".luaurc:1:19: bad mode \"loose\"",
+        ),
+        (
+            "{ \"lint\": { \"Unused\": true } }",
+            ".luaurc:1:13: unknown lint Unused",
+        ),
+        ("{ \"lintErrors\": \"yes\" }", ".luaurc:1:17: bad setting 'yes'"),
+        (
+            "{ \"aliases\": { \"a/b\": \"./a\" } }",
+            ".luaurc:1:16: invalid alias a/b",
+        ),
+        ("{ \"paths\": [\"./src\"] }", ".luaurc:1:3: unknown key paths"),
+        (
+            "{ \"globals\": [1] }",
+            ".luaurc:1:15: expected array element or ']', got '1'",
+        ),
+        ("{} {}", ".luaurc:1:4: expected end of file, got '{'"),
+    ] {
+        let err = LuauRc::parse(source).unwrap_err().to_string();
+        assert!(err.contains(error), "unexpected error for {source:?}: {err}");
+    }
+    match LuauRc::parse("{\n  \"languageMode\": \"loose\"\n}") {
+        Err(Error::SyntaxError { diagnostics, .. }) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].chunk_name.as_deref(), Some(".luaurc"));
+            assert_eq!(diagnostics[0].line, 2);
+            assert_eq!(diagnostics[0].columns, Some(19..26));
+            assert!(diagnostics[0].message.starts_with("bad mode \"loose\""));
+        }
+        res => panic!("expected syntax error, got {res:?}"),
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".luaurc");
+    std::fs::write(&path, "{\n  \"typeErrors\": 1\n}").unwrap();
+    let err = LuauRc::load(&path).unwrap_err().to_string();
+    assert!(err.contains(&format!("{}:2:17: expected field value", path.display())));
+
+    // Programmatic construction
+    let parent = LuauRc::new()
+        .set_language_mode(LanguageMode::NonStrict)
+        .set_lint("*", false)
+        .set_type_errors(false)
+        .add_global("game")
+        .add_alias("Lib", "./lib")
+        .add_alias("utils", "./parent_utils");
+    let mut child = LuauRc::new()
+        .set_lint("LocalShadow", true)
+        .add_global("script")
+        .add_alias("utils", "./utils");
+    child.validate()?;
+    assert!(LuauRc::new().add_alias("@utils", "./utils").validate().is_err());
+    assert!(LuauRc::new().set_lint("Unused", true).validate().is_err());
+
+    // Child options take precedence
+    child.inherit(&parent);
+    assert_eq!(child.language_mode(), Some(LanguageMode::NonStrict));
+    assert_eq!(child.lint("LocalShadow"), Some(true));
+    assert_eq!(child.lint("LocalUnused"), Some(false));
+    assert_eq!(child.type_errors(), Some(false));
+    assert_eq!(child.globals(), ["game", "script"]);
+    assert_eq!(child.alias("lib"), Some("./lib"));
+    assert_eq!(child.alias("utils"), Some("./utils"));
+
+    // Used by the require system
+    let requirer = MemoryRequirer::new()
+        .with_module("lib/init.luau", "return 'lib'")
+        .with_config("", parent.to_string());
+    let lua = Lua::new();
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+    assert_eq!(lua.load("return require('@lib')").eval::<String>()?, "lib");
+
+    Ok(())
+}
+
+#[test]
+fn test_debugger() -> Result<()> {
+    let lua = Lua::new();
+    let debugger = lua.debugger();
+    debugger.set_breakpoint("@main.luau", 6)?;
+    assert_eq!(debugger.breakpoints(), vec![("@main.luau".to_string(), 6)]);
+
+    let stops = Arc::new(Mutex::new(Vec::new()));
+    let actions = Arc::new(Mutex::new(VecDeque::new()));
+    debugger.on_break({
+        let (stops, actions) = (stops.clone(), actions.clone());
+        move |_, debug, reason| {
+            stops
+                .lock()
+                .unwrap()
+                .push((reason, debug.current_line().unwrap()));
+            Ok(actions
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(DebugAction::Continue))
+        }
+    });
+
+    let source = r#"
+        local function add(a, b)
+            local c = a + b
+            return c
+        end
+        local x = add(1, 2)
+        local y = add(x, 3)
+        return y
+    "#;
+    let chunk = lua.load(source).set_name("@main.luau").into_function()?;
+
+    // Continue
+    assert_eq!(chunk.call::<i64>(())?, 6);
+    assert_eq!(*stops.lock().unwrap(), vec![(BreakReason::Breakpoint, 6)]);
+
+    // Step in, step out and step over
+    stops.lock().unwrap().clear();
+    let steps = [
+        DebugAction::StepIn,
+        DebugAction::StepOver,
+        DebugAction::StepOut,
+        DebugAction::StepOver,
+    ];
+    actions.lock().unwrap().extend(steps);
+    assert_eq!(chunk.call::<i64>(())?, 6);
+    let expected = vec![
+        (BreakReason::Breakpoint, 6),
+        (BreakReason::Step, 3),
+        (BreakReason::Step, 4),
+        (BreakReason::Step, 7),
+        (BreakReason::Step, 8),
+    ];
+    assert_eq!(*stops.lock().unwrap(), expected);
+
+    // Stepping into and out of a function of another chunk
+    let lib = lua
+        .load("\n\n\nlocal function double(n)\n    return n * 2\nend\nreturn double")
+        .set_name("@lib.luau")
+        .eval::<Function>()?;
+    let main = lua
+        .load("local double = ...\nlocal x = double(tonumber('2'))\nreturn x + 1")
+        .set_name("@main2.luau")
+        .into_function()?;
+    debugger.set_breakpoint("@main2.luau", 2)?;
+    stops.lock().unwrap().clear();
+    actions
+        .lock()
+        .unwrap()
+        .extend([DebugAction::StepIn, DebugAction::StepOut]);
+    assert_eq!(main.call::<i64>(lib)?, 5);
+    let expected = vec![
+        (BreakReason::Breakpoint, 2),
+        (BreakReason::Step, 5),
+        (BreakReason::Step, 3),
+    ];
+    assert_eq!(*stops.lock().unwrap(), expected);
+    debugger.clear_breakpoint("@main2.luau", 2)?;
+
+    // Breakpoints are applied to chunks loaded before setting them
+    stops.lock().unwrap().clear();
+    debugger.set_breakpoint("@main.luau", 4)?;
+    assert_eq!(chunk.call::<i64>(())?, 6);
+    let expected = vec![
+        (BreakReason::Breakpoint, 6),
+        (BreakReason::Breakpoint, 4),
+        (BreakReason::Breakpoint, 4),
+    ];
+    assert_eq!(*stops.lock().unwrap(), expected);
+
+    // Errors from the callback abort execution
+    debugger.on_break(|_, _, _| Err(Error::runtime("stopped")));
+    match chunk.call::<i64>(()) {
+        Err(Error::RuntimeError(msg)) => assert_eq!(msg, "stopped"),
+        r => panic!("expected RuntimeError, got {r:?}"),
+    }
+
+    assert!(debugger.clear_breakpoint("@main.luau", 6)?);
+    assert!(!debugger.clear_breakpoint("@main.luau", 6)?);
+    debugger.detach()?;
+    assert!(!debugger.is_attached());
+    assert!(debugger.breakpoints().is_empty());
+    assert_eq!(chunk.call::<i64>(())?, 6);
+
+    Ok(())
+}
+
+#[cfg(feature = "dap")]
+#[test]
+fn test_dap_server() -> Result<()> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::sync::mpsc;
+
+    use serde_json::{json, Value as JsonValue};
+    use ulua::DapServer;
+
+    // Reader fed by the client
+    struct Pipe(mpsc::Receiver<Vec<u8>>, VecDeque<u8>);
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.1.is_empty() {
+                match self.0.recv() {
+                    Ok(data) => self.1.extend(data),
+                    Err(_) => return Ok(0),
+                }
+            }
+            self.1.read(buf)
+        }
+    }
+
+    // Writer read by the client
+    struct Output(mpsc::Sender<Vec<u8>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.0.send(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Minimal DAP client
+    struct Client {
+        reader: BufReader<Pipe>,
+        writer: mpsc::Sender<Vec<u8>>,
+        seq: i64,
+    }
+
+    impl Client {
+        fn receive(&mut self) -> JsonValue {
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                self.reader.read_line(&mut line).unwrap();
+                match line.trim().strip_prefix("Content-Length: ") {
+                    Some(value) => length = value.parse().unwrap(),
+                    None if line.trim().is_empty() => break,
+                    None => {}
+                }
+            }
+            let mut body = vec![0; length];
+            self.reader.read_exact(&mut body).unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        fn request(&mut self, command: &str, arguments: JsonValue) -> JsonValue {
+            self.seq += 1;
+            let message =
+                json!({"seq": self.seq, "type": "request", "command": command, "arguments": arguments});
+            let body = message.to_string();
+            let data = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+            self.writer.send(data.into_bytes()).unwrap();
+            loop {
+                let message = self.receive();
+                if message["type"] == "response" && message["command"] == command {
+                    assert_eq!(message["success"], true, "{message}");
+                    return message["body"].clone();
+                }
+            }
+        }
+
+        fn wait_event(&mut self, event: &str) -> JsonValue {
+            loop {
+                let message = self.receive();
+                if message["type"] == "event" && message["event"] == event {
+                    return message["body"].clone();
+                }
+            }
+        }
+
+        fn wait_stopped(&mut self) -> JsonValue {
+            self.wait_event("stopped")["reason"].clone()
+        }
+    }
+
+    let (to_server, server_input) = mpsc::channel();
+    let (server_output, from_server) = mpsc::channel();
+
+    let lua = Lua::new();
+    let session = DapServer::new(&lua)
+        .with_source_root("/scripts")
+        .connect(Pipe(server_input, VecDeque::new()), Output(server_output));
+
+    let client = std::thread::spawn(move || {
+        let mut client = Client {
+            reader: BufReader::new(Pipe(from_server, VecDeque::new())),
+            writer: to_server,
+            seq: 0,
+        };
+
+        client.request("initialize", json!({"adapterID": "ulua"}));
+        let body = client.request(
+            "setBreakpoints",
+            json!({"source": {"path": "/scripts/main.luau"}, "breakpoints": [{"line": 3}]}),
+        );
+        // Breakpoints are verified when the chunk is loaded, and moved to lines with code
+        let breakpoint = &body["breakpoints"][0];
+        assert_eq!(breakpoint["verified"], false);
+        client.request("configurationDone", json!({}));
+        let body = client.wait_event("breakpoint");
+        assert_eq!(body["breakpoint"]["id"], breakpoint["id"]);
+        assert_eq!(body["breakpoint"]["verified"], true);
+        assert_eq!(body["breakpoint"]["line"], 4);
+        assert_eq!(client.wait_stopped(), "breakpoint");
+
+        let body = client.request("stackTrace", json!({"threadId": 1}));
+        let frame = &body["stackFrames"][0];
+        assert_eq!(frame["line"], 4);
+        assert_eq!(frame["source"]["path"], "/scripts/main.luau");
+        let frame_id = frame["id"].clone();
+
+        let body = client.request("scopes", json!({"frameId": frame_id}));
+        let locals = body["scopes"][0]["variablesReference"].clone();
+        let body = client.request("variables", json!({"variablesReference": locals}));
+        let variables = body["variables"].as_array().unwrap();
+        assert!(variables
+            .iter()
+            .any(|var| var["name"] == "a" && var["value"] == "1"));
+
+        let body = client.request("evaluate", json!({"expression": "a + 1", "frameId": frame_id}));
+        assert_eq!(body["result"], "2");
+
+        client.request("next", json!({"threadId": 1}));
+        assert_eq!(client.wait_stopped(), "step");
+        let body = client.request("stackTrace", json!({"threadId": 1}));
+        assert_eq!(body["stackFrames"][0]["line"], 5);
+
+        client.request("continue", json!({"threadId": 1}));
+        client.request("disconnect", json!({}));
+    });
+
+    assert!(session.wait_for_configuration());
+    let source = "local a = tonumber(\"1\")\nlocal b = a * 2\n\nlocal c = a + b\nreturn c * 2";
+    let result: i64 = lua.load(source).set_name("@main.luau").eval()?;
+    assert_eq!(result, 6);
+    client.join().unwrap();
+
+    // Oversized messages close the connection
+    let (to_server, server_input) = mpsc::channel();
+    let (server_output, _from_server) = mpsc::channel();
+    let session =
+        DapServer::new(&Lua::new()).connect(Pipe(server_input, VecDeque::new()), Output(server_output));
+    to_server
+        .send(b"Content-Length: 1000000000000\r\n\r\n".to_vec())
+        .unwrap();
+    assert!(!session.wait_for_configuration());
+
+    Ok(())
+}
+
 #[path = "luau/require.rs"]
 mod require;
@@ -1,7 +1,11 @@
 use std::io::Result as IoResult;
+use std::path::Path;
 use std::result::Result as StdResult;
 
-use ulua::{Error, IntoLua, Lua, MultiValue, NavigateError, Require, Result, TextRequirer, Value};
+use ulua::{
+    ArchiveRequirer, Compiler, Error, HostModules, IntoLua, Lua, LuauRc, MemoryRequirer, ModuleArchive,
+    MultiValue, NavigateError, Require, RequireRequest, Result, Table, TextRequirer, Value,
+};
 
 fn run_require(lua: &Lua, path: impl IntoLua) -> Result<Value> {
     lua.load(r#"return require(...)"#).call(path)
@@ -207,6 +211,64 @@ fn test_require_with_config() {
     assert!((res.unwrap_err().to_string()).contains("@ is not a valid alias"));
 }
 
+#[test]
+fn test_require_with_inherited_config() -> Result<()> {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    let write = |path: &Path, contents: &str| {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    };
+
+    let parent = LuauRc::new()
+        .add_alias("Shared", "./shared")
+        .add_alias("lib", "./parent_lib");
+    let child = LuauRc::new()
+        .add_alias("LIB", "./child_lib")
+        .add_alias("local", "./local");
+    write(&root.join(".luaurc"), &parent.to_string());
+    write(&root.join("project/.luaurc"), &child.to_string());
+    write(&root.join("project/main.luau"), "");
+    // Every alias path exists next to both configuration files
+    for dir in ["", "project/"] {
+        for name in ["shared", "parent_lib", "child_lib", "local"] {
+            let module = format!("{dir}{name}");
+            write(
+                &root.join(&module).join("init.luau"),
+                &format!("return '{module}'"),
+            );
+        }
+    }
+
+    let lua = Lua::new();
+    lua.globals()
+        .set("require", lua.create_require_function(TextRequirer::new())?)?;
+
+    // Aliases of the merged configuration must resolve to the same modules as `require` finds by
+    // searching the configuration files upwards
+    let mut merged = child.clone();
+    merged.inherit(&parent);
+    assert_eq!(merged.aliases().count(), 3);
+    for (name, path) in merged.aliases() {
+        let dir = if child.alias(name).is_some() {
+            "project/"
+        } else {
+            ""
+        };
+        let module = lua
+            .load(format!("return require('@{name}')"))
+            .set_name(format!("@{}/project/main", root.display()))
+            .eval::<String>()?;
+        assert_eq!(
+            module,
+            format!("{dir}{}", path.trim_start_matches("./")),
+            "alias {name}"
+        );
+    }
+
+    Ok(())
+}
+
 #[cfg(all(feature = "async", not(windows)))]
 #[tokio::test]
 async fn test_async_require() -> Result<()> {
@@ -246,3 +308,569 @@ async fn test_async_require() -> Result<()> {
     .exec_async()
     .await
 }
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_require_function() -> Result<()> {
+    use std::result::Result as StdResult;
+    use std::time::Duration;
+
+    use ulua::{AsyncRequire, Function};
+
+    // Simulates a remote storage by delaying every operation
+    struct DelayedRequirer(MemoryRequirer);
+
+    async fn delay() {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    impl AsyncRequire for DelayedRequirer {
+        fn is_require_allowed(&self, chunk_name: &str) -> bool {
+            self.0.is_require_allowed(chunk_name)
+        }
+
+        async fn reset(&mut self, chunk_name: &str) -> StdResult<(), NavigateError> {
+            delay().await;
+            self.0.reset(chunk_name)
+        }
+
+        async fn jump_to_alias(&mut self, path: &str) -> StdResult<(), NavigateError> {
+            delay().await;
+            self.0.jump_to_alias(path)
+        }
+
+        async fn to_parent(&mut self) -> StdResult<(), NavigateError> {
+            delay().await;
+            self.0.to_parent()
+        }
+
+        async fn to_child(&mut self, name: &str) -> StdResult<(), NavigateError> {
+            delay().await;
+            self.0.to_child(name)
+        }
+
+        fn has_module(&self) -> bool {
+            self.0.has_module()
+        }
+
+        fn cache_key(&self) -> String {
+            self.0.cache_key()
+        }
+
+        async fn has_config(&mut self) -> bool {
+            delay().await;
+            self.0.has_config()
+        }
+
+        async fn config(&mut self) -> std::io::Result<Vec<u8>> {
+            delay().await;
+            self.0.config()
+        }
+
+        async fn loader(&mut self, lua: &Lua) -> Result<Function> {
+            delay().await;
+            self.0.loader(lua)
+        }
+    }
+
+    let requirer = MemoryRequirer::from_iter([
+        ("game/.luaurc", r#"{ "aliases": { "config": "./config" } }"#),
+        ("game/config.luau", "return { speed = 10 }"),
+        (
+            "game/player.luau",
+            "return { speed = require('@config').speed * 2 }",
+        ),
+        (
+            "game/slow.luau",
+            "sleep(); return { player = require('./player') }",
+        ),
+    ]);
+
+    let lua = Lua::new();
+    let require = lua.create_async_require_function(DelayedRequirer(requirer))?;
+    lua.globals().set("require", require)?;
+    lua.globals().set(
+        "sleep",
+        lua.create_async_function(|_, ()| async move {
+            delay().await;
+            Ok(())
+        })?,
+    )?;
+
+    let speed: i32 = (lua.load("return require('./game/slow').player.speed"))
+        .eval_async()
+        .await?;
+    assert_eq!(speed, 20);
+
+    // Cached result
+    let (slow, slow2): (Value, Value) = (lua.load("return require('./game/slow'), require('./game/slow')"))
+        .eval_async()
+        .await?;
+    assert_eq!(slow, slow2);
+
+    // Errors
+    let res = lua.load("require('./game/missing')").exec_async().await;
+    assert!((res.unwrap_err().to_string()).contains("could not resolve child component \"missing\""));
+
+    // Async require cannot be called outside of a coroutine
+    let res = lua.load("require('./game/config')").exec();
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_require_from_archive() -> Result<()> {
+    let archive = ModuleArchive::from_dir("tests/luau/require", &Compiler::new())?;
+    assert!(archive.get("without_config/nested/init.luau").is_some());
+    assert!(archive.get("with_config/src/.luaurc").is_some());
+
+    // Roundtrip through a file
+    let temp_dir = tempfile::tempdir().unwrap();
+    let archive_path = temp_dir.path().join("modules.luaua");
+    archive.save(&archive_path).unwrap();
+    let archive = ModuleArchive::open(&archive_path).unwrap();
+
+    let lua = Lua::new();
+    let requirer = ArchiveRequirer::new(archive);
+    let prefix = format!("{}/", requirer.name());
+    assert!(prefix.starts_with("archive-"));
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+
+    let res = run_require(&lua, "./without_config/dependency")?;
+    assert_eq!("result from dependency", get_str(&res, 1));
+    let modules = lua.loaded_modules()?;
+    assert_eq!(
+        modules[0].cache_key,
+        format!("{prefix}without_config/dependency.luau")
+    );
+
+    // Relative to requiring module
+    let res = run_require(&lua, "./without_config/module")?;
+    assert_eq!("result from dependency", get_str(&res, 1));
+    assert_eq!("required into module", get_str(&res, 2));
+
+    // Init files
+    let res = run_require(&lua, "./without_config/lua")?;
+    assert_eq!("result from init.lua", get_str(&res, 1));
+    let res = run_require(&lua, "./without_config/nested")?;
+    assert_eq!("result from submodule", get_str(&res, 1));
+
+    // Aliases
+    let res = run_require(&lua, "./with_config/src/alias_requirer")?;
+    assert_eq!("result from dependency", get_str(&res, 1));
+    let res = run_require(&lua, "./with_config/src/parent_alias_requirer")?;
+    assert_eq!("result from other_dependency", get_str(&res, 1));
+    let res = run_require(&lua, "./with_config/src/directory_alias_requirer")?;
+    assert_eq!("result from subdirectory_dependency", get_str(&res, 1));
+
+    // Cached result
+    let res = run_require(&lua, "./without_config/validate_cache")?;
+    assert!(res.is_table());
+
+    // Errors
+    let res = run_require(&lua, "./without_config/ambiguous_file_requirer");
+    assert!((res.unwrap_err().to_string())
+        .contains("could not resolve child component \"dependency\" (ambiguous)"));
+    let res = run_require(&lua, "./without_config/missing");
+    assert!((res.unwrap_err().to_string()).contains("could not resolve child component \"missing\""));
+
+    Ok(())
+}
+
+#[test]
+fn test_require_from_memory() -> Result<()> {
+    let requirer = MemoryRequirer::from_iter([
+        ("game/.luaurc", r#"{ "aliases": { "config": "./config" } }"#),
+        ("game/config.luau", "return { speed = 10 }"),
+        (
+            "game/player/init.luau",
+            "return { speed = require('@self/stats').speed }",
+        ),
+        (
+            "game/player/stats.luau",
+            "return { speed = require('@config').speed * 2 }",
+        ),
+        (
+            "game/world.luau",
+            "return { player = require('./player'), id = {} }",
+        ),
+        (
+            "shared/util.lua",
+            "return { name = 'util', parent = require('../game/config') }",
+        ),
+        ("ambiguous.lua", "return 1"),
+        ("ambiguous.luau", "return 2"),
+    ])
+    .with_module("/./shared//other.luau", "return 'other'")
+    .with_config("shared", r#"{ "aliases": { "game": "../game" } }"#);
+    assert!(requirer.paths().any(|path| path == "shared/other.luau"));
+    assert!(requirer.paths().any(|path| path == "shared/.luaurc"));
+
+    let lua = Lua::new();
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+
+    let world = run_require(&lua, "./game/world")?;
+    let speed = lua
+        .load("return require('./game/world').player.speed")
+        .eval::<i32>()?;
+    assert_eq!(speed, 20);
+
+    // Cached result
+    let world2 = run_require(&lua, "./game/world")?;
+    assert_eq!(world, world2);
+
+    let util = run_require(&lua, "./shared/util")?;
+    assert_eq!(get_str(&util, "name"), "util");
+    assert_eq!(
+        run_require(&lua, "./shared/other")?,
+        Value::String(lua.create_string("other")?)
+    );
+
+    // Errors
+    let res = run_require(&lua, "./ambiguous");
+    assert!((res.unwrap_err().to_string())
+        .contains("could not resolve child component \"ambiguous\" (ambiguous)"));
+    let res = run_require(&lua, "./game/missing");
+    assert!((res.unwrap_err().to_string()).contains("could not resolve child component \"missing\""));
+
+    Ok(())
+}
+
+#[test]
+fn test_require_hot_reload() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+    use ulua::{HotReloader, Table};
+
+    fn write_module(dir: &std::path::Path, name: &str, source: &str, age: u64) {
+        let path = dir.join(name);
+        std::fs::write(&path, source).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    write_module(dir, "config.luau", "return { speed = 1 }", 10);
+    write_module(
+        dir,
+        "player.luau",
+        "return { speed = require('./config').speed * 10 }",
+        10,
+    );
+    write_module(dir, "other.luau", "return {}", 10);
+
+    let lua = Lua::new();
+    let reloader = HotReloader::new(TextRequirer::new());
+    lua.globals()
+        .set("require", lua.create_require_function(reloader.clone())?)?;
+
+    let main = "require('./other') return require('./player')";
+    write_module(dir, "main.luau", main, 10);
+    let player = (lua.load(main))
+        .set_name(format!("@{}/main", dir.display()))
+        .into_function()?;
+    assert_eq!(player.call::<Table>(())?.get::<i32>("speed")?, 10);
+
+    // Nothing changed
+    assert!(reloader.poll(&lua)?.is_empty());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    reloader.on_reload(move |_, event| {
+        // Keep state of the old module
+        if let (Some(old), Some(new)) = (event.old.as_table(), event.new.as_table()) {
+            new.set("state", old.get::<Value>("state")?)?;
+        }
+        let name = event.chunk_name.rsplit('/').next().unwrap().to_string();
+        events2.lock().unwrap().push((name, event.is_dependent));
+        Ok(())
+    });
+    player.call::<Table>(())?.set("state", "saved")?;
+
+    write_module(dir, "config.luau", "return { speed = 2 }", 0);
+    let reloaded = reloader.poll(&lua)?;
+    assert_eq!(reloaded.len(), 2);
+    assert!(reloaded[0].ends_with("config.luau") && reloaded[1].ends_with("player.luau"));
+    assert_eq!(
+        *events.lock().unwrap(),
+        [("config".to_string(), false), ("player".to_string(), true)]
+    );
+    let player_module = player.call::<Table>(())?;
+    assert_eq!(player_module.get::<i32>("speed")?, 20);
+    assert_eq!(player_module.get::<String>("state")?, "saved");
+
+    // Explicit reload
+    assert_eq!(reloader.reload(&lua, &reloaded[1])?, [reloaded[1].clone()]);
+    assert!(reloader.reload(&lua, "unknown").is_err());
+
+    // Errors in a reloaded module are reported, the previous version is kept
+    write_module(dir, "player.luau", "error('broken')", 0);
+    assert!((reloader.poll(&lua).unwrap_err().to_string()).contains("broken"));
+    assert_eq!(player.call::<Table>(())?.get::<i32>("speed")?, 20);
+
+    // The failed module is retried until it loads
+    assert!((reloader.poll(&lua).unwrap_err().to_string()).contains("broken"));
+    write_module(dir, "player.luau", "return { speed = 30 }", 0);
+    assert_eq!(reloader.poll(&lua)?, [reloaded[1].clone()]);
+    assert_eq!(player.call::<Table>(())?.get::<i32>("speed")?, 30);
+    assert!(reloader.poll(&lua)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_module_cache() -> Result<()> {
+    use std::time::SystemTime;
+
+    let lua = Lua::new();
+    let requirer = MemoryRequirer::new()
+        .with_name("plugins")
+        .with_module("counter.luau", "loads += 1 return { loads = loads }")
+        .with_module("plugin/init.luau", "return { counter = require('./counter') }");
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+    lua.globals().set("loads", 0)?;
+
+    let started = SystemTime::now();
+    let plugin = run_require(&lua, "./plugin")?;
+    let modules = lua.loaded_modules()?;
+    assert_eq!(modules.len(), 2);
+    let (counter_key, plugin_key) = (modules[0].cache_key.clone(), modules[1].cache_key.clone());
+    assert_eq!(counter_key, "plugins/counter.luau");
+    assert_eq!(plugin_key, "plugins/plugin/init.luau");
+    assert!(modules.iter().all(|module| module.path.is_none()));
+    assert!((modules.iter()).all(|module| module.loaded_at.is_some_and(|time| time >= started)));
+
+    assert_eq!(lua.cached_module(&plugin_key)?, Some(plugin.clone()));
+    assert_eq!(lua.cached_module("unknown")?, None);
+
+    // Evicted modules are loaded again
+    assert!(lua.evict_module(&counter_key)?);
+    assert!(!lua.evict_module(&counter_key)?);
+    assert_eq!(lua.loaded_modules()?.len(), 1);
+    let counter = run_require(&lua, "./counter")?;
+    assert_eq!(counter.as_table().unwrap().get::<i32>("loads")?, 2);
+    assert_eq!(run_require(&lua, "./plugin")?, plugin);
+
+    lua.clear_module_cache()?;
+    assert!(lua.loaded_modules()?.is_empty());
+    let plugin = run_require(&lua, "./plugin")?;
+    assert_eq!(lua.load("return loads").eval::<i32>()?, 3);
+    assert_eq!(lua.loaded_modules()?.len(), 2);
+    assert_eq!(lua.cached_module(&plugin_key)?, Some(plugin));
+
+    // Paths of modules loaded from files
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    std::fs::write(dir.join("main.luau"), "").unwrap();
+    std::fs::write(dir.join("module.luau"), "return {}").unwrap();
+    lua.globals()
+        .set("require", lua.create_require_function(TextRequirer::new())?)?;
+    lua.load("require('./module')")
+        .set_name(format!("@{}/main", dir.display()))
+        .exec()?;
+    let module = (lua.loaded_modules()?.into_iter())
+        .find(|module| module.cache_key.ends_with("module.luau"))
+        .unwrap();
+    assert_eq!(module.path, Some(dir.join("module.luau")));
+
+    Ok(())
+}
+
+#[test]
+fn test_module_graph() -> Result<()> {
+    let lua = Lua::new();
+    let requirer = MemoryRequirer::new()
+        .with_module("a.luau", "return { b = require('./b') }")
+        .with_module("b.luau", "return { c = require('./c') }")
+        .with_module("c.luau", "return { a = require('./a') }")
+        .with_module("main.luau", "require('./utils') return require('./utils')")
+        .with_module("utils.luau", "return {}");
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+
+    run_require(&lua, "./main")?;
+    let graph = lua.module_graph();
+    let root = lua.loaded_modules()?[0]
+        .cache_key
+        .rsplit_once('/')
+        .unwrap()
+        .0
+        .to_string();
+    let key = |name: &str| format!("{root}/{name}.luau");
+    assert_eq!(graph.dependencies("@main").collect::<Vec<_>>(), [key("utils")]);
+    assert_eq!(graph.dependents(&key("utils")).collect::<Vec<_>>(), ["@main"]);
+    assert_eq!(graph.len(), 2);
+
+    // Require cycles are reported with the chain of modules
+    let err = run_require(&lua, "./a").unwrap_err();
+    match err {
+        Error::CallbackError { ref cause, .. } => match cause.as_ref() {
+            Error::RequireCycle { chain } => {
+                assert_eq!(*chain, [key("a"), key("b"), key("c"), key("a")]);
+            }
+            err => panic!("expected RequireCycle, got {err:?}"),
+        },
+        err => panic!("expected CallbackError, got {err:?}"),
+    }
+    assert!(err.to_string().contains("require cycle detected: "));
+    assert_eq!(lua.module_graph().len(), 6);
+    assert_eq!(
+        lua.module_graph().dependencies("@c").collect::<Vec<_>>(),
+        [key("a")]
+    );
+
+    // Loading state is not kept after errors
+    let err = run_require(&lua, "./a").unwrap_err();
+    assert!(err.to_string().contains("require cycle detected: "));
+
+    lua.clear_module_cache()?;
+    assert!(lua.module_graph().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_require_policy() -> Result<()> {
+    let lua = Lua::new();
+    let requirer = MemoryRequirer::new()
+        .with_module("shared/log.luau", "return { name = 'log' }")
+        .with_module("plugins/a/init.luau", "return { log = require('@shared/log') }")
+        .with_module("plugins/a/state.luau", "return {}")
+        .with_module("plugins/a/broken.luau", "return {")
+        .with_module("plugins/b/init.luau", "return { state = require('./a/state') }")
+        .with_config("", r#"{ "aliases": { "shared": "./shared" } }"#);
+
+    // Plugins may require shared modules and their own modules, but not each other's internals
+    let plugin = |name: &str| {
+        let rest = name.split_once("plugins/")?.1;
+        rest.split(['/', '.']).next().map(|plugin| plugin.to_string())
+    };
+    let policy = move |request: &RequireRequest| match (plugin(request.chunk_name), plugin(request.cache_key))
+    {
+        (Some(from), Some(to)) if from != to => Err(format!("plugin '{from}' cannot access plugin '{to}'")),
+        _ => Ok(()),
+    };
+    lua.globals().set(
+        "require",
+        lua.create_require_function_with_policy(requirer, policy)?,
+    )?;
+
+    let a = run_require(&lua, "./plugins/a")?;
+    assert_eq!(
+        a.as_table().unwrap().get::<Table>("log")?.get::<String>("name")?,
+        "log"
+    );
+
+    let err = run_require(&lua, "./plugins/b").unwrap_err().to_string();
+    assert!(
+        err.contains("plugins/b:1: require './a/state' is not allowed from '@plugins/b': plugin 'b' cannot access plugin 'a'"),
+        "{err}"
+    );
+
+    // The policy is checked before the module is loaded
+    let err = lua
+        .load("require('./a/broken')")
+        .set_name("@plugins/b")
+        .exec()
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("plugin 'b' cannot access plugin 'a'"), "{err}");
+
+    // The policy is checked for cached modules too
+    run_require(&lua, "./plugins/a/state")?;
+    let (ok, err): (bool, String) = lua
+        .load("return pcall(require, './a/state')")
+        .set_name("@plugins/b")
+        .call(())?;
+    assert!(!ok);
+    assert!(err.contains("plugin 'b' cannot access plugin 'a'"));
+
+    // A module that fails to load does not skip the check for the next require
+    let lua = Lua::new();
+    let requirer = MemoryRequirer::new().with_module("broken.luau", "return {");
+    let requirer = HostModules::new(requirer).with_module("secret", "SECRET");
+    let policy = |request: &RequireRequest| match request.path {
+        "@host/secret" => Err("access denied".to_string()),
+        _ => Ok(()),
+    };
+    lua.globals().set(
+        "require",
+        lua.create_require_function_with_policy(requirer, policy)?,
+    )?;
+    let (ok, err): (bool, String) = lua
+        .load("local ok, err = pcall(require, './broken') return ok, tostring(err)")
+        .call(())?;
+    assert!(!ok);
+    assert!(!err.contains("access denied"), "{err}");
+    let err = run_require(&lua, "@host/secret").unwrap_err().to_string();
+    assert!(err.contains("access denied"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_require_host_modules() -> Result<()> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    let lua = Lua::new();
+    let db_opened = Arc::new(AtomicU32::new(0));
+    let requirer = MemoryRequirer::new()
+        .with_module(
+            "main.luau",
+            "return { net = require('@host/net'), db = require('@HOST/Db') }",
+        )
+        .with_module("local.luau", "return 'local'");
+    let requirer = HostModules::new(requirer)
+        .with_module("net", "net module")
+        .with_lazy_module("db", {
+            let db_opened = db_opened.clone();
+            move |lua| {
+                db_opened.fetch_add(1, Ordering::Relaxed);
+                lua.create_table_from([("name", "db")])
+            }
+        })
+        .with_lazy_module("broken", |_| Err::<Value, _>(Error::runtime("cannot open")));
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+
+    assert_eq!(db_opened.load(Ordering::Relaxed), 0);
+    let main = run_require(&lua, "./main")?;
+    let main = main.as_table().unwrap();
+    assert_eq!(main.get::<String>("net")?, "net module");
+    assert_eq!(main.get::<Table>("db")?.get::<String>("name")?, "db");
+    assert_eq!(db_opened.load(Ordering::Relaxed), 1);
+
+    // Host modules are cached
+    assert_eq!(run_require(&lua, "@host/db")?, main.get::<Value>("db")?);
+    assert_eq!(db_opened.load(Ordering::Relaxed), 1);
+    assert!(lua.cached_module("@host/db")?.is_some());
+
+    // Other paths are resolved by the wrapped requirer
+    assert_eq!(run_require(&lua, "./local")?, "local".into_lua(&lua)?);
+
+    // Errors are propagated
+    let err = run_require(&lua, "@host/broken").unwrap_err().to_string();
+    assert!(err.contains("cannot open"), "{err}");
+    let err = run_require(&lua, "@host/unknown").unwrap_err().to_string();
+    assert!(
+        err.contains("module '@host/unknown' is not provided by the host"),
+        "{err}"
+    );
+
+    // Custom alias
+    let requirer = HostModules::new(MemoryRequirer::new())
+        .with_alias("@rust")
+        .with_module("answer", 42);
+    lua.globals()
+        .set("require", lua.create_require_function(requirer)?)?;
+    assert_eq!(run_require(&lua, "@rust/answer")?, Value::Integer(42));
+
+    Ok(())
+}
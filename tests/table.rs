@@ -102,7 +102,7 @@ fn test_table_push_pop() -> Result<()> {
     assert_eq!(table2.len()?, 2);
     assert_eq!(
         table2.sequence_values::<i64>().collect::<Result<Vec<_>>>()?,
-        vec![]
+        Vec::<i64>::new()
     );
     assert_eq!(table2.pop::<i64>()?, 345);
     assert_eq!(table2.pop::<i64>()?, 234);
@@ -3,12 +3,13 @@ use std::collections::HashMap;
 use std::iter::FromIterator;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::string::String as StdString;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::{error, f32, f64, fmt};
 
 use ulua::{
-    ffi, ChunkMode, Error, ExternalError, Function, Lua, LuaOptions, Nil, Result, StdLib, String, Table,
-    UserData, Value, Variadic,
+    ffi, ChunkMode, Compiler, Error, ExternalError, Function, Lua, LuaOptions, Nil, Result, StdLib, String,
+    Table, UserData, Value, Variadic,
 };
 
 #[test]
@@ -1125,6 +1126,50 @@ fn test_register_module() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_register_lazy_module() -> Result<()> {
+    let lua = Lua::new();
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls2 = calls.clone();
+    lua.register_lazy_module("@My_DB", move |lua| {
+        if calls2.fetch_add(1, Ordering::Relaxed) == 0 {
+            return Err(Error::runtime("database is not ready"));
+        }
+        lua.create_table_from([("name", "db")])
+    })?;
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+    // Errors are propagated to the requiring script and the module is constructed again
+    lua.load(
+        r#"
+        local ok, err = pcall(require, "@my_db")
+        assert(not ok and string.find(tostring(err), "database is not ready"))
+        local db = require("@MY_DB")
+        assert(db.name == "db")
+        assert(require("@my_db") == db)
+    "#,
+    )
+    .exec()?;
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+    // Lazy modules are replaced by registered modules and vice versa
+    lua.register_lazy_module("@answer", |_| Ok(42))?;
+    lua.register_module("@answer", 1)?;
+    assert_eq!(lua.load("return require('@answer')").eval::<i32>()?, 1);
+    lua.register_lazy_module("@answer", |_| Ok(42))?;
+    assert_eq!(lua.load("return require('@answer')").eval::<i32>()?, 42);
+
+    lua.register_lazy_module("@unloaded", |_| Ok(true))?;
+    lua.unload_module("@unloaded")?;
+    assert!(lua.load("return require('@unloaded')").exec().is_err());
+
+    let res = lua.register_lazy_module("my_module", |_| Ok(123));
+    assert!(res.is_err());
+
+    Ok(())
+}
+
 #[test]
 fn test_inspect_stack() -> Result<()> {
     let lua = Lua::new();
@@ -1202,6 +1247,48 @@ fn test_inspect_stack() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_inspect_locals() -> Result<()> {
+    let lua = Lua::new();
+    lua.set_compiler(Compiler::new().set_debug_level(2));
+
+    let locals = lua.create_function(|lua, ()| {
+        lua.inspect_stack(1, |debug| {
+            let arguments = debug.arguments();
+            assert_eq!(
+                arguments,
+                [
+                    ("a".to_string(), Value::Integer(1)),
+                    ("b".to_string(), Value::Boolean(true))
+                ]
+            );
+            let locals = debug.locals();
+            assert_eq!(locals[..2], arguments[..]);
+            let c = ("c".to_string(), Value::String(lua.create_string("local")?));
+            assert!(locals.contains(&c), "{locals:?}");
+            assert!(debug.set_local("c", "changed")?);
+            assert!(!debug.set_local("missing", 0)?);
+            Ok(())
+        })
+        .unwrap()
+    })?;
+    lua.globals().set("locals", locals)?;
+
+    lua.load(
+        r#"
+        local function foo(a, b, ...)
+            local c = tostring("local")
+            locals()
+            assert(c == "changed")
+        end
+        foo(1, true, "extra")
+    "#,
+    )
+    .exec()?;
+
+    Ok(())
+}
+
 #[test]
 fn test_multi_states() -> Result<()> {
     let lua = Lua::new();
@@ -1,20 +1,21 @@
-use proc_macro::{TokenStream, TokenTree};
+use proc_macro::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error2::abort;
 
-use crate::token::{Pos, Token, Tokens};
+use crate::token::{Pos, Source, Tokens};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Capture {
-    key: Token,
+    key: String,
     rust: TokenTree,
 }
 
 impl Capture {
-    fn new(key: Token, rust: TokenTree) -> Self {
+    fn new(key: String, rust: TokenTree) -> Self {
         Self { key, rust }
     }
 
-    /// Token string inside `chunk!`
-    pub(crate) fn key(&self) -> &Token {
+    /// Variable name inside `chunk!`
+    pub(crate) fn key(&self) -> &str {
         &self.key
     }
 
@@ -32,14 +33,11 @@ impl Captures {
         Self(Vec::new())
     }
 
-    pub(crate) fn add(&mut self, token: &Token) -> Capture {
-        let tt = token.tree();
-        let key = token.clone();
-
-        match self.0.iter().find(|arg| arg.key() == &key) {
+    pub(crate) fn add(&mut self, key: &str, rust: &TokenTree) -> Capture {
+        match self.0.iter().find(|arg| arg.key() == key) {
             Some(arg) => arg.clone(),
             None => {
-                let arg = Capture::new(key, tt.clone());
+                let arg = Capture::new(key.to_string(), rust.clone());
                 self.0.push(arg.clone());
                 arg
             }
@@ -51,23 +49,115 @@ impl Captures {
     }
 }
 
+/// Chunk options set using inner attributes (`#![...]`) at the beginning of the chunk.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkAttributes {
+    /// Embed compiled bytecode instead of source code
+    pub(crate) bytecode: bool,
+}
+
+impl ChunkAttributes {
+    fn parse(tokens: TokenStream) -> (Self, TokenStream) {
+        let mut attrs = Self::default();
+        let mut tokens = tokens.into_iter().collect::<Vec<_>>();
+        let mut start = 0;
+        while let [TokenTree::Punct(hash), TokenTree::Punct(bang), TokenTree::Group(group), ..] =
+            &tokens[start..]
+        {
+            if hash.as_char() != '#' || bang.as_char() != '!' || group.delimiter() != Delimiter::Bracket {
+                break;
+            }
+            match group.stream().to_string().as_str() {
+                "bytecode" => attrs.bytecode = true,
+                _ => abort!(
+                    proc_macro2::Span::from(group.span()),
+                    "unsupported chunk attribute"
+                ),
+            }
+            start += 3;
+        }
+        (attrs, tokens.drain(start..).collect())
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Chunk {
     source: String,
     caps: Captures,
+    attrs: ChunkAttributes,
+    /// Span of the first token on each line of the source
+    lines: Vec<Span>,
 }
 
 impl Chunk {
     pub(crate) fn new(tokens: TokenStream) -> Self {
+        let (attrs, tokens) = ChunkAttributes::parse(tokens);
         let tokens = Tokens::retokenize(tokens);
 
+        match Self::invocation_source(&tokens) {
+            Some(source) => Self::from_source(source, tokens, attrs),
+            None => Self::from_tokens(tokens, attrs),
+        }
+    }
+
+    /// Returns the original text of the chunk, if it's available and contains all the tokens.
+    ///
+    /// When the macro is called from another macro, the call site text is the text of that macro
+    /// (e.g. a `macro_rules!` transcriber with `$($t)*`) and does not contain the tokens.
+    fn invocation_source(tokens: &Tokens) -> Option<Source> {
+        let source = Source::from_invocation(&Span::call_site().source_text()?)?;
+
+        // Every token must be found in the text in order, starting with the first one. Tokens
+        // after `//` are missing (the rest of the line is a Rust comment), but not the other way.
+        let mut texts = (tokens.0.iter())
+            .map(|t| t.to_string())
+            .filter(|text| !text.is_empty());
+        let first = texts.next()?;
+        let mut rest = source.code.trim_start().strip_prefix(&first)?;
+        for text in texts {
+            rest = &rest[rest.find(&text)? + text.len()..];
+        }
+        Some(source)
+    }
+
+    fn from_source(source: Source, tokens: Tokens, attrs: ChunkAttributes) -> Self {
+        // Map source lines to the first token on the same line
+        let first_line = proc_macro2::Span::from(Span::call_site()).start().line + source.line_offset;
+        let lines = (0..source.code.lines().count())
+            .map(|i| {
+                let token = tokens.0.iter().find(|t| t.start().line == first_line + i);
+                token.map_or_else(Span::call_site, |t| t.span())
+            })
+            .collect();
+
+        let mut caps = Captures::new();
+        for name in &source.captures {
+            let token = tokens.0.iter().find(|t| t.is_cap() && t.to_string() == *name);
+            // Variables inside Rust comments (e.g. after `//` operator) are not tokenized
+            let rust = token.map_or_else(
+                || TokenTree::Ident(Ident::new(name, Span::call_site())),
+                |t| t.tree().clone(),
+            );
+            caps.add(name, &rust);
+        }
+
+        Self {
+            source: source.code,
+            caps,
+            attrs,
+            lines,
+        }
+    }
+
+    fn from_tokens(tokens: Tokens, attrs: ChunkAttributes) -> Self {
         let mut source = String::new();
         let mut caps = Captures::new();
+        let mut lines = Vec::new();
 
         let mut pos: Option<Pos> = None;
         for t in tokens {
             if t.is_cap() {
-                caps.add(&t);
+                caps.add(&t.to_string(), t.tree());
             }
 
             let (line, col) = (t.start().line, t.start().column);
@@ -76,9 +166,13 @@ impl Chunk {
                 .map(|lc| (lc.line, lc.column))
                 .unwrap_or_else(|| (line, col));
 
+            if lines.is_empty() {
+                lines.push(t.span());
+            }
             #[allow(clippy::comparison_chain)]
             if line > prev_line {
                 source.push('\n');
+                lines.push(t.span());
             } else if line == prev_line {
                 for _ in 0..col.saturating_sub(prev_col) {
                     source.push(' ');
@@ -92,9 +186,32 @@ impl Chunk {
         Self {
             source: source.trim_end().to_string(),
             caps,
+            attrs,
+            lines,
         }
     }
 
+    /// Compiles the chunk source using the Luau compiler.
+    ///
+    /// Syntax errors are reported as compile errors pointing at the line with the error.
+    pub(crate) fn compile(&self) -> Vec<u8> {
+        match compile(self.source.as_bytes(), Default::default()) {
+            Ok(bytecode) => bytecode,
+            Err(error) => {
+                let (line, message) = (error.strip_prefix(':'))
+                    .and_then(|error| error.split_once(": "))
+                    .and_then(|(line, message)| Some((line.parse::<usize>().ok()?, message)))
+                    .unwrap_or((1, &error));
+                let span = (self.lines.get(line.saturating_sub(1)).copied()).unwrap_or_else(Span::call_site);
+                abort!(proc_macro2::Span::from(span), "Luau syntax error: {}", message);
+            }
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> &ChunkAttributes {
+        &self.attrs
+    }
+
     pub(crate) fn source(&self) -> &str {
         &self.source
     }
@@ -103,3 +220,15 @@ impl Chunk {
         self.caps.captures()
     }
 }
+
+/// Compiles Luau source code to bytecode.
+///
+/// Returns the compiler error message in format `:line: message` on failure.
+pub(crate) fn compile(source: &[u8], options: ffi::lua_CompileOptions) -> Result<Vec<u8>, String> {
+    let bytecode = unsafe { ffi::luau_compile(source, options) };
+    match bytecode.first() {
+        // The rest of the bytecode is the error message
+        Some(0) => Err(String::from_utf8_lossy(&bytecode[1..]).into_owned()),
+        _ => Ok(bytecode),
+    }
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::path::PathBuf;
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Error, Ident, LitInt, LitStr, Result, Token};
+
+use crate::chunk::compile;
+
+/// Arguments of the `include_luau!` macro.
+pub(crate) struct IncludeLuau {
+    path: LitStr,
+    options: ffi::lua_CompileOptions,
+}
+
+impl Parse for IncludeLuau {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.parse::<LitStr>()?;
+        let mut options = ffi::lua_CompileOptions::default();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            let value = input.parse::<LitInt>()?;
+            let level = match value.base10_parse::<u8>()? {
+                level @ 0..=2 => level as _,
+                _ => return Err(Error::new(value.span(), "level must be in range 0..=2")),
+            };
+            match &*name.to_string() {
+                "optimize" => options.optimizationLevel = level,
+                "debug" => options.debugLevel = level,
+                "type_info" => options.typeInfoLevel = level,
+                "coverage" => options.coverageLevel = level,
+                _ => return Err(Error::new(name.span(), "unsupported compiler option")),
+            }
+        }
+        Ok(IncludeLuau { path, options })
+    }
+}
+
+impl IncludeLuau {
+    pub(crate) fn expand(self) -> Result<TokenStream> {
+        let path = self.path.value();
+        // Paths are relative to the crate root, as proc macros cannot locate the calling file
+        let root = env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let full_path = root.join(&path);
+        let source = std::fs::read(&full_path).map_err(|err| {
+            let message = format!("couldn't read `{}`: {err}", full_path.display());
+            Error::new(self.path.span(), message)
+        })?;
+        let bytecode = compile(&source, self.options)
+            .map_err(|error| Error::new(self.path.span(), format!("Luau syntax error in {path}{error}")))?;
+
+        let name = format!("@{path}");
+        let full_path = full_path.to_string_lossy();
+        let bytecode = Literal::byte_string(&bytecode);
+        Ok(quote! {{
+            use ulua::{AsChunk, ChunkMode};
+            use ::std::borrow::Cow;
+            use ::std::io::Result as IoResult;
+
+            // Rebuild when the file is changed
+            const _: &[u8] = include_bytes!(#full_path);
+
+            struct InnerChunk;
+
+            impl AsChunk for InnerChunk {
+                fn name(&self) -> Option<String> {
+                    Some(#name.to_string())
+                }
+
+                fn mode(&self) -> Option<ChunkMode> {
+                    Some(ChunkMode::Binary)
+                }
+
+                fn is_trusted(&self) -> bool {
+                    true
+                }
+
+                fn source<'a>(&self) -> IoResult<Cow<'a, [u8]>> {
+                    Ok(Cow::Borrowed(#bytecode))
+                }
+            }
+
+            InnerChunk
+        }})
+    }
+}
@@ -6,8 +6,8 @@ use syn::{parse_macro_input, ItemFn, LitStr, Result};
 
 #[cfg(feature = "macros")]
 use {
-    crate::chunk::Chunk, proc_macro::TokenTree, proc_macro2::TokenStream as TokenStream2,
-    proc_macro_error2::proc_macro_error,
+    crate::chunk::Chunk, proc_macro::TokenTree, proc_macro2::Literal,
+    proc_macro2::TokenStream as TokenStream2, proc_macro_error2::proc_macro_error,
 };
 
 #[derive(Default)]
@@ -86,7 +86,15 @@ fn to_ident(tt: &TokenTree) -> TokenStream2 {
 pub fn chunk(input: TokenStream) -> TokenStream {
     let chunk = Chunk::new(input);
 
-    let source = chunk.source();
+    // Validate the chunk syntax at compile time
+    let bytecode = chunk.compile();
+    let (mode, source) = if chunk.attributes().bytecode {
+        let bytecode = Literal::byte_string(&bytecode);
+        (quote! { ChunkMode::Binary }, quote! { #bytecode as &[u8] })
+    } else {
+        let source = chunk.source();
+        (quote! { ChunkMode::Text }, quote! { (#source).as_bytes() })
+    };
 
     let caps_len = chunk.captures().len();
     let caps = chunk.captures().iter().map(|cap| {
@@ -117,11 +125,15 @@ pub fn chunk(input: TokenStream) -> TokenStream {
             }
 
             fn mode(&self) -> Option<ChunkMode> {
-                Some(ChunkMode::Text)
+                Some(#mode)
+            }
+
+            fn is_trusted(&self) -> bool {
+                true
             }
 
             fn source<'a>(&self) -> IoResult<Cow<'a, [u8]>> {
-                Ok(Cow::Borrowed((#source).as_bytes()))
+                Ok(Cow::Borrowed(#source))
             }
         }
 
@@ -145,6 +157,16 @@ pub fn chunk(input: TokenStream) -> TokenStream {
     wrapped_code.into()
 }
 
+#[cfg(feature = "macros")]
+#[proc_macro]
+pub fn include_luau(input: TokenStream) -> TokenStream {
+    let include = parse_macro_input!(input as include::IncludeLuau);
+    match include.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 #[cfg(feature = "macros")]
 #[proc_macro_derive(FromLua)]
 pub fn from_lua(input: TokenStream) -> TokenStream {
@@ -156,4 +178,6 @@ mod chunk;
 #[cfg(feature = "macros")]
 mod from_lua;
 #[cfg(feature = "macros")]
+mod include;
+#[cfg(feature = "macros")]
 mod token;
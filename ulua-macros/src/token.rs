@@ -92,6 +92,7 @@ enum TokenAttr {
 pub(crate) struct Token {
     source: String,
     tree: TokenTree,
+    span: Span,
     start: Pos,
     end: Pos,
     attr: TokenAttr,
@@ -110,6 +111,7 @@ impl Token {
         let (start, end) = span_pos(&tree.span());
         Self {
             source: tree.to_string(),
+            span: tree.span(),
             start,
             end,
             tree,
@@ -124,10 +126,16 @@ impl Token {
         } else {
             (end.left(), end)
         };
+        let span = match &tree {
+            TokenTree::Group(g) if open => g.span_open(),
+            TokenTree::Group(g) => g.span_close(),
+            _ => tree.span(),
+        };
 
         Self {
             source,
             tree,
+            span,
             start,
             end,
             attr: TokenAttr::None,
@@ -138,6 +146,10 @@ impl Token {
         &self.tree
     }
 
+    pub(crate) fn span(&self) -> Span {
+        self.span
+    }
+
     pub(crate) fn is_cap(&self) -> bool {
         self.attr == TokenAttr::Cap
     }
@@ -223,3 +235,170 @@ impl Display for Token {
         write!(f, "{}", self.source)
     }
 }
+
+/// Luau source code reconstructed from the original text of a `chunk!` invocation.
+///
+/// Unlike tokens, the original text keeps comments, escape sequences, line breaks and the `//`
+/// operator intact.
+#[derive(Debug)]
+pub(crate) struct Source {
+    /// Source code with `$` removed from captured variables
+    pub(crate) code: String,
+    /// Names of captured variables, in order of appearance
+    pub(crate) captures: Vec<String>,
+    /// Number of lines before the chunk body in the invocation text
+    pub(crate) line_offset: usize,
+}
+
+impl Source {
+    /// Extracts the chunk body from the macro invocation text (e.g. `chunk! { ... }`).
+    pub(crate) fn from_invocation(text: &str) -> Option<Self> {
+        let (path, rest) = text.split_once('!')?;
+        if !path.trim_end().ends_with("chunk") {
+            return None;
+        }
+        let body = rest.trim_start();
+        let close = match body.as_bytes().first()? {
+            b'{' => '}',
+            b'(' => ')',
+            b'[' => ']',
+            _ => return None,
+        };
+        let body = body[1..].strip_suffix(close)?;
+        let body_start = body.as_ptr() as usize - text.as_ptr() as usize;
+        let line_offset = text[..body_start].matches('\n').count();
+
+        let mut scanner = Scanner {
+            src: body,
+            pos: 0,
+            code: String::with_capacity(body.len()),
+            captures: Vec::new(),
+        };
+        scanner.skip_attributes();
+        scanner.code();
+        let code = scanner.code.trim_end().to_string();
+        Some(Source {
+            code,
+            captures: scanner.captures,
+            line_offset,
+        })
+    }
+}
+
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+    code: String,
+    captures: Vec<String>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self, n: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + n).copied()
+    }
+
+    fn copy(&mut self, len: usize) {
+        let end = (self.pos + len).min(self.src.len());
+        self.code.push_str(&self.src[self.pos..end]);
+        self.pos = end;
+    }
+
+    fn copy_char(&mut self) {
+        let len = self.src[self.pos..].chars().next().map_or(1, char::len_utf8);
+        self.copy(len);
+    }
+
+    // Replaces inner attributes (`#![...]`) with whitespace, keeping line breaks
+    fn skip_attributes(&mut self) {
+        loop {
+            let len = self.src[self.pos..].len() - self.src[self.pos..].trim_start().len();
+            self.copy(len);
+            if !self.src[self.pos..].starts_with("#!") {
+                return;
+            }
+            let end = self.src[self.pos..]
+                .find(']')
+                .map_or(self.src.len(), |i| self.pos + i + 1);
+            let attr = &self.src[self.pos..end];
+            self.code
+                .extend(attr.chars().map(|c| if c == '\n' { c } else { ' ' }));
+            self.pos = end;
+        }
+    }
+
+    // Copies code until the end of source
+    fn code(&mut self) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'-' if self.peek(1) == Some(b'-') => {
+                    self.copy(2);
+                    match self.long_bracket() {
+                        Some(level) => self.long_string(level),
+                        None => {
+                            let len = self.src[self.pos..]
+                                .find('\n')
+                                .unwrap_or(self.src.len() - self.pos);
+                            self.copy(len);
+                        }
+                    }
+                }
+                b'[' if self.long_bracket().is_some() => {
+                    let level = self.long_bracket().unwrap();
+                    self.long_string(level);
+                }
+                b'"' | b'\'' => self.quoted_string(c),
+                b'$' if self.peek(1).is_some_and(|c| c.is_ascii_alphabetic() || c == b'_') => {
+                    self.pos += 1;
+                    let rest = &self.src[self.pos..];
+                    let len = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    let name = rest[..len].to_string();
+                    self.copy(len);
+                    if !self.captures.contains(&name) {
+                        self.captures.push(name);
+                    }
+                }
+                _ => self.copy_char(),
+            }
+        }
+    }
+
+    // Returns the level of a long bracket (`[[` or `[=*[`) at the current position
+    fn long_bracket(&self) -> Option<usize> {
+        if self.peek(0) != Some(b'[') {
+            return None;
+        }
+        let level = self.src.as_bytes()[self.pos + 1..]
+            .iter()
+            .take_while(|&&c| c == b'=')
+            .count();
+        (self.peek(level + 1) == Some(b'[')).then_some(level)
+    }
+
+    fn long_string(&mut self, level: usize) {
+        let close = format!("]{}]", "=".repeat(level));
+        let start = self.pos + level + 2;
+        let len = self.src[start..]
+            .find(&close)
+            .map_or(self.src.len() - self.pos, |i| start + i + close.len() - self.pos);
+        self.copy(len);
+    }
+
+    fn quoted_string(&mut self, quote: u8) {
+        self.copy(1);
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'\n' => return,
+                b'\\' => {
+                    self.copy(1);
+                    if self.peek(0).is_some() {
+                        self.copy_char();
+                    }
+                }
+                c if c == quote => return self.copy(1),
+                _ => self.copy_char(),
+            }
+        }
+    }
+}
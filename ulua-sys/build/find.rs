@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
 pub fn probe_lua() {
     let artifacts = luau0_src::Build::new()
         .enable_codegen(true)
@@ -7,5 +11,70 @@ pub fn probe_lua() {
         .set_vector_size(if cfg!(feature = "vector4") { 4 } else { 3 })
         .build();
 
+    // The AST shim must be linked before the Luau libraries it depends on
+    build_ast_shim(&artifacts);
     artifacts.print_cargo_metadata();
 }
+
+// Compiles the C shim that exposes the Luau parser (which has only a C++ interface)
+fn build_ast_shim(artifacts: &luau0_src::Artifacts) {
+    println!("cargo:rerun-if-changed=src/luau/ast.cpp");
+
+    let version = artifacts.version().expect("unknown Luau version");
+    let source_dir = find_luau_source(&version).unwrap_or_else(|| {
+        panic!("cannot find luau0-src sources for Luau {version} (required to build the AST shim)")
+    });
+    let luau_dir = source_dir.join("luau");
+
+    let mut config = cc::Build::new();
+    config
+        .warnings(false)
+        .cpp(true)
+        .std("c++17")
+        .cpp_link_stdlib(None)
+        .include(luau_dir.join("Common").join("include"))
+        .include(luau_dir.join("Ast").join("include"))
+        .file("src/luau/ast.cpp");
+    if cfg!(debug_assertions) {
+        config.define("LUAU_ENABLE_ASSERT", None);
+    }
+    config.compile("uluaast");
+}
+
+// Looks for the luau0-src package that matches the Luau version being built.
+//
+// luau0-src does not expose its include directories, so check the directory where the registry
+// (or `cargo vendor`) unpacks packages next to this one, and the local registry cache.
+fn find_luau_source(version: &str) -> Option<PathBuf> {
+    // `0.697` -> `+luau697`
+    let suffix = format!("+luau{}", version.strip_prefix("0.")?);
+
+    let mut search_dirs = Vec::new();
+    if let Some(dir) = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from) {
+        search_dirs.extend(dir.parent().map(Path::to_path_buf));
+    }
+    let cargo_home = (env::var_os("CARGO_HOME").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+    if let Some(registry) = cargo_home.map(|home| home.join("registry").join("src")) {
+        for entry in fs::read_dir(registry).into_iter().flatten().flatten() {
+            search_dirs.push(entry.path());
+        }
+    }
+
+    for dir in search_dirs {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let is_luau_src = (path.file_name().and_then(|name| name.to_str()))
+                .is_some_and(|name| name.starts_with("luau0-src"));
+            if is_luau_src && path.join("luau/Ast/include/Luau/Parser.h").exists() {
+                let manifest = fs::read_to_string(path.join("Cargo.toml")).unwrap_or_default();
+                let matches = (manifest.lines())
+                    .any(|line| line.starts_with("version") && line.contains(&format!("{suffix}\"")));
+                if matches {
+                    return Some(path);
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,476 @@
+// Flattens Luau syntax trees into plain arrays that can be read through the C ABI.
+//
+// Nodes are stored in pre-order, so every subtree occupies a contiguous range of the array.
+// Strings (names, constants, operators and error messages) are appended to a single text buffer
+// and referenced by offset.
+
+#include "Luau/Ast.h"
+#include "Luau/Parser.h"
+
+#include <memory>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <string>
+#include <vector>
+
+using namespace Luau;
+
+extern "C" {
+
+struct ulua_AstSpan
+{
+    uint32_t begin_line, begin_column, end_line, end_column;
+};
+
+struct ulua_AstNode
+{
+    int32_t kind;
+    size_t parent;
+    size_t end;
+    ulua_AstSpan span;
+    size_t text_offset;
+    size_t text_len;
+    int32_t has_text;
+};
+
+struct ulua_AstComment
+{
+    int32_t kind;
+    ulua_AstSpan span;
+};
+
+struct ulua_AstError
+{
+    ulua_AstSpan span;
+    size_t message_offset;
+    size_t message_len;
+};
+
+struct ulua_AstResult
+{
+    const ulua_AstNode* nodes;
+    size_t nodes_len;
+    const ulua_AstComment* comments;
+    size_t comments_len;
+    const ulua_AstError* errors;
+    size_t errors_len;
+    const char* text;
+    size_t text_len;
+};
+
+} // extern "C"
+
+// Must be kept in sync with `NodeKind` in `src/ast.rs`
+enum Kind : int32_t
+{
+    Unknown,
+    StatBlock,
+    StatIf,
+    StatWhile,
+    StatRepeat,
+    StatBreak,
+    StatContinue,
+    StatReturn,
+    StatExpr,
+    StatLocal,
+    StatFor,
+    StatForIn,
+    StatAssign,
+    StatCompoundAssign,
+    StatFunction,
+    StatLocalFunction,
+    StatTypeAlias,
+    StatTypeFunction,
+    StatDeclareGlobal,
+    StatDeclareFunction,
+    StatDeclareExternType,
+    StatError,
+    ExprGroup,
+    ExprConstantNil,
+    ExprConstantBool,
+    ExprConstantNumber,
+    ExprConstantString,
+    ExprLocal,
+    ExprGlobal,
+    ExprVarargs,
+    ExprCall,
+    ExprIndexName,
+    ExprIndexExpr,
+    ExprFunction,
+    ExprTable,
+    ExprUnary,
+    ExprBinary,
+    ExprTypeAssertion,
+    ExprIfElse,
+    ExprInterpString,
+    ExprError,
+    TypeReference,
+    TypeTable,
+    TypeFunction,
+    TypeTypeof,
+    TypeOptional,
+    TypeUnion,
+    TypeIntersection,
+    TypeSingletonBool,
+    TypeSingletonString,
+    TypeGroup,
+    TypeError,
+    TypePackExplicit,
+    TypePackVariadic,
+    TypePackGeneric,
+    Attribute,
+    GenericType,
+    GenericTypePack,
+    Local,
+};
+
+#define ULUA_AST_KIND(Class) \
+    if (node->is<Ast##Class>()) \
+        return Class;
+
+static Kind kindOf(AstNode* node)
+{
+    ULUA_AST_KIND(StatBlock)
+    ULUA_AST_KIND(StatIf)
+    ULUA_AST_KIND(StatWhile)
+    ULUA_AST_KIND(StatRepeat)
+    ULUA_AST_KIND(StatBreak)
+    ULUA_AST_KIND(StatContinue)
+    ULUA_AST_KIND(StatReturn)
+    ULUA_AST_KIND(StatExpr)
+    ULUA_AST_KIND(StatLocal)
+    ULUA_AST_KIND(StatFor)
+    ULUA_AST_KIND(StatForIn)
+    ULUA_AST_KIND(StatAssign)
+    ULUA_AST_KIND(StatCompoundAssign)
+    ULUA_AST_KIND(StatFunction)
+    ULUA_AST_KIND(StatLocalFunction)
+    ULUA_AST_KIND(StatTypeAlias)
+    ULUA_AST_KIND(StatTypeFunction)
+    ULUA_AST_KIND(StatDeclareGlobal)
+    ULUA_AST_KIND(StatDeclareFunction)
+    ULUA_AST_KIND(StatDeclareExternType)
+    ULUA_AST_KIND(StatError)
+    ULUA_AST_KIND(ExprGroup)
+    ULUA_AST_KIND(ExprConstantNil)
+    ULUA_AST_KIND(ExprConstantBool)
+    ULUA_AST_KIND(ExprConstantNumber)
+    ULUA_AST_KIND(ExprConstantString)
+    ULUA_AST_KIND(ExprLocal)
+    ULUA_AST_KIND(ExprGlobal)
+    ULUA_AST_KIND(ExprVarargs)
+    ULUA_AST_KIND(ExprCall)
+    ULUA_AST_KIND(ExprIndexName)
+    ULUA_AST_KIND(ExprIndexExpr)
+    ULUA_AST_KIND(ExprFunction)
+    ULUA_AST_KIND(ExprTable)
+    ULUA_AST_KIND(ExprUnary)
+    ULUA_AST_KIND(ExprBinary)
+    ULUA_AST_KIND(ExprTypeAssertion)
+    ULUA_AST_KIND(ExprIfElse)
+    ULUA_AST_KIND(ExprInterpString)
+    ULUA_AST_KIND(ExprError)
+    ULUA_AST_KIND(TypeReference)
+    ULUA_AST_KIND(TypeTable)
+    ULUA_AST_KIND(TypeFunction)
+    ULUA_AST_KIND(TypeTypeof)
+    ULUA_AST_KIND(TypeOptional)
+    ULUA_AST_KIND(TypeUnion)
+    ULUA_AST_KIND(TypeIntersection)
+    ULUA_AST_KIND(TypeSingletonBool)
+    ULUA_AST_KIND(TypeSingletonString)
+    ULUA_AST_KIND(TypeGroup)
+    ULUA_AST_KIND(TypeError)
+    ULUA_AST_KIND(TypePackExplicit)
+    ULUA_AST_KIND(TypePackVariadic)
+    ULUA_AST_KIND(TypePackGeneric)
+    ULUA_AST_KIND(GenericType)
+    ULUA_AST_KIND(GenericTypePack)
+    if (node->is<AstAttr>())
+        return Attribute;
+    return Unknown;
+}
+
+#undef ULUA_AST_KIND
+
+struct Result : ulua_AstResult
+{
+    std::vector<ulua_AstNode> nodeList;
+    std::vector<ulua_AstComment> commentList;
+    std::vector<ulua_AstError> errorList;
+    std::string textBuffer;
+};
+
+static ulua_AstSpan toSpan(const Location& location)
+{
+    return {location.begin.line, location.begin.column, location.end.line, location.end.column};
+}
+
+struct Flattener : AstVisitor
+{
+    Result& result;
+    size_t parent = SIZE_MAX;
+    // Node whose children are being visited by its own `visit` method
+    AstNode* entered = nullptr;
+
+    explicit Flattener(Result& result)
+        : result(result)
+    {
+    }
+
+    size_t push(Kind kind, const Location& location, const char* text, size_t len)
+    {
+        ulua_AstNode node = {kind, parent, 0, toSpan(location), result.textBuffer.size(), len, text != nullptr};
+        if (text)
+            result.textBuffer.append(text, len);
+        result.nodeList.push_back(node);
+        return result.nodeList.size() - 1;
+    }
+
+    size_t push(Kind kind, const Location& location, const char* text)
+    {
+        return push(kind, location, text, strlen(text));
+    }
+
+    size_t push(Kind kind, const Location& location, const std::string& text)
+    {
+        return push(kind, location, text.data(), text.size());
+    }
+
+    size_t push(Kind kind, const Location& location, const AstName& name)
+    {
+        return push(kind, location, name.value, name.value ? strlen(name.value) : 0);
+    }
+
+    size_t push(Kind kind, const Location& location, const AstArray<char>& value)
+    {
+        return push(kind, location, value.data ? value.data : "", value.size);
+    }
+
+    size_t pushNode(AstNode* node)
+    {
+        Kind kind = kindOf(node);
+        const Location& location = node->location;
+
+        if (auto expr = node->as<AstExprConstantBool>())
+            return push(kind, location, expr->value ? "true" : "false");
+        if (auto expr = node->as<AstExprConstantNumber>())
+        {
+            char buffer[32];
+            snprintf(buffer, sizeof(buffer), "%.17g", expr->value);
+            return push(kind, location, std::string(buffer));
+        }
+        if (auto expr = node->as<AstExprConstantString>())
+            return push(kind, location, expr->value);
+        if (auto expr = node->as<AstExprLocal>())
+            return push(kind, location, expr->local->name);
+        if (auto expr = node->as<AstExprGlobal>())
+            return push(kind, location, expr->name);
+        if (auto expr = node->as<AstExprIndexName>())
+            return push(kind, location, expr->index);
+        if (auto expr = node->as<AstExprUnary>())
+            return push(kind, location, toString(expr->op));
+        if (auto expr = node->as<AstExprBinary>())
+            return push(kind, location, toString(expr->op));
+        if (auto expr = node->as<AstExprFunction>(); expr && expr->debugname.value)
+            return push(kind, location, expr->debugname);
+        if (auto stat = node->as<AstStatCompoundAssign>())
+            return push(kind, location, toString(stat->op));
+        if (auto stat = node->as<AstStatTypeAlias>())
+            return push(kind, location, stat->name);
+        if (auto stat = node->as<AstStatTypeFunction>())
+            return push(kind, location, stat->name);
+        if (auto stat = node->as<AstStatDeclareGlobal>())
+            return push(kind, location, stat->name);
+        if (auto stat = node->as<AstStatDeclareFunction>())
+            return push(kind, location, stat->name);
+        if (auto stat = node->as<AstStatDeclareExternType>())
+            return push(kind, location, stat->name);
+        if (auto type = node->as<AstTypeReference>())
+        {
+            std::string name = type->prefix ? std::string(type->prefix->value) + "." : "";
+            return push(kind, location, name + type->name.value);
+        }
+        if (auto type = node->as<AstTypeSingletonBool>())
+            return push(kind, location, type->value ? "true" : "false");
+        if (auto type = node->as<AstTypeSingletonString>())
+            return push(kind, location, type->value);
+        if (auto type = node->as<AstTypePackGeneric>())
+            return push(kind, location, type->genericName);
+        if (auto generic = node->as<AstGenericType>())
+            return push(kind, location, generic->name);
+        if (auto generic = node->as<AstGenericTypePack>())
+            return push(kind, location, generic->name);
+        if (auto attr = node->as<AstAttr>())
+        {
+            switch (attr->type)
+            {
+            case AstAttr::Checked:
+                return push(kind, location, "checked");
+            case AstAttr::Native:
+                return push(kind, location, "native");
+            case AstAttr::Deprecated:
+                return push(kind, location, "deprecated");
+            default:
+                return push(kind, location, attr->name);
+            }
+        }
+
+        return push(kind, location, nullptr, 0);
+    }
+
+    void visitChild(AstNode* node)
+    {
+        if (node)
+            visit(node);
+    }
+
+    // Local variable declarations are not nodes in Luau, so they are recorded together with their
+    // type annotations
+    void visitLocal(AstLocal* local)
+    {
+        if (!local)
+            return;
+        size_t index = push(Local, local->location, local->name);
+        size_t saved = parent;
+        parent = index;
+        visitChild(local->annotation);
+        parent = saved;
+        result.nodeList[index].end = result.nodeList.size();
+    }
+
+    // Visits children of nodes that declare locals, in source order
+    bool visitDeclarations(AstNode* node)
+    {
+        if (auto stat = node->as<AstStatLocal>())
+        {
+            for (AstLocal* var : stat->vars)
+                visitLocal(var);
+            for (AstExpr* expr : stat->values)
+                visitChild(expr);
+        }
+        else if (auto stat = node->as<AstStatFor>())
+        {
+            visitLocal(stat->var);
+            visitChild(stat->from);
+            visitChild(stat->to);
+            visitChild(stat->step);
+            visitChild(stat->body);
+        }
+        else if (auto stat = node->as<AstStatForIn>())
+        {
+            for (AstLocal* var : stat->vars)
+                visitLocal(var);
+            for (AstExpr* expr : stat->values)
+                visitChild(expr);
+            visitChild(stat->body);
+        }
+        else if (auto stat = node->as<AstStatLocalFunction>())
+        {
+            visitLocal(stat->name);
+            visitChild(stat->func);
+        }
+        else if (auto expr = node->as<AstExprFunction>())
+        {
+            for (AstAttr* attr : expr->attributes)
+                visitChild(attr);
+            for (AstGenericType* generic : expr->generics)
+                visitChild(generic);
+            for (AstGenericTypePack* generic : expr->genericPacks)
+                visitChild(generic);
+            visitLocal(expr->self);
+            for (AstLocal* arg : expr->args)
+                visitLocal(arg);
+            visitChild(expr->varargAnnotation);
+            visitChild(expr->returnAnnotation);
+            visitChild(expr->body);
+        }
+        else
+        {
+            return false;
+        }
+        return true;
+    }
+
+    bool visit(AstNode* node) override
+    {
+        // Let the node visit its children
+        if (node == entered)
+        {
+            entered = nullptr;
+            return true;
+        }
+
+        size_t index = pushNode(node);
+        size_t saved = parent;
+        parent = index;
+        if (!visitDeclarations(node))
+        {
+            entered = node;
+            node->visit(this);
+            entered = nullptr;
+        }
+        parent = saved;
+        result.nodeList[index].end = result.nodeList.size();
+        return false;
+    }
+
+    // Type annotations are not visited by default
+    bool visit(AstType* node) override
+    {
+        return visit(static_cast<AstNode*>(node));
+    }
+
+    bool visit(AstTypePack* node) override
+    {
+        return visit(static_cast<AstNode*>(node));
+    }
+};
+
+extern "C" ulua_AstResult* ulua_ast_parse(const char* source, size_t size)
+{
+    try
+    {
+        Allocator allocator;
+        AstNameTable names(allocator);
+        ParseOptions options;
+        options.captureComments = true;
+        ParseResult parsed = Parser::parse(source, size, names, allocator, options);
+
+        std::unique_ptr<Result> result(new Result());
+        Flattener flattener(*result);
+        for (const ParseError& error : parsed.errors)
+        {
+            const std::string& message = error.getMessage();
+            result->errorList.push_back({toSpan(error.getLocation()), result->textBuffer.size(), message.size()});
+            result->textBuffer += message;
+        }
+        if (parsed.errors.empty() && parsed.root)
+            flattener.visit(static_cast<AstNode*>(parsed.root));
+        for (const Comment& comment : parsed.commentLocations)
+        {
+            int32_t kind = comment.type == Lexeme::Comment ? 0 : comment.type == Lexeme::BlockComment ? 1 : 2;
+            result->commentList.push_back({kind, toSpan(comment.location)});
+        }
+
+        result->nodes = result->nodeList.data();
+        result->nodes_len = result->nodeList.size();
+        result->comments = result->commentList.data();
+        result->comments_len = result->commentList.size();
+        result->errors = result->errorList.data();
+        result->errors_len = result->errorList.size();
+        result->text = result->textBuffer.data();
+        result->text_len = result->textBuffer.size();
+        return result.release();
+    }
+    catch (...)
+    {
+        return nullptr;
+    }
+}
+
+extern "C" void ulua_ast_free(ulua_AstResult* result)
+{
+    delete static_cast<Result*>(result);
+}
@@ -0,0 +1,60 @@
+//! Contains definitions of the Luau parser shim (`ast.cpp`).
+
+use std::os::raw::c_char;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ulua_AstSpan {
+    pub begin_line: u32,
+    pub begin_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+// Nodes are stored in pre-order, `end` is the index after the last node of the subtree.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ulua_AstNode {
+    pub kind: i32,
+    // `usize::MAX` for the root node
+    pub parent: usize,
+    pub end: usize,
+    pub span: ulua_AstSpan,
+    pub text_offset: usize,
+    pub text_len: usize,
+    pub has_text: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ulua_AstComment {
+    // 0: line comment, 1: block comment, 2: unterminated block comment
+    pub kind: i32,
+    pub span: ulua_AstSpan,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ulua_AstError {
+    pub span: ulua_AstSpan,
+    pub message_offset: usize,
+    pub message_len: usize,
+}
+
+#[repr(C)]
+pub struct ulua_AstResult {
+    pub nodes: *const ulua_AstNode,
+    pub nodes_len: usize,
+    pub comments: *const ulua_AstComment,
+    pub comments_len: usize,
+    pub errors: *const ulua_AstError,
+    pub errors_len: usize,
+    pub text: *const c_char,
+    pub text_len: usize,
+}
+
+unsafe extern "C" {
+    // Returns null on allocation failure
+    pub fn ulua_ast_parse(source: *const c_char, size: usize) -> *mut ulua_AstResult;
+    pub fn ulua_ast_free(result: *mut ulua_AstResult);
+}
@@ -3,6 +3,7 @@
 pub use compat::*;
 pub use lauxlib::*;
 pub use lua::*;
+pub use luaast::*;
 pub use luacode::*;
 pub use luacodegen::*;
 pub use lualib::*;
@@ -11,6 +12,7 @@ pub use luarequire::*;
 pub mod compat;
 pub mod lauxlib;
 pub mod lua;
+pub mod luaast;
 pub mod luacode;
 pub mod luacodegen;
 pub mod lualib;